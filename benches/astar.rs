@@ -0,0 +1,63 @@
+//! A* 搜索吞吐量基准：`cargo bench --features ai --bench astar`
+//!
+//! 先用 AI 自己对局采集一批局面（蛇身长短不一），再对每个局面重复搜索，
+//! 输出每秒展开的状态数。工作量由种子固定，便于对比不同实现。
+
+use snake::config::MapConfig;
+use snake::game::Game;
+use snake::pathfinding::{self, AiConfig};
+use snake::snake::SnakeGame;
+use snake::types::GameState;
+use std::time::{Duration, Instant};
+
+/// 采集局面的种子数、每局步数与采样间隔
+const SEEDS: u64 = 3;
+const TICKS: u64 = 3000;
+const SAMPLE_EVERY: u64 = 25;
+/// 每个局面重复搜索的次数
+const ROUNDS: u32 = 3;
+
+fn positions() -> Vec<SnakeGame> {
+    let mut out = Vec::new();
+    for seed in 0..SEEDS {
+        let mut game = Game::with_seed(MapConfig::new(16, 16), 3, 1, seed);
+        for tick in 0..TICKS {
+            if tick % SAMPLE_EVERY == 0 {
+                out.push(game.snake().clone());
+            }
+            let state = game.tick(pathfinding::next_dir(game.snake()));
+            if matches!(state, GameState::Over | GameState::Won) {
+                break;
+            }
+        }
+    }
+    out
+}
+
+fn main() {
+    let positions = positions();
+    let ai = AiConfig::default();
+    let mut expanded = 0u64;
+    let mut searches = 0u64;
+    let start = Instant::now();
+    for _ in 0..ROUNDS {
+        for snake in &positions {
+            if let Some(plan) = pathfinding::plan(snake, &ai) {
+                expanded += plan.expanded as u64;
+                searches += 1;
+            }
+        }
+    }
+    let elapsed = start.elapsed().max(Duration::from_nanos(1));
+    let mean_len = positions.iter().map(SnakeGame::length).sum::<usize>() as f64 / positions.len() as f64;
+    println!(
+        "{} positions (mean length {mean_len:.1}), {searches} searches, {expanded} expanded in {:.3} s",
+        positions.len(),
+        elapsed.as_secs_f64()
+    );
+    println!(
+        "{:.0} expanded/s, {:.1} µs/search",
+        expanded as f64 / elapsed.as_secs_f64(),
+        elapsed.as_secs_f64() * 1e6 / searches as f64
+    );
+}
@@ -0,0 +1,34 @@
+#!/usr/bin/env python3
+"""示例 bot：朝最近的食物贪心移动，避开墙与蛇身。
+
+用法: snake bot python3 bots/greedy.py
+协议见 src/bot.rs：每行读入一个 JSON 局面，回复一行"序号 方向"。
+"""
+import json
+import sys
+
+MOVES = {"up": (0, -1), "down": (0, 1), "left": (-1, 0), "right": (1, 0)}
+
+
+def choose(state):
+    body = [tuple(c) for c in state["body"]]
+    hx, hy = body[-1]
+    occupied = set(body)
+    foods = state["food"] or [[hx, hy]]
+    tx, ty = min(foods, key=lambda f: abs(f[0] - hx) + abs(f[1] - hy))
+    best = None
+    for name, (dx, dy) in MOVES.items():
+        x, y = hx + dx, hy + dy
+        if not (0 <= x < state["width"] and 0 <= y < state["height"]):
+            continue
+        if (x, y) in occupied:
+            continue
+        score = abs(x - tx) + abs(y - ty)
+        if best is None or score < best[0]:
+            best = (score, name)
+    return best[1] if best else "null"
+
+
+for line in sys.stdin:
+    state = json.loads(line)
+    print(state["seq"], choose(state), flush=True)
@@ -0,0 +1,53 @@
+//! 用任意字节驱动 `SnakeGame`，每次 `update` / `revert` 之后检查全部不变式
+//!
+//! 前 12 字节依次决定宽、高、初始长度、食物数与 8 字节 RNG 种子，之后每个字节是一个 tick：
+//! 低 3 位选择方向（4–7 为无输入），第 3 位置位时先执行并撤销一次，再正式执行。
+//!
+//! `cargo fuzz run engine`
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rand::SeedableRng;
+use snake::config::MapConfig;
+use snake::rng::GameRng;
+use snake::snake::SnakeGame;
+use snake::types::{Direction, GameState};
+
+fuzz_target!(|data: &[u8]| {
+    let Some((header, ticks)) = data.split_first_chunk::<12>() else { return };
+    let width = header[0] as u32 % 16 + 1;
+    let height = header[1] as u32 % 16 + 1;
+    let total = (width * height) as usize;
+    // 初始蛇水平放在中间一行，长度不能超过宽度
+    let length = header[2] as usize % width as usize + 1;
+    let food = header[3] as usize % (total - length + 1);
+    let seed = u64::from_le_bytes(header[4..].try_into().unwrap());
+
+    let mut rng = GameRng::seed_from_u64(seed);
+    let mut game = SnakeGame::new(MapConfig::new(width, height), length, food, &mut rng);
+    game.check_invariants();
+
+    for &byte in ticks {
+        let dir = match byte & 7 {
+            0 => Some(Direction::Up),
+            1 => Some(Direction::Down),
+            2 => Some(Direction::Left),
+            3 => Some(Direction::Right),
+            _ => None,
+        };
+        if byte & 8 != 0 {
+            let saved = rng;
+            let (_, delta) = game.update_recorded(dir, &mut rng);
+            game.check_invariants();
+            game.revert(&delta);
+            game.check_invariants();
+            rng = saved;
+        }
+        let state = game.update(dir, &mut rng);
+        game.check_invariants();
+        if matches!(state, GameState::Over | GameState::Won) {
+            break;
+        }
+    }
+});
@@ -0,0 +1,82 @@
+//! 协议示例 bot（Rust 版）：朝第一份食物贪心移动，避开墙与蛇身
+//!
+//! 供集成测试作为外部进程使用，协议见 `snake::bot`。
+//! 参数：`--delay <毫秒>` 每次回复前等待；`--garbage` 回复无法解析的内容；`--exit` 不读输入直接退出。
+
+use std::io::{self, BufRead, Write};
+use std::time::Duration;
+
+/// 读取 `"key":[[x,y],...]` 中的坐标列表
+fn cells(json: &str, key: &str) -> Vec<(i64, i64)> {
+    let pattern = format!("\"{key}\":[");
+    let Some(start) = json.find(&pattern) else {
+        return Vec::new();
+    };
+    let rest = &json[start + pattern.len()..];
+    let end = rest.find("]]").map_or(0, |e| e + 1);
+    let nums: Vec<i64> = rest[..end]
+        .split(|c: char| !c.is_ascii_digit())
+        .filter(|s| !s.is_empty())
+        .map(|s| s.parse().unwrap())
+        .collect();
+    nums.chunks(2).map(|c| (c[0], c[1])).collect()
+}
+
+/// 读取 `"key":<整数>`
+fn number(json: &str, key: &str) -> i64 {
+    let pattern = format!("\"{key}\":");
+    let start = json.find(&pattern).unwrap() + pattern.len();
+    let digits: String = json[start..].chars().take_while(char::is_ascii_digit).collect();
+    digits.parse().unwrap()
+}
+
+fn choose(json: &str) -> &'static str {
+    let (w, h) = (number(json, "width"), number(json, "height"));
+    let body = cells(json, "body");
+    let food = cells(json, "food");
+    let Some(&(hx, hy)) = body.last() else {
+        return "null";
+    };
+    let target = food.first().copied().unwrap_or((hx, hy));
+    let mut options: Vec<(i64, &str)> = [("up", 0, -1), ("down", 0, 1), ("left", -1, 0), ("right", 1, 0)]
+        .into_iter()
+        .filter_map(|(name, dx, dy)| {
+            let (x, y) = (hx + dx, hy + dy);
+            // 蛇尾本 tick 也会被判为碰撞，因此整条蛇身都要避开
+            let free = (0..w).contains(&x) && (0..h).contains(&y) && !body.contains(&(x, y));
+            free.then_some(((x - target.0).abs() + (y - target.1).abs(), name))
+        })
+        .collect();
+    options.sort();
+    options.first().map_or("null", |&(_, name)| name)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let delay = args
+        .iter()
+        .position(|a| a == "--delay")
+        .and_then(|i| args.get(i + 1)?.parse().ok())
+        .map(Duration::from_millis);
+    let garbage = args.iter().any(|a| a == "--garbage");
+    if args.iter().any(|a| a == "--exit") {
+        return;
+    }
+
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    for line in io::stdin().lock().lines() {
+        let Ok(line) = line else { break };
+        if let Some(delay) = delay {
+            std::thread::sleep(delay);
+        }
+        let reply = if garbage {
+            "sideways".to_string()
+        } else {
+            format!("{} {}", number(&line, "seq"), choose(&line))
+        };
+        if writeln!(out, "{reply}").and_then(|_| out.flush()).is_err() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,260 @@
+//! 外部 AI 协议：通过子进程的 stdin/stdout 交换 JSON 行
+//!
+//! 每个 tick 向 bot 的 stdin 写一行局面（坐标均为 `[x, y]`，蛇身从尾到头）：
+//!
+//! ```text
+//! {"seq":5,"tick":3,"width":16,"height":16,"state":"running","direction":"right","body":[[6,8],[7,8],[8,8]],"food":[[2,5]]}
+//! ```
+//!
+//! `seq` 是请求序号，每次询问加一；`state` 为 `ready` / `running`，`direction` 在尚未出发时为 `null`。
+//! bot 需在超时前从 stdout 回复一行 `<seq> <方向>`，序号原样回显，方向为
+//! `up` / `down` / `left` / `right`（可带 JSON 引号），或 `null` / 省略表示保持方向，例如 `5 left`。
+//! 序号较小的回复是此前超时的迟到回复，直接丢弃；超时或无法解析的回复按保持方向处理，并计入报告。
+//! 对局结束后关闭 bot 的 stdin，bot 应随之退出。
+
+use crate::game::Game;
+use crate::types::{Direction, GameState, Position};
+use std::fmt::Write as _;
+use std::io::{self, BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// 方向的协议名称
+pub const fn direction_name(dir: Direction) -> &'static str {
+    match dir {
+        Direction::Up => "up",
+        Direction::Down => "down",
+        Direction::Left => "left",
+        Direction::Right => "right",
+    }
+}
+
+/// 编码第 `seq` 次询问的局面 JSON（不含换行符）
+pub fn state_json(game: &Game, seq: u64) -> String {
+    let snake = game.snake();
+    let config = snake.config();
+    let mut out = String::with_capacity(96 + (snake.length() + snake.food_count()) * 8);
+    let state = if snake.direction().is_some() { "running" } else { "ready" };
+    let direction = match snake.direction() {
+        Some(dir) => format!("\"{}\"", direction_name(dir)),
+        None => "null".to_string(),
+    };
+    let _ = write!(
+        out,
+        "{{\"seq\":{seq},\"tick\":{},\"width\":{},\"height\":{},\"state\":\"{state}\",\"direction\":{direction},\"body\":",
+        game.moves_count(),
+        config.width,
+        config.height,
+    );
+    let write_cells = |out: &mut String, cells: &mut dyn Iterator<Item = usize>| {
+        out.push('[');
+        for (i, hash) in cells.enumerate() {
+            let Position { x, y } = config.from_hash(hash);
+            let _ = write!(out, "{}[{x},{y}]", if i > 0 { "," } else { "" });
+        }
+        out.push(']');
+    };
+    write_cells(&mut out, &mut snake.snake_hashes().copied());
+    out.push_str(",\"food\":");
+    write_cells(&mut out, &mut snake.food_hashes().iter().copied());
+    out.push('}');
+    out
+}
+
+/// 解析 bot 的回复 `<seq> <方向>`，返回序号与方向（None 表示保持方向）
+pub fn parse_reply(line: &str) -> Option<(u64, Option<Direction>)> {
+    let line = line.trim();
+    let (seq, dir) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let dir = match dir.trim().trim_matches('"') {
+        "up" => Some(Direction::Up),
+        "down" => Some(Direction::Down),
+        "left" => Some(Direction::Left),
+        "right" => Some(Direction::Right),
+        "" | "null" => None,
+        _ => return None,
+    };
+    Some((seq.parse().ok()?, dir))
+}
+
+/// 运行中的 bot 子进程
+pub struct BotProcess {
+    child: Child,
+    stdin: Option<ChildStdin>,
+    /// stdout 逐行读取（后台线程），进程退出时通道关闭
+    lines: mpsc::Receiver<String>,
+    /// 下一次询问的序号
+    seq: u64,
+}
+
+/// 单次询问的结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BotReply {
+    /// 合法回复（None 表示保持方向）
+    Dir(Option<Direction>),
+    /// 超时未回复
+    Timeout,
+    /// 无法解析的回复
+    Invalid(String),
+    /// bot 已退出或关闭了 stdout
+    Exited,
+}
+
+impl BotProcess {
+    /// 启动 bot；stdin/stdout 由本进程接管，stderr 继承（便于 bot 打印调试信息）
+    pub fn spawn(command: &mut Command) -> io::Result<Self> {
+        let mut child = command
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()?;
+        let stdin = child.stdin.take();
+        let stdout = child.stdout.take().expect("stdout 已设为 piped");
+        let (tx, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                if tx.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+            seq: 0,
+        })
+    }
+
+    /// 发送局面并在 `timeout` 内等待序号匹配的回复
+    ///
+    /// 序号更小的行是之前超时询问的迟到回复，丢弃后继续等待，保证回复与局面一一对应。
+    pub fn ask(&mut self, game: &Game, timeout: Duration) -> BotReply {
+        let seq = self.seq;
+        self.seq += 1;
+        let Some(stdin) = self.stdin.as_mut() else {
+            return BotReply::Exited;
+        };
+        if writeln!(stdin, "{}", state_json(game, seq)).and_then(|_| stdin.flush()).is_err() {
+            return BotReply::Exited;
+        }
+        let deadline = Instant::now() + timeout;
+        loop {
+            let line = match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(line) => line,
+                Err(mpsc::RecvTimeoutError::Timeout) => return BotReply::Timeout,
+                Err(mpsc::RecvTimeoutError::Disconnected) => return BotReply::Exited,
+            };
+            match parse_reply(&line) {
+                Some((s, _)) if s < seq => {}
+                Some((s, dir)) if s == seq => return BotReply::Dir(dir),
+                _ => return BotReply::Invalid(line),
+            }
+        }
+    }
+}
+
+impl Drop for BotProcess {
+    /// 关闭 stdin 通知 bot 退出；仍未退出的进程直接结束
+    fn drop(&mut self) {
+        drop(self.stdin.take());
+        std::thread::sleep(Duration::from_millis(10));
+        if let Ok(None) = self.child.try_wait() {
+            let _ = self.child.kill();
+        }
+        let _ = self.child.wait();
+    }
+}
+
+/// 一局 bot 对局的统计
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BotReport {
+    /// 最终状态（达到 tick 上限时为 Running）
+    pub state: GameState,
+    pub moves: u64,
+    pub length: usize,
+    pub timeouts: u32,
+    pub invalid_replies: u32,
+    /// bot 是否中途退出
+    pub exited: bool,
+}
+
+/// 用 bot 驱动 [`Game::tick`]，直到游戏结束、bot 退出或达到 `max_ticks`
+pub fn run(game: &mut Game, bot: &mut BotProcess, timeout: Duration, max_ticks: u64) -> BotReport {
+    run_with(game, bot, timeout, max_ticks, |_| {})
+}
+
+/// 与 [`run`] 相同，每个 tick 之后以新局面调用 `on_tick`（用于录像等）
+pub fn run_with(
+    game: &mut Game,
+    bot: &mut BotProcess,
+    timeout: Duration,
+    max_ticks: u64,
+    mut on_tick: impl FnMut(&Game),
+) -> BotReport {
+    let mut report = BotReport {
+        state: GameState::Ready,
+        moves: 0,
+        length: game.snake().length(),
+        timeouts: 0,
+        invalid_replies: 0,
+        exited: false,
+    };
+    for _ in 0..max_ticks {
+        let direction = match bot.ask(game, timeout) {
+            BotReply::Dir(dir) => dir,
+            BotReply::Timeout => {
+                report.timeouts += 1;
+                None
+            }
+            BotReply::Invalid(_) => {
+                report.invalid_replies += 1;
+                None
+            }
+            BotReply::Exited => {
+                report.exited = true;
+                break;
+            }
+        };
+        report.state = game.tick(direction);
+        on_tick(game);
+        if matches!(report.state, GameState::Over | GameState::Won) {
+            break;
+        }
+    }
+    report.moves = game.moves_count();
+    report.length = game.snake().length();
+    report
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MapConfig;
+
+    #[test]
+    fn test_state_json_format() {
+        let game = Game::with_seed(MapConfig::new(8, 4), 3, 1, 5);
+        let json = state_json(&game, 7);
+        assert!(json.starts_with(
+            "{\"seq\":7,\"tick\":0,\"width\":8,\"height\":4,\"state\":\"running\",\"direction\":\"right\",\"body\":[[2,2],[3,2],[4,2]],\"food\":[["
+        ), "{json}");
+        assert!(json.ends_with("]]}"));
+    }
+
+    #[test]
+    fn test_parse_reply() {
+        assert_eq!(parse_reply("3 up"), Some((3, Some(Direction::Up))));
+        assert_eq!(parse_reply("0 \"left\"\r"), Some((0, Some(Direction::Left))));
+        assert_eq!(parse_reply("12"), Some((12, None)));
+        assert_eq!(parse_reply("12 null"), Some((12, None)));
+        assert_eq!(parse_reply("up"), None);
+        assert_eq!(parse_reply("1 UP"), None);
+        assert_eq!(parse_reply("1 north"), None);
+    }
+}
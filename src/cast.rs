@@ -0,0 +1,246 @@
+//! asciicast v2 录制与回放
+//!
+//! 录制器直接接收 [`render`](crate::render) 的输出：第一行为头部 JSON，
+//! 之后每帧一行 `[秒数, "o", "数据"]`。终端在 raw 模式下需要 `\r\n` 才会回到行首，
+//! 所以写入时把裸 `\n` 转换为 `\r\n`，回放时原样输出即可。
+
+use std::fmt::{self, Write as _};
+use std::io::{self, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// 录制器：把帧以 asciicast v2 事件写入 `out`
+pub struct Recorder<W: Write> {
+    out: W,
+    start: Instant,
+}
+
+impl<W: Write> Recorder<W> {
+    /// 写入头部并开始计时；`width`/`height` 为终端列数与行数
+    pub fn new(mut out: W, width: u32, height: u32) -> io::Result<Self> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        writeln!(
+            out,
+            "{{\"version\": 2, \"width\": {width}, \"height\": {height}, \"timestamp\": {timestamp}}}"
+        )?;
+        Ok(Self {
+            out,
+            start: Instant::now(),
+        })
+    }
+
+    /// 以当前时间记录一帧输出
+    pub fn frame(&mut self, data: &str) -> io::Result<()> {
+        let time = self.start.elapsed().as_secs_f64();
+        self.frame_at(time, data)
+    }
+
+    /// 以指定时间（秒）记录一帧输出
+    pub fn frame_at(&mut self, time: f64, data: &str) -> io::Result<()> {
+        let mut line = String::with_capacity(data.len() + 32);
+        let _ = write!(line, "[{time:.6}, \"o\", \"");
+        let mut prev = '\0';
+        for ch in data.chars() {
+            if ch == '\n' && prev != '\r' {
+                line.push_str("\\r");
+            }
+            escape_char(&mut line, ch);
+            prev = ch;
+        }
+        line.push_str("\"]\n");
+        self.out.write_all(line.as_bytes())
+    }
+
+    /// 取回底层写入端
+    pub fn into_inner(self) -> W {
+        self.out
+    }
+}
+
+/// JSON 字符串转义
+fn escape_char(out: &mut String, ch: char) {
+    match ch {
+        '"' => out.push_str("\\\""),
+        '\\' => out.push_str("\\\\"),
+        '\n' => out.push_str("\\n"),
+        '\r' => out.push_str("\\r"),
+        '\t' => out.push_str("\\t"),
+        c if (c as u32) < 0x20 => {
+            let _ = write!(out, "\\u{:04x}", c as u32);
+        }
+        c => out.push(c),
+    }
+}
+
+/// 解析错误：出错的行号（从 1 开始）与原因
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CastError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+impl fmt::Display for CastError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "第 {} 行: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for CastError {}
+
+/// 解析后的录像
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cast {
+    pub width: u32,
+    pub height: u32,
+    /// 输出事件：(秒数, 数据)；输入等其他类型的事件被忽略
+    pub events: Vec<(f64, String)>,
+}
+
+/// 解析 `.cast` 文件内容
+pub fn parse(text: &str) -> Result<Cast, CastError> {
+    let mut lines = text.lines().enumerate().filter(|(_, l)| !l.trim().is_empty());
+    let err = |line: usize, message| CastError { line: line + 1, message };
+
+    let (_, header) = lines.next().ok_or(err(0, "缺少头部"))?;
+    if header_field(header, "version") != Some(2) {
+        return Err(err(0, "仅支持 asciicast v2"));
+    }
+    let width = header_field(header, "width").ok_or(err(0, "头部缺少 width"))?;
+    let height = header_field(header, "height").ok_or(err(0, "头部缺少 height"))?;
+
+    let mut events = Vec::new();
+    for (n, line) in lines {
+        let (time, kind, data) = parse_event(line).ok_or(err(n, "事件格式错误"))?;
+        if kind == "o" {
+            events.push((time, data));
+        }
+    }
+    Ok(Cast {
+        width: width as u32,
+        height: height as u32,
+        events,
+    })
+}
+
+/// 读取头部中 `"key": <整数>`
+fn header_field(header: &str, key: &str) -> Option<u64> {
+    let pattern = format!("\"{key}\"");
+    let rest = &header[header.find(&pattern)? + pattern.len()..];
+    let rest = rest.trim_start().strip_prefix(':')?.trim_start();
+    let end = rest.find(|c: char| !c.is_ascii_digit()).unwrap_or(rest.len());
+    rest[..end].parse().ok()
+}
+
+/// 解析 `[time, "kind", "data"]`
+fn parse_event(line: &str) -> Option<(f64, String, String)> {
+    let inner = line.trim().strip_prefix('[')?.strip_suffix(']')?;
+    let (time, rest) = inner.split_once(',')?;
+    let time = time.trim().parse().ok()?;
+    let (kind, rest) = parse_string(rest.trim_start())?;
+    let rest = rest.trim_start().strip_prefix(',')?;
+    let (data, rest) = parse_string(rest.trim_start())?;
+    rest.trim().is_empty().then_some((time, kind, data))
+}
+
+/// 解析一个 JSON 字符串，返回 (内容, 剩余部分)
+fn parse_string(s: &str) -> Option<(String, &str)> {
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    let mut out = String::new();
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '"' => return Some((out, &s[i + 2..])),
+            '\\' => {
+                let (_, esc) = chars.next()?;
+                out.push(match esc {
+                    'n' => '\n',
+                    'r' => '\r',
+                    't' => '\t',
+                    'b' => '\u{8}',
+                    'f' => '\u{c}',
+                    'u' => {
+                        let hex: String = (0..4).map(|_| chars.next().map(|(_, c)| c)).collect::<Option<_>>()?;
+                        char::from_u32(u32::from_str_radix(&hex, 16).ok()?)?
+                    }
+                    c => c,
+                });
+            }
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// 按时间戳回放到 `out`；`speed` 为倍速（2.0 表示两倍速）
+///
+/// # Panics
+/// `speed` 不是正数
+pub fn play(cast: &Cast, speed: f64, out: &mut impl Write) -> io::Result<()> {
+    assert!(speed > 0.0, "回放速度必须为正数");
+    let start = Instant::now();
+    for (time, data) in &cast.events {
+        let due = Duration::from_secs_f64(time.max(0.0) / speed);
+        if let Some(wait) = due.checked_sub(start.elapsed()) {
+            std::thread::sleep(wait);
+        }
+        out.write_all(data.as_bytes())?;
+        out.flush()?;
+    }
+    Ok(())
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_parse_roundtrip() {
+        let mut rec = Recorder::new(Vec::new(), 40, 20).unwrap();
+        rec.frame_at(0.0, "\x1B[2J\x1B[1;1H|#  F|\n").unwrap();
+        rec.frame_at(0.05, "quote \" back\\slash\ttab\r\n").unwrap();
+        let text = String::from_utf8(rec.into_inner()).unwrap();
+
+        let mut lines = text.lines();
+        assert!(lines.next().unwrap().starts_with("{\"version\": 2, \"width\": 40, \"height\": 20,"));
+        assert_eq!(
+            lines.next().unwrap(),
+            r#"[0.000000, "o", "\u001b[2J\u001b[1;1H|#  F|\r\n"]"#
+        );
+
+        let cast = parse(&text).unwrap();
+        assert_eq!((cast.width, cast.height), (40, 20));
+        assert_eq!(cast.events.len(), 2);
+        // 裸 \n 被转换为 \r\n，已有的 \r\n 保持不变
+        assert_eq!(cast.events[0].1, "\x1B[2J\x1B[1;1H|#  F|\r\n");
+        assert_eq!(cast.events[1], (0.05, "quote \" back\\slash\ttab\r\n".to_string()));
+    }
+
+    #[test]
+    fn test_parse_errors_and_foreign_events() {
+        assert_eq!(parse("").unwrap_err().line, 1);
+        assert!(parse("{\"version\": 1, \"width\": 80, \"height\": 24}").is_err());
+        let text = "{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.5, \"i\", \"x\"]\n[1.0, \"o\", \"y\"]\n";
+        assert_eq!(parse(text).unwrap().events, vec![(1.0, "y".to_string())]);
+        let bad = "{\"version\": 2, \"width\": 80, \"height\": 24}\n[0.5, \"o\"]\n";
+        assert_eq!(parse(bad).unwrap_err().line, 2);
+    }
+
+    #[test]
+    fn test_play_respects_speed() {
+        let cast = Cast {
+            width: 10,
+            height: 2,
+            events: vec![(0.0, "a".into()), (0.2, "b".into())],
+        };
+        let mut out = Vec::new();
+        let start = Instant::now();
+        play(&cast, 10.0, &mut out).unwrap();
+        let elapsed = start.elapsed();
+        assert_eq!(out, b"ab");
+        assert!(elapsed >= Duration::from_millis(20) && elapsed < Duration::from_millis(150));
+    }
+}
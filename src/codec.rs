@@ -0,0 +1,130 @@
+//! 无依赖的二进制编码工具：CRC-32、Adler-32 与 gzip / zlib 封装
+//!
+//! 压缩只使用 deflate 的"存储"块（不压缩），足以生成任何标准解码器都能读取的文件，
+//! 又不必引入外部压缩库。
+
+/// CRC-32（IEEE 802.3，反射多项式 0xEDB88320）
+pub fn crc32(data: &[u8]) -> u32 {
+    crc32_update(0, data)
+}
+
+/// 在已有 CRC 基础上继续累加（用于分段计算）
+pub fn crc32_update(crc: u32, data: &[u8]) -> u32 {
+    let mut crc = !crc;
+    for &byte in data {
+        crc ^= byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+/// 以 deflate 存储块编码（每块最多 65535 字节）
+pub fn deflate_stored(data: &[u8]) -> Vec<u8> {
+    const MAX_BLOCK: usize = 0xFFFF;
+    let mut out = Vec::with_capacity(data.len() + data.len() / MAX_BLOCK * 5 + 5);
+    let mut chunks = data.chunks(MAX_BLOCK).peekable();
+    if chunks.peek().is_none() {
+        // 空输入也需要一个终止块
+        out.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+        return out;
+    }
+    while let Some(chunk) = chunks.next() {
+        let is_final = chunks.peek().is_none();
+        let len = chunk.len() as u16;
+        out.push(is_final as u8);
+        out.extend_from_slice(&len.to_le_bytes());
+        out.extend_from_slice(&(!len).to_le_bytes());
+        out.extend_from_slice(chunk);
+    }
+    out
+}
+
+/// gzip 封装（RFC 1952）
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    let mut out = vec![0x1F, 0x8B, 8, 0, 0, 0, 0, 0, 0, 0xFF];
+    out.extend_from_slice(&deflate_stored(data));
+    out.extend_from_slice(&crc32(data).to_le_bytes());
+    out.extend_from_slice(&(data.len() as u32).to_le_bytes());
+    out
+}
+
+/// Adler-32（zlib 尾部校验和）
+pub fn adler32(data: &[u8]) -> u32 {
+    const MOD: u32 = 65521;
+    let (mut a, mut b) = (1u32, 0u32);
+    // 每 5552 字节取模一次即可保证 u32 不溢出
+    for chunk in data.chunks(5552) {
+        for &byte in chunk {
+            a += byte as u32;
+            b += a;
+        }
+        a %= MOD;
+        b %= MOD;
+    }
+    b << 16 | a
+}
+
+/// zlib 封装（RFC 1950），PNG 的 IDAT 使用此格式
+pub fn zlib(data: &[u8]) -> Vec<u8> {
+    // CMF = deflate / 32K 窗口，FLG 使 (CMF << 8 | FLG) 为 31 的倍数
+    let mut out = vec![0x78, 0x01];
+    out.extend_from_slice(&deflate_stored(data));
+    out.extend_from_slice(&adler32(data).to_be_bytes());
+    out
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    /// 解码仅含存储块的 deflate 流（测试用）
+    pub(crate) fn inflate_stored(mut data: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        loop {
+            let header = data[0];
+            let len = u16::from_le_bytes([data[1], data[2]]) as usize;
+            let nlen = u16::from_le_bytes([data[3], data[4]]);
+            assert_eq!(header & 0b110, 0, "只支持存储块");
+            assert_eq!(!nlen as usize, len);
+            out.extend_from_slice(&data[5..5 + len]);
+            data = &data[5 + len..];
+            if header & 1 == 1 {
+                return out;
+            }
+        }
+    }
+
+    #[test]
+    fn test_crc32_known_values() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        assert_eq!(crc32_update(crc32(b"1234"), b"56789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_gzip_roundtrip() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i * 7 % 251) as u8).collect();
+        let gz = gzip(&data);
+        assert_eq!(&gz[..3], &[0x1F, 0x8B, 8]);
+        let body = &gz[10..gz.len() - 8];
+        assert_eq!(inflate_stored(body), data);
+        let trailer = &gz[gz.len() - 8..];
+        assert_eq!(trailer[..4], crc32(&data).to_le_bytes());
+        assert_eq!(trailer[4..], (data.len() as u32).to_le_bytes());
+        assert_eq!(inflate_stored(&deflate_stored(&[])), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_adler32_and_zlib() {
+        assert_eq!(adler32(b""), 1);
+        assert_eq!(adler32(b"Wikipedia"), 0x11E6_0398);
+        let data: Vec<u8> = (0..20_000u32).map(|i| (i % 256) as u8).collect();
+        let z = zlib(&data);
+        assert_eq!(u16::from_be_bytes([z[0], z[1]]) % 31, 0);
+        assert_eq!(inflate_stored(&z[2..z.len() - 4]), data);
+        assert_eq!(z[z.len() - 4..], adler32(&data).to_be_bytes());
+    }
+}
@@ -1,7 +1,7 @@
 use crate::types::Position;
 
 /// 地图维度配置与坐标-哈希转换工具
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct MapConfig {
     pub width: u32,
     pub height: u32,
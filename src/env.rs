@@ -0,0 +1,294 @@
+//! 强化学习环境：gym 风格的 [`SnakeGame`] 封装
+//!
+//! [`SnakeEnv::reset`] 以种子开局，[`SnakeEnv::step`] 执行一个动作并返回
+//! `(观测, 奖励, terminated, truncated)`：前者是对局真正结束（死亡或填满），
+//! 后者是到达步数上限被截断，此时下一状态仍有价值，训练时应照常自举。奖励由 [`Rewards`] 塑形，观测按 [`Encoding`] 编码为 `f32` 向量：
+//!
+//! - [`Encoding::Grid`]：三个 `宽 × 高` 平面（蛇身、蛇头、食物），按格子 hash 顺序排列，取值 0/1；
+//! - [`Encoding::Rays`]：从蛇头向 8 个方向（上起顺时针）各射一条线，
+//!   依次给出到墙、蛇身、食物的距离倒数（看不到蛇身或食物时为 0），共 24 个值。
+
+use crate::config::MapConfig;
+use crate::rng::GameRng;
+use crate::snake::SnakeGame;
+use crate::types::{CellState, Direction, GameState, Position};
+use rand::SeedableRng;
+
+/// 离散动作空间：动作下标与方向的对应关系
+pub const ACTIONS: [Direction; 4] = [Direction::Up, Direction::Right, Direction::Down, Direction::Left];
+
+/// 射线方向（上起顺时针）
+const RAYS: [(i32, i32); 8] = [(0, -1), (1, -1), (1, 0), (1, 1), (0, 1), (-1, 1), (-1, 0), (-1, -1)];
+
+/// 奖励塑形
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Rewards {
+    /// 吃到食物
+    pub food: f32,
+    /// 撞墙或撞到蛇身
+    pub death: f32,
+    /// 每步都加上（通常为负，促使尽快吃到食物）
+    pub step: f32,
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards { food: 1.0, death: -1.0, step: -0.01 }
+    }
+}
+
+/// 观测编码
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// 蛇身、蛇头、食物三个平面
+    Grid,
+    /// 8 方向射线距离
+    Rays,
+}
+
+/// 环境参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvConfig {
+    pub map: MapConfig,
+    pub initial_length: usize,
+    pub food_count: usize,
+    pub rewards: Rewards,
+    pub encoding: Encoding,
+    /// 单局步数上限，到达后 `truncated`（None 表示不限）
+    pub max_steps: Option<u64>,
+}
+
+impl Default for EnvConfig {
+    fn default() -> Self {
+        EnvConfig {
+            map: MapConfig::new(16, 16),
+            initial_length: 3,
+            food_count: 1,
+            rewards: Rewards::default(),
+            encoding: Encoding::Rays,
+            max_steps: None,
+        }
+    }
+}
+
+/// gym 风格的环境
+#[derive(Debug, Clone)]
+pub struct SnakeEnv {
+    config: EnvConfig,
+    game: SnakeGame,
+    rng: GameRng,
+    steps: u64,
+    /// 上一步已 terminated 或 truncated
+    done: bool,
+}
+
+impl SnakeEnv {
+    /// 创建环境并以种子 0 开局
+    pub fn new(config: EnvConfig) -> Self {
+        let mut rng = GameRng::seed_from_u64(0);
+        let game = SnakeGame::new(config.map.clone(), config.initial_length, config.food_count, &mut rng);
+        SnakeEnv { config, game, rng, steps: 0, done: false }
+    }
+
+    /// 以种子 `seed` 重新开局，返回初始观测
+    pub fn reset(&mut self, seed: u64) -> Vec<f32> {
+        self.rng = GameRng::seed_from_u64(seed);
+        self.game = SnakeGame::new(
+            self.config.map.clone(),
+            self.config.initial_length,
+            self.config.food_count,
+            &mut self.rng,
+        );
+        self.steps = 0;
+        self.done = false;
+        self.observe()
+    }
+
+    /// 执行一个动作，返回 `(观测, 奖励, terminated, truncated)`
+    ///
+    /// `terminated` 表示撞死或获胜；`truncated` 表示对局仍在进行，只是到达了
+    /// [`EnvConfig::max_steps`]。两者同时成立时只报告 `terminated`。
+    /// 180 度掉头与游戏中一样被忽略（继续直行）。
+    ///
+    /// # Panics
+    /// 上一步已经返回 `terminated` 或 `truncated` 且尚未 [`reset`](Self::reset)
+    pub fn step(&mut self, action: Direction) -> (Vec<f32>, f32, bool, bool) {
+        assert!(!self.done, "对局已结束，需先 reset");
+        let length = self.game.length();
+        let state = self.game.update(Some(action), &mut self.rng);
+        self.steps += 1;
+
+        let rewards = &self.config.rewards;
+        let mut reward = rewards.step;
+        if state == GameState::Over {
+            reward += rewards.death;
+        } else if self.game.length() > length {
+            reward += rewards.food;
+        }
+        let terminated = matches!(state, GameState::Over | GameState::Won);
+        let truncated = !terminated && self.config.max_steps.is_some_and(|max| self.steps >= max);
+        self.done = terminated || truncated;
+        (self.observe(), reward, terminated, truncated)
+    }
+
+    /// 当前局面的观测
+    pub fn observe(&self) -> Vec<f32> {
+        match self.config.encoding {
+            Encoding::Grid => self.grid_planes(),
+            Encoding::Rays => self.rays(),
+        }
+    }
+
+    /// 观测向量的长度
+    pub fn observation_len(&self) -> usize {
+        match self.config.encoding {
+            Encoding::Grid => 3 * self.config.map.total_size(),
+            Encoding::Rays => 3 * RAYS.len(),
+        }
+    }
+
+    pub fn game(&self) -> &SnakeGame {
+        &self.game
+    }
+
+    pub fn config(&self) -> &EnvConfig {
+        &self.config
+    }
+
+    /// 本局已走的步数
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    fn grid_planes(&self) -> Vec<f32> {
+        let n = self.config.map.total_size();
+        let mut obs = vec![0.0; 3 * n];
+        for &hash in self.game.snake_hashes() {
+            obs[hash] = 1.0;
+        }
+        if let Some(&head) = self.game.snake_hashes().next_back() {
+            obs[head] = 0.0;
+            obs[n + head] = 1.0;
+        }
+        for &hash in self.game.food_hashes() {
+            obs[2 * n + hash] = 1.0;
+        }
+        obs
+    }
+
+    fn rays(&self) -> Vec<f32> {
+        let cfg = &self.config.map;
+        let mut obs = Vec::with_capacity(3 * RAYS.len());
+        let Some(head) = self.game.head_position() else {
+            obs.resize(3 * RAYS.len(), 0.0);
+            return obs;
+        };
+        for (dx, dy) in RAYS {
+            let (mut body, mut food) = (0.0, 0.0);
+            let mut dist = 1;
+            loop {
+                let x = head.x as i64 + dx as i64 * dist;
+                let y = head.y as i64 + dy as i64 * dist;
+                if x < 0 || y < 0 || x >= cfg.width as i64 || y >= cfg.height as i64 {
+                    break;
+                }
+                let hash = cfg.to_hash(Position { x: x as u32, y: y as u32 });
+                match self.game.cell_state(hash) {
+                    CellState::Snake if body == 0.0 => body = 1.0 / dist as f32,
+                    CellState::Food if food == 0.0 => food = 1.0 / dist as f32,
+                    _ => {}
+                }
+                dist += 1;
+            }
+            obs.extend([1.0 / dist as f32, body, food]);
+        }
+        obs
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn env(encoding: Encoding, food_count: usize) -> SnakeEnv {
+        SnakeEnv::new(EnvConfig {
+            map: MapConfig::new(8, 6),
+            food_count,
+            encoding,
+            ..EnvConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_reset_is_reproducible() {
+        let mut env = env(Encoding::Grid, 3);
+        let a = env.reset(5);
+        env.step(Direction::Up);
+        let b = env.reset(5);
+        assert_eq!(a, b);
+        assert_eq!(env.steps(), 0);
+        assert_eq!(a.len(), env.observation_len());
+    }
+
+    #[test]
+    fn test_grid_planes() {
+        let mut env = env(Encoding::Grid, 2);
+        let obs = env.reset(1);
+        let n = 8 * 6;
+        // 开局蛇 (2,3)(3,3)(4,3)，蛇头 (4,3)
+        assert_eq!(obs[..n].iter().sum::<f32>(), 2.0);
+        assert_eq!(obs[n..2 * n].iter().sum::<f32>(), 1.0);
+        assert_eq!(obs[n + 3 * 8 + 4], 1.0);
+        assert_eq!(obs[2 * n..].iter().sum::<f32>(), 2.0);
+    }
+
+    #[test]
+    fn test_rays() {
+        let mut env = env(Encoding::Rays, 0);
+        let obs = env.reset(0);
+        assert_eq!(obs.len(), 24);
+        // 蛇头 (4,3)：向上 4 格出界，向右 4 格出界，向左先看到蛇身
+        assert_eq!(obs[0..3], [0.25, 0.0, 0.0]);
+        assert_eq!(obs[6..9], [0.25, 0.0, 0.0]);
+        assert_eq!(obs[18..21], [0.2, 1.0, 0.0]);
+    }
+
+    #[test]
+    fn test_rewards_and_done() {
+        let mut env = env(Encoding::Rays, 0);
+        env.reset(0);
+        // 蛇头 (4,3) 向右 3 步到达 (7,3)，第 4 步撞墙
+        for _ in 0..3 {
+            let (_, reward, terminated, truncated) = env.step(Direction::Right);
+            assert_eq!((reward, terminated, truncated), (-0.01, false, false));
+        }
+        let (_, reward, terminated, truncated) = env.step(Direction::Right);
+        assert_eq!((reward, terminated, truncated), (-1.01, true, false));
+    }
+
+    #[test]
+    fn test_food_reward_and_step_limit() {
+        let mut env = SnakeEnv::new(EnvConfig {
+            map: MapConfig::new(4, 1),
+            initial_length: 1,
+            food_count: 3,
+            ..EnvConfig::default()
+        });
+        env.reset(0);
+        // 1×4 地图除蛇头外全是食物
+        let (_, reward, terminated, truncated) = env.step(Direction::Right);
+        assert_eq!((reward, terminated, truncated), (0.99, false, false));
+
+        // 步数上限是截断而不是终止
+        let mut env = SnakeEnv::new(EnvConfig { max_steps: Some(2), ..EnvConfig::default() });
+        env.reset(0);
+        let (_, _, terminated, truncated) = env.step(Direction::Up);
+        assert_eq!((terminated, truncated), (false, false));
+        let (_, _, terminated, truncated) = env.step(Direction::Up);
+        assert_eq!((terminated, truncated), (false, true));
+    }
+}
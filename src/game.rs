@@ -1,13 +1,33 @@
 use crate::config::MapConfig;
-use crate::snake::SnakeGame;
+use crate::rng::GameRng;
+use crate::snake::{BoardChange, SnakeGame, TickDelta};
+use crate::snapshot::{self, SnapshotError};
 use crate::types::{Direction, GameState};
-use rand::rngs::ThreadRng;
+use rand::SeedableRng;
+use std::collections::VecDeque;
+
+/// 可回退的最大 tick 数
+pub const HISTORY_LIMIT: usize = 1000;
+
+/// 历史记录中的一项：棋盘增量 + tick 前的 RNG 状态
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    delta: TickDelta,
+    rng: GameRng,
+    /// 该 tick 是否计入了步数
+    counted: bool,
+}
 
 /// 游戏管理器：封装蛇游戏状态、RNG 和步数计数
+#[derive(Debug, Clone)]
 pub struct Game {
     snake: SnakeGame,
     moves_count: u64,
-    rng: ThreadRng,
+    rng: GameRng,
+    /// 最近的 tick 增量（环形，超出 [`HISTORY_LIMIT`] 时丢弃最旧的）
+    history: VecDeque<HistoryEntry>,
+    /// 进入过历史的 tick 总数，回退时减一（不受 [`HISTORY_LIMIT`] 截断）
+    recorded: u64,
 }
 
 /// 将 crossterm KeyCode 转换为游戏方向
@@ -24,19 +44,53 @@ pub fn key_to_direction(key_code: crossterm::event::KeyCode) -> Option<Direction
     }
 }
 
+/// 将 WASD 键转换为游戏方向（本地双人对战中的 2 号玩家）
+///
+/// 大小写均可；返回 None 表示非 WASD 键。
+pub fn wasd_to_direction(key_code: crossterm::event::KeyCode) -> Option<Direction> {
+    use crossterm::event::KeyCode;
+    match key_code {
+        KeyCode::Char('w' | 'W') => Some(Direction::Up),
+        KeyCode::Char('s' | 'S') => Some(Direction::Down),
+        KeyCode::Char('a' | 'A') => Some(Direction::Left),
+        KeyCode::Char('d' | 'D') => Some(Direction::Right),
+        _ => None,
+    }
+}
+
 impl Game {
-    /// 创建新的游戏实例
+    /// 创建新的游戏实例（随机种子）
     pub fn new(
         config: MapConfig,
         initial_length: usize,
         food_count: usize,
     ) -> Self {
-        let mut rng = rand::rng();
+        Self::with_rng(config, initial_length, food_count, GameRng::from_entropy())
+    }
+
+    /// 以固定种子创建游戏实例，同样的种子与输入序列得到同样的对局
+    pub fn with_seed(
+        config: MapConfig,
+        initial_length: usize,
+        food_count: usize,
+        seed: u64,
+    ) -> Self {
+        Self::with_rng(config, initial_length, food_count, GameRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(
+        config: MapConfig,
+        initial_length: usize,
+        food_count: usize,
+        mut rng: GameRng,
+    ) -> Self {
         let snake = SnakeGame::new(config, initial_length, food_count, &mut rng);
         Self {
             snake,
             moves_count: 0,
             rng,
+            history: VecDeque::new(),
+            recorded: 0,
         }
     }
 
@@ -52,15 +106,143 @@ impl Game {
 
     /// 执行一个游戏 tick：应用方向并推进一帧，返回新状态
     pub fn tick(&mut self, direction: Option<Direction>) -> GameState {
-        let state = self.snake.update(direction, &mut self.rng);
-        if state == GameState::Running {
+        let rng = self.rng;
+        let (state, delta) = self.snake.update_recorded(direction, &mut self.rng);
+        let counted = state == GameState::Running;
+        if counted {
             self.moves_count += 1;
         }
+        // 什么都没变的 tick（等待输入）不占历史
+        if delta.change != BoardChange::None || delta.prev_direction != self.snake.direction() {
+            if self.history.len() == HISTORY_LIMIT {
+                self.history.pop_front();
+            }
+            self.history.push_back(HistoryEntry { delta, rng, counted });
+            self.recorded += 1;
+        }
         state
     }
 
+    /// 回退一个 tick（O(1)），连同 RNG 与步数一起恢复
+    ///
+    /// 历史为空时返回 false。
+    pub fn rewind(&mut self) -> bool {
+        let Some(entry) = self.history.pop_back() else {
+            return false;
+        };
+        self.snake.revert(&entry.delta);
+        self.rng = entry.rng;
+        if entry.counted {
+            self.moves_count -= 1;
+        }
+        self.recorded -= 1;
+        true
+    }
+
+    /// 可回退的 tick 数
+    pub fn history_len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// 进入过历史的 tick 总数（每次回退减一）
+    ///
+    /// 与 [`history_len`](Self::history_len) 不同，超出 [`HISTORY_LIMIT`] 后仍继续增长，
+    /// 可用来判断一个 tick 是否改变了局面，以及让逐 tick 的外部记录与回退保持同步。
+    pub fn recorded_ticks(&self) -> u64 {
+        self.recorded
+    }
+
+    /// 编码为快照（见 [`snapshot`]）
+    pub fn snapshot(&self) -> Vec<u8> {
+        snapshot::encode(&self.snake, self.moves_count, &self.rng)
+    }
+
+    /// 从快照恢复
+    pub fn restore(bytes: &[u8]) -> Result<Self, SnapshotError> {
+        let snap = snapshot::decode(bytes)?;
+        Ok(Self {
+            snake: snap.game,
+            moves_count: snap.moves_count,
+            rng: snap.rng,
+            history: VecDeque::new(),
+            recorded: 0,
+        })
+    }
+
     /// 渲染当前游戏画面到给定缓冲区
     pub fn render(&self, output: &mut String) {
         crate::render::render(&self.snake, output);
     }
 }
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DIRS: [Direction; 4] = [Direction::Up, Direction::Left, Direction::Down, Direction::Right];
+
+    #[test]
+    fn test_rewind_restores_exact_state() {
+        let mut game = Game::with_seed(MapConfig::new(8, 8), 3, 4, 7);
+        // 每个入历史的 tick 之前的状态
+        let mut before = Vec::new();
+        for i in 0..40 {
+            let prev = game.clone();
+            let state = game.tick(Some(DIRS[(i / 3) % 4]));
+            if game.history_len() > prev.history_len() {
+                before.push(prev);
+            }
+            if state != GameState::Running {
+                break;
+            }
+        }
+        while let Some(expected) = before.pop() {
+            assert!(game.rewind());
+            assert_eq!(game.moves_count, expected.moves_count);
+            assert_eq!(game.rng, expected.rng);
+            assert_eq!(format!("{:?}", game.snake), format!("{:?}", expected.snake));
+        }
+        assert!(!game.rewind());
+    }
+
+    #[test]
+    fn test_rewind_then_replay_is_deterministic() {
+        let mut game = Game::with_seed(MapConfig::new(10, 10), 3, 2, 3);
+        for i in 0..30 {
+            game.tick(Some(DIRS[(i / 4) % 4]));
+        }
+        let mut branch = game.clone();
+        for i in 0..10 {
+            branch.tick(Some(DIRS[i % 4]));
+        }
+        while branch.history_len() > game.history_len() {
+            branch.rewind();
+        }
+        for i in 0..10 {
+            assert_eq!(branch.tick(Some(DIRS[i % 2])), game.tick(Some(DIRS[i % 2])));
+            assert_eq!(branch.snake.food_hashes(), game.snake.food_hashes());
+        }
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut game = Game::with_seed(MapConfig::new(64, 64), 3, 0, 1);
+        for i in 0..HISTORY_LIMIT + 50 {
+            // 沿 3×3 方框绕圈，永不撞墙
+            let dir = [Direction::Up, Direction::Right, Direction::Down, Direction::Left][i / 2 % 4];
+            assert_eq!(game.tick(Some(dir)), GameState::Running);
+        }
+        assert_eq!(game.history_len(), HISTORY_LIMIT);
+        assert_eq!(game.recorded_ticks(), HISTORY_LIMIT as u64 + 50);
+        let mut n = 0;
+        while game.rewind() {
+            n += 1;
+        }
+        assert_eq!(n, HISTORY_LIMIT);
+        assert_eq!(game.recorded_ticks(), 50);
+    }
+}
@@ -0,0 +1,492 @@
+//! 软件光栅化：把局面画成索引色像素帧，并编码为 PNG 或 GIF 动画
+//!
+//! 每个格子画成 `cell × cell` 的色块，像素值是 [`Palette`] 中的下标，
+//! 因此同一组帧可以直接写成 PNG 调色板图像，也可以作为 GIF 帧。
+//! 压缩复用 [`codec`]（PNG 的 zlib 只用存储块），GIF 使用自带的 LZW 编码器。
+
+use crate::codec;
+use crate::snake::SnakeGame;
+use crate::types::CellState;
+use std::collections::VecDeque;
+use std::io::{self, Write};
+
+/// RGB 颜色
+pub type Rgb = [u8; 3];
+
+/// 背景色下标
+pub const BACKGROUND: u8 = 0;
+/// 蛇身色下标
+pub const BODY: u8 = 1;
+/// 蛇头色下标
+pub const HEAD: u8 = 2;
+/// 食物色下标
+pub const FOOD: u8 = 3;
+
+/// 调色板（按 [`BACKGROUND`]、[`BODY`]、[`HEAD`]、[`FOOD`] 的顺序）
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Palette(pub [Rgb; 4]);
+
+impl Default for Palette {
+    fn default() -> Self {
+        Palette([[24, 24, 32], [64, 192, 96], [240, 240, 96], [224, 64, 64]])
+    }
+}
+
+/// 一帧索引色图像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Frame {
+    pub width: u32,
+    pub height: u32,
+    /// 行优先的调色板下标
+    pub pixels: Vec<u8>,
+}
+
+/// 把局面画成一帧，每格 `cell × cell` 像素
+///
+/// # Panics
+/// `cell` 为 0
+pub fn rasterize(game: &SnakeGame, cell: u32) -> Frame {
+    assert!(cell > 0, "格子像素尺寸必须大于 0");
+    let config = game.config();
+    let width = config.width * cell;
+    let height = config.height * cell;
+    let head = game.snake_hashes().next_back().copied();
+    let mut pixels = vec![BACKGROUND; width as usize * height as usize];
+    for hash in 0..config.total_size() {
+        let color = match game.cell_state(hash) {
+            CellState::Empty => continue,
+            CellState::Food => FOOD,
+            CellState::Snake if Some(hash) == head => HEAD,
+            CellState::Snake => BODY,
+        };
+        let pos = config.from_hash(hash);
+        for dy in 0..cell {
+            let row = ((pos.y * cell + dy) * width + pos.x * cell) as usize;
+            pixels[row..row + cell as usize].fill(color);
+        }
+    }
+    Frame {
+        width,
+        height,
+        pixels,
+    }
+}
+
+// ============================================================================
+// PNG
+// ============================================================================
+
+/// 编码为 PNG（8 位调色板图像）
+pub fn to_png(frame: &Frame, palette: &Palette) -> Vec<u8> {
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut ihdr = Vec::with_capacity(13);
+    ihdr.extend_from_slice(&frame.width.to_be_bytes());
+    ihdr.extend_from_slice(&frame.height.to_be_bytes());
+    // 位深 8、颜色类型 3（调色板）、标准压缩/滤波、无隔行
+    ihdr.extend_from_slice(&[8, 3, 0, 0, 0]);
+    png_chunk(&mut out, b"IHDR", &ihdr);
+    png_chunk(&mut out, b"PLTE", palette.0.as_flattened());
+
+    // 每行前加滤波类型 0（None）
+    let stride = frame.width as usize;
+    let mut raw = Vec::with_capacity((stride + 1) * frame.height as usize);
+    for row in frame.pixels.chunks(stride.max(1)) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+    png_chunk(&mut out, b"IDAT", &codec::zlib(&raw));
+    png_chunk(&mut out, b"IEND", &[]);
+    out
+}
+
+fn png_chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = codec::crc32_update(codec::crc32(kind), data);
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+// ============================================================================
+// GIF
+// ============================================================================
+
+/// 调色板只有 4 色，LZW 最小码长为 2
+const GIF_MIN_CODE_SIZE: u8 = 2;
+
+/// 编码为循环播放的 GIF 动画，`delay_cs` 为每帧时长（1/100 秒）
+///
+/// # Panics
+/// - `frames` 为空或各帧尺寸不同
+/// - 尺寸超过 65535
+pub fn to_gif(frames: &[Frame], palette: &Palette, delay_cs: u16) -> Vec<u8> {
+    let mut gif = GifWriter::new(Vec::new(), palette, delay_cs, 0);
+    for frame in frames {
+        gif.push(frame).expect("写入 Vec 不会失败");
+    }
+    gif.finish().expect("写入 Vec 不会失败")
+}
+
+/// 边录边写的 GIF 动画
+///
+/// 每帧在 [`push`](Self::push) 时即完成 LZW 编码，最近 `keep` 帧暂存在内存中，
+/// 仍可用 [`pop`](Self::pop) 撤销（对应游戏回退）；更早的帧直接写入 `out`。
+/// 内存占用只与 `keep` 有关，与动画总长度无关。
+pub struct GifWriter<W: Write> {
+    out: W,
+    palette: Palette,
+    delay_cs: u16,
+    keep: usize,
+    /// 首帧确定的画布尺寸；写出文件头之前为 None
+    size: Option<(u16, u16)>,
+    /// 尚未写出的已编码帧
+    pending: VecDeque<Vec<u8>>,
+}
+
+impl<W: Write> GifWriter<W> {
+    pub fn new(out: W, palette: &Palette, delay_cs: u16, keep: usize) -> Self {
+        GifWriter {
+            out,
+            palette: palette.clone(),
+            delay_cs,
+            keep,
+            size: None,
+            pending: VecDeque::new(),
+        }
+    }
+
+    /// 追加一帧；首帧决定画布尺寸并写出文件头
+    ///
+    /// # Panics
+    /// 尺寸超过 65535，或与首帧不同
+    pub fn push(&mut self, frame: &Frame) -> io::Result<()> {
+        let width = u16::try_from(frame.width).expect("GIF 宽度超过 65535");
+        let height = u16::try_from(frame.height).expect("GIF 高度超过 65535");
+        match self.size {
+            Some(size) => assert_eq!(size, (width, height), "所有帧的尺寸必须相同"),
+            None => {
+                self.size = Some((width, height));
+                let mut header = b"GIF89a".to_vec();
+                header.extend_from_slice(&width.to_le_bytes());
+                header.extend_from_slice(&height.to_le_bytes());
+                // 全局颜色表：存在、色深 2 位、4 项
+                header.extend_from_slice(&[0x80 | 1 << 4 | 1, BACKGROUND, 0]);
+                header.extend_from_slice(self.palette.0.as_flattened());
+                // NETSCAPE2.0 扩展：无限循环
+                header.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+                self.out.write_all(&header)?;
+            }
+        }
+
+        // 图形控制扩展：帧延时
+        let mut block = vec![0x21, 0xF9, 4, 0];
+        block.extend_from_slice(&self.delay_cs.to_le_bytes());
+        block.extend_from_slice(&[0, 0]);
+        // 图像描述符：整幅画布，使用全局颜色表
+        block.push(0x2C);
+        block.extend_from_slice(&[0, 0, 0, 0]);
+        block.extend_from_slice(&width.to_le_bytes());
+        block.extend_from_slice(&height.to_le_bytes());
+        block.push(0);
+        block.push(GIF_MIN_CODE_SIZE);
+        for chunk in lzw_encode(&frame.pixels, GIF_MIN_CODE_SIZE).chunks(255) {
+            block.push(chunk.len() as u8);
+            block.extend_from_slice(chunk);
+        }
+        block.push(0);
+
+        self.pending.push_back(block);
+        while self.pending.len() > self.keep {
+            let block = self.pending.pop_front().unwrap();
+            self.out.write_all(&block)?;
+        }
+        Ok(())
+    }
+
+    /// 撤销最近一帧；已写出或没有暂存帧时返回 false
+    pub fn pop(&mut self) -> bool {
+        self.pending.pop_back().is_some()
+    }
+
+    /// 写出暂存的帧与文件尾，返回底层写入端
+    ///
+    /// # Panics
+    /// 从未追加过帧
+    pub fn finish(mut self) -> io::Result<W> {
+        assert!(self.size.is_some(), "GIF 至少需要一帧");
+        for block in &self.pending {
+            self.out.write_all(block)?;
+        }
+        self.out.write_all(&[0x3B])?;
+        Ok(self.out)
+    }
+}
+
+/// 按 LSB 优先拼接变长码字
+struct BitWriter {
+    bytes: Vec<u8>,
+    acc: u32,
+    bits: u8,
+}
+
+impl BitWriter {
+    fn write(&mut self, code: u16, size: u8) {
+        self.acc |= (code as u32) << self.bits;
+        self.bits += size;
+        while self.bits >= 8 {
+            self.bytes.push(self.acc as u8);
+            self.acc >>= 8;
+            self.bits -= 8;
+        }
+    }
+
+    fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.bytes.push(self.acc as u8);
+        }
+        self.bytes
+    }
+}
+
+/// GIF 变体的 LZW 编码（码长 3–12 位，字典满时发送清除码）
+fn lzw_encode(pixels: &[u8], min_code_size: u8) -> Vec<u8> {
+    const MAX_CODES: u16 = 4096;
+    let clear = 1u16 << min_code_size;
+    let end = clear + 1;
+
+    // 字典：(前缀码, 像素) → 码字；前缀码 < 4096、像素 < 256，直接用数组索引
+    let mut table = vec![u16::MAX; MAX_CODES as usize * 256];
+    let mut next = end + 1;
+    let mut size = min_code_size + 1;
+    let mut out = BitWriter {
+        bytes: Vec::with_capacity(pixels.len() / 2),
+        acc: 0,
+        bits: 0,
+    };
+
+    // 写入码字后，若下一个码字已超出当前码长可表示的范围则加长（与解码器同步）
+    let emit = |out: &mut BitWriter, code: u16, size: &mut u8, next: u16| {
+        out.write(code, *size);
+        if next >= 1 << *size && *size < 12 {
+            *size += 1;
+        }
+    };
+
+    out.write(clear, size);
+    let Some((&first, rest)) = pixels.split_first() else {
+        out.write(end, size);
+        return out.finish();
+    };
+    let mut prefix = first as u16;
+    for &pixel in rest {
+        let slot = prefix as usize * 256 + pixel as usize;
+        if table[slot] != u16::MAX {
+            prefix = table[slot];
+            continue;
+        }
+        emit(&mut out, prefix, &mut size, next);
+        if next < MAX_CODES {
+            table[slot] = next;
+            next += 1;
+        } else {
+            out.write(clear, size);
+            table.fill(u16::MAX);
+            next = end + 1;
+            size = min_code_size + 1;
+        }
+        prefix = pixel as u16;
+    }
+    emit(&mut out, prefix, &mut size, next);
+    out.write(end, size);
+    out.finish()
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::tests::inflate_stored;
+    use crate::config::MapConfig;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    fn sample_game() -> SnakeGame {
+        let mut rng = SmallRng::seed_from_u64(4);
+        SnakeGame::new(MapConfig::new(6, 4), 3, 2, &mut rng)
+    }
+
+    #[test]
+    fn test_rasterize_blocks() {
+        let game = sample_game();
+        let frame = rasterize(&game, 3);
+        assert_eq!((frame.width, frame.height), (18, 12));
+        let head = game.head_position().unwrap();
+        for dy in 0..3 {
+            for dx in 0..3 {
+                let (x, y) = (head.x * 3 + dx, head.y * 3 + dy);
+                assert_eq!(frame.pixels[(y * 18 + x) as usize], HEAD);
+            }
+        }
+        let count = |c| frame.pixels.iter().filter(|&&p| p == c).count();
+        assert_eq!(count(BODY), 2 * 9);
+        assert_eq!(count(FOOD), 2 * 9);
+    }
+
+    #[test]
+    fn test_png_structure() {
+        let frame = rasterize(&sample_game(), 2);
+        let png = to_png(&frame, &Palette::default());
+        assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+
+        // 逐块校验 CRC 并取出 IDAT
+        let mut at = 8;
+        let mut idat = Vec::new();
+        let mut kinds = Vec::new();
+        while at < png.len() {
+            let len = u32::from_be_bytes(png[at..at + 4].try_into().unwrap()) as usize;
+            let kind = &png[at + 4..at + 8];
+            let data = &png[at + 8..at + 8 + len];
+            let crc = u32::from_be_bytes(png[at + 8 + len..at + 12 + len].try_into().unwrap());
+            assert_eq!(crc, codec::crc32_update(codec::crc32(kind), data));
+            if kind == b"IDAT" {
+                idat.extend_from_slice(data);
+            }
+            kinds.push(String::from_utf8(kind.to_vec()).unwrap());
+            at += 12 + len;
+        }
+        assert_eq!(kinds, ["IHDR", "PLTE", "IDAT", "IEND"]);
+
+        let raw = inflate_stored(&idat[2..idat.len() - 4]);
+        let stride = frame.width as usize + 1;
+        assert_eq!(raw.len(), stride * frame.height as usize);
+        for (y, row) in raw.chunks(stride).enumerate() {
+            assert_eq!(row[0], 0, "滤波类型");
+            let start = y * frame.width as usize;
+            assert_eq!(&row[1..], &frame.pixels[start..start + frame.width as usize]);
+        }
+    }
+
+    /// 标准 GIF LZW 解码（测试用）
+    fn lzw_decode(data: &[u8], min_code_size: u8) -> Vec<u8> {
+        let clear = 1usize << min_code_size;
+        let end = clear + 1;
+        let reset = || -> Vec<Vec<u8>> {
+            let mut dict: Vec<Vec<u8>> = (0..clear).map(|i| vec![i as u8]).collect();
+            dict.push(Vec::new());
+            dict.push(Vec::new());
+            dict
+        };
+        let mut dict = reset();
+        let mut size = min_code_size as u32 + 1;
+        let mut prev: Option<Vec<u8>> = None;
+        let mut out = Vec::new();
+        let (mut acc, mut bits, mut bytes) = (0u32, 0u32, data.iter());
+        loop {
+            while bits < size {
+                acc |= (*bytes.next().expect("缺少结束码") as u32) << bits;
+                bits += 8;
+            }
+            let code = (acc & ((1 << size) - 1)) as usize;
+            acc >>= size;
+            bits -= size;
+            if code == clear {
+                dict = reset();
+                size = min_code_size as u32 + 1;
+                prev = None;
+                continue;
+            }
+            if code == end {
+                return out;
+            }
+            let entry = match (&prev, dict.get(code)) {
+                (_, Some(e)) => e.clone(),
+                (Some(p), None) => {
+                    assert_eq!(code, dict.len());
+                    let mut e = p.clone();
+                    e.push(p[0]);
+                    e
+                }
+                (None, None) => panic!("非法码字 {code}"),
+            };
+            out.extend_from_slice(&entry);
+            if let Some(mut p) = prev.take()
+                && dict.len() < 4096
+            {
+                p.push(entry[0]);
+                dict.push(p);
+            }
+            if dict.len() == 1 << size && size < 12 {
+                size += 1;
+            }
+            prev = Some(entry);
+        }
+    }
+
+    #[test]
+    fn test_lzw_roundtrip() {
+        let mut rng = SmallRng::seed_from_u64(8);
+        // 随机数据会填满字典并触发清除码；大块同色数据产生长匹配
+        let noisy: Vec<u8> = (0..50_000).map(|_| rng.random_range(0..4)).collect();
+        let flat = vec![1u8; 30_000];
+        let frame = rasterize(&sample_game(), 5).pixels;
+        for data in [noisy, flat, frame, vec![], vec![3]] {
+            assert_eq!(lzw_decode(&lzw_encode(&data, 2), 2), data);
+        }
+    }
+
+    #[test]
+    fn test_gif_structure() {
+        let frames = [rasterize(&sample_game(), 2), rasterize(&sample_game(), 2)];
+        let gif = to_gif(&frames, &Palette::default(), 10);
+        assert_eq!(&gif[..6], b"GIF89a");
+        assert_eq!(u16::from_le_bytes([gif[6], gif[7]]), 12);
+        assert_eq!(u16::from_le_bytes([gif[8], gif[9]]), 8);
+        assert_eq!(*gif.last().unwrap(), 0x3B);
+
+        // 跳过头部（13 字节 + 颜色表 12 字节 + 循环扩展 19 字节）后解出第一帧
+        let mut at = 13 + 12 + 19;
+        assert_eq!(&gif[at..at + 2], &[0x21, 0xF9]);
+        at += 8;
+        assert_eq!(gif[at], 0x2C);
+        at += 10;
+        assert_eq!(gif[at], GIF_MIN_CODE_SIZE);
+        at += 1;
+        let mut data = Vec::new();
+        while gif[at] != 0 {
+            let len = gif[at] as usize;
+            data.extend_from_slice(&gif[at + 1..at + 1 + len]);
+            at += 1 + len;
+        }
+        assert_eq!(lzw_decode(&data, GIF_MIN_CODE_SIZE), frames[0].pixels);
+    }
+
+    #[test]
+    fn test_gif_writer_pop_and_keep() {
+        let a = rasterize(&sample_game(), 2);
+        let mut b = a.clone();
+        b.pixels.fill(FOOD);
+        let palette = Palette::default();
+
+        // 暂存区内的帧可以撤销
+        let mut gif = GifWriter::new(Vec::new(), &palette, 10, 2);
+        for frame in [&a, &b, &a, &b] {
+            gif.push(frame).unwrap();
+        }
+        assert!(gif.pop());
+        assert!(gif.pop());
+        gif.push(&b).unwrap();
+        assert_eq!(gif.finish().unwrap(), to_gif(&[a.clone(), b.clone(), b.clone()], &palette, 10));
+
+        // 超出 keep 的帧已写出，不能再撤销
+        let mut gif = GifWriter::new(Vec::new(), &palette, 10, 1);
+        gif.push(&a).unwrap();
+        gif.push(&b).unwrap();
+        assert!(gif.pop());
+        assert!(!gif.pop());
+        assert_eq!(gif.finish().unwrap(), to_gif(&[a], &palette, 10));
+    }
+}
@@ -0,0 +1,31 @@
+//! 贪吃蛇引擎
+//!
+//! 逐帧 O(1) 的核心数据结构（[`snake`]），以及终端渲染、游戏管理与红石移植工具。
+
+pub mod bot;
+pub mod cast;
+pub mod codec;
+pub mod config;
+pub mod env;
+pub mod game;
+pub mod image;
+pub mod multi;
+#[cfg(feature = "ai")]
+pub mod mcts;
+pub mod net;
+pub mod redstone;
+pub mod render;
+pub mod rng;
+pub mod snake;
+pub mod snapshot;
+pub mod svg;
+pub mod types;
+
+#[cfg(feature = "ai")]
+pub mod pathfinding;
+#[cfg(feature = "ai")]
+pub mod sim;
+#[cfg(feature = "ai")]
+pub mod tournament;
+#[cfg(feature = "ai")]
+pub mod traffic_rom;
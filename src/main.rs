@@ -1,32 +1,109 @@
-mod config;
-mod game;
-mod render;
-mod snake;
-mod types;
+use snake::cast::Recorder;
+use snake::config::MapConfig;
+use snake::game::Game;
+use snake::types::GameState;
+use std::io::Write;
+use std::time::Duration;
 
 #[cfg(feature = "ai")]
-mod pathfinding;
-
-use crate::config::MapConfig;
-use crate::game::Game;
-use crate::types::GameState;
-use std::time::Duration;
+use snake::pathfinding;
 
 /// 默认更新间隔（毫秒）
 const UPDATE_INTERVAL_MS: u64 = 50;
 
+/// 游戏中按 F2 保存快照的文件名
+const SNAPSHOT_FILE: &str = "snake.snap";
+
+/// 导出图像时每格的像素尺寸
+const CELL_PX: u32 = 8;
+
 fn main() -> std::io::Result<()> {
+    let mut args: Vec<String> = std::env::args().skip(1).collect();
+    // 以下选项可出现在任意位置：
+    // `--record <文件>` 录成 asciicast，`--gif <文件>` 导出 GIF 动画，`--png <目录>` 导出逐帧 PNG
+    let record_path = take_flag(&mut args, "--record");
+    let gif_path = take_flag(&mut args, "--gif");
+    let png_dir = take_flag(&mut args, "--png");
+    // `--debug`：在棋盘旁显示 A* 的距离热力图、计划路径与搜索统计
+    #[cfg(feature = "ai")]
+    let debug = match args.iter().position(|a| a == "--debug") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+    let record = record_path.as_deref();
+    // `--record` 只对有画面的对局有意义，其余子命令直接报错而不是静默忽略
+    const NO_RECORD: [&str; 9] = ["asm", "export", "serve", "play", "svg", "rom", "sim", "tournament", "frames"];
+    if record.is_some()
+        && let Some(cmd) = args.first().filter(|cmd| NO_RECORD.contains(&cmd.as_str()))
+    {
+        eprintln!("--record 只能用于单人、versus、race、connect 与 bot 对局，不支持 {cmd}");
+        return Ok(());
+    }
+    // `--gif` / `--png` 只在单人对局（含 `load`）与 `frames` 重放中逐帧采集
+    const NO_CAPTURE: [&str; 12] = [
+        "asm", "export", "versus", "serve", "connect", "bot", "play", "svg", "race", "rom", "sim", "tournament",
+    ];
+    if (gif_path.is_some() || png_dir.is_some())
+        && let Some(cmd) = args.first().filter(|cmd| NO_CAPTURE.contains(&cmd.as_str()))
+    {
+        eprintln!("--gif 与 --png 只能用于单人对局与 frames，不支持 {cmd}");
+        return Ok(());
+    }
+    match args.first().map(String::as_str) {
+        Some("asm") => return print_asm(&args[1..]),
+        Some("export") => return export_memory(&args[1..]),
+        Some("versus") => return run_versus(record),
+        Some("serve") => return serve(&args[1..]),
+        Some("connect") => return connect(&args[1..], record),
+        Some("bot") => return run_bot(&args[1..], record),
+        Some("play") => return play_cast(&args[1..]),
+        Some("svg") => return export_svg(&args[1..]),
+        Some("frames") => return export_frames(&args[1..], gif_path.as_deref(), png_dir.as_deref()),
+        #[cfg(feature = "ai")]
+        Some("race") => return run_race(record),
+        #[cfg(feature = "ai")]
+        Some("rom") => return export_traffic_rom(&args[1..]),
+        #[cfg(feature = "ai")]
+        Some("sim") => return run_sim(args.split_off(1)),
+        #[cfg(feature = "ai")]
+        Some("tournament") => return run_tournament(args.split_off(1)),
+        _ => {}
+    }
+
+    // `snake load <快照>` 从存档继续，否则开新局
+    let mut game = match args.as_slice() {
+        [cmd, path, ..] if cmd == "load" => {
+            Game::restore(&std::fs::read(path)?).map_err(std::io::Error::other)?
+        }
+        _ => Game::new(MapConfig::new(16, 16), 3, 1),
+    };
+
+    let mut recorder = open_recorder(record, game.snake().config())?;
+
+    // 导出图像时每个改变局面的 tick 采一帧，回退时撤掉对应的帧
+    let mut capture = Capture::open(gif_path.as_deref(), png_dir.as_deref())?;
+    capture.push(&game)?;
+
     crossterm::terminal::enable_raw_mode()?;
 
-    let config = MapConfig::new(16, 16);
-    let mut game = Game::new(config, 3, 1);
     let mut render_buf = String::new();
+    // Backspace 回退后暂停，Space 或方向键继续
+    let mut paused = false;
+    // 调试模式下渲染时搜索得到的计划，下一 tick 直接采用，避免重复搜索
+    #[cfg(feature = "ai")]
+    let mut plan: Option<pathfinding::Plan> = None;
+    // 非调试模式下缓存计划，局面与计划一致时不重新搜索
+    #[cfg(feature = "ai")]
+    let mut planner = pathfinding::Planner::new();
 
     // 初始渲染
     game.render(&mut render_buf);
-    print!("{render_buf}");
+    show(&render_buf, &mut recorder)?;
 
-    loop {
+    'game: loop {
         #[cfg(not(feature = "ai"))]
         let mut direction = None;
 
@@ -40,39 +117,709 @@ fn main() -> std::io::Result<()> {
                         .contains(crossterm::event::KeyModifiers::CONTROL)
                 {
                     println!("Interrupted after {} moves", game.moves_count());
-                    crossterm::terminal::disable_raw_mode()?;
-                    return Ok(());
+                    break 'game;
+                }
+                match key_event.code {
+                    KeyCode::F(2) => {
+                        std::fs::write(SNAPSHOT_FILE, game.snapshot())?;
+                        continue;
+                    }
+                    KeyCode::Backspace => {
+                        if game.rewind() {
+                            capture.pop()?;
+                        }
+                        paused = true;
+                        #[cfg(feature = "ai")]
+                        {
+                            plan = None;
+                        }
+                        continue;
+                    }
+                    KeyCode::Char(' ') => {
+                        paused = !paused;
+                        continue;
+                    }
+                    _ => {}
                 }
                 #[cfg(not(feature = "ai"))]
                 {
-                    direction = game::key_to_direction(key_event.code);
+                    direction = snake::game::key_to_direction(key_event.code);
+                    paused &= direction.is_none();
                 }
             }
         }
 
-        #[cfg(feature = "ai")]
-        let direction = pathfinding::next_dir(game.snake());
+        if !paused {
+            #[cfg(feature = "ai")]
+            let direction = match plan.take() {
+                Some(plan) => plan.direction(),
+                None => planner.next_dir(game.snake()),
+            };
 
-        let end_state = game.tick(direction);
+            let recorded = game.recorded_ticks();
+            let end_state = game.tick(direction);
+            if game.recorded_ticks() > recorded {
+                capture.push(&game)?;
+            }
 
-        if matches!(end_state, GameState::Over | GameState::Won) {
-            let label = match end_state {
-                GameState::Won => "You win",
-                _ => "Game over",
-            };
-            println!("{label} after {} moves", game.moves_count());
-            break;
+            if matches!(end_state, GameState::Over | GameState::Won) {
+                let label = match end_state {
+                    GameState::Won => "You win",
+                    _ => "Game over",
+                };
+                println!("{label} after {} moves (Backspace to rewind)", game.moves_count());
+                // 结束后仍可回退查看；其他任意键退出
+                if !wait_for_rewind()? {
+                    break;
+                }
+                if game.rewind() {
+                    capture.pop()?;
+                }
+                paused = true;
+            }
         }
 
         game.render(&mut render_buf);
         #[cfg(feature = "ai")]
+        if debug {
+            plan = pathfinding::plan(game.snake(), &pathfinding::AiConfig::default());
+            if let Some(plan) = &plan {
+                snake::render::render_debug(game.snake(), plan, &mut render_buf);
+            }
+        }
+        #[cfg(feature = "ai")]
         render_buf.push_str("[AI] ");
-        print!("{render_buf}");
+        show(&render_buf, &mut recorder)?;
 
         std::thread::sleep(Duration::from_millis(UPDATE_INTERVAL_MS));
     }
 
     crossterm::terminal::disable_raw_mode()?;
+    capture.finish()
+}
+
+/// `snake frames <快照> [输入] --gif <文件> | --png <目录>`：不开终端界面，从快照重放并导出图像
+///
+/// 快照带有 RNG 状态，因此同样的输入总是得到同样的对局。输入每个字符对应一个 tick：
+/// `U` / `D` / `L` / `R` 为方向，`.` 为不按键；对局结束时停止。
+fn export_frames(args: &[String], gif_path: Option<&str>, png_dir: Option<&str>) -> std::io::Result<()> {
+    use snake::types::Direction;
+
+    let usage = || {
+        eprintln!("用法: snake frames <快照> [输入 UDLR.] --gif <文件> | --png <目录>");
+        Ok(())
+    };
+    let (path, inputs) = match args {
+        [path] => (path, ""),
+        [path, inputs] => (path, inputs.as_str()),
+        _ => return usage(),
+    };
+    if gif_path.is_none() && png_dir.is_none() {
+        return usage();
+    }
+    let inputs: Option<Vec<Option<Direction>>> = inputs
+        .chars()
+        .map(|c| match c.to_ascii_uppercase() {
+            'U' => Some(Some(Direction::Up)),
+            'D' => Some(Some(Direction::Down)),
+            'L' => Some(Some(Direction::Left)),
+            'R' => Some(Some(Direction::Right)),
+            '.' => Some(None),
+            _ => None,
+        })
+        .collect();
+    let Some(inputs) = inputs else { return usage() };
+
+    let mut game = Game::restore(&std::fs::read(path)?).map_err(std::io::Error::other)?;
+    let mut capture = Capture::open(gif_path, png_dir)?;
+    capture.push(&game)?;
+    for direction in inputs {
+        let recorded = game.recorded_ticks();
+        let state = game.tick(direction);
+        if game.recorded_ticks() > recorded {
+            capture.push(&game)?;
+        }
+        if matches!(state, GameState::Over | GameState::Won) {
+            break;
+        }
+    }
+    capture.finish()
+}
+
+/// `--gif` / `--png` 的逐帧导出：PNG 采到即写盘，GIF 只暂存仍可回退的帧
+struct Capture {
+    gif: Option<snake::image::GifWriter<std::io::BufWriter<std::fs::File>>>,
+    png_dir: Option<std::path::PathBuf>,
+    palette: snake::image::Palette,
+    /// 当前保留的帧数
+    frames: usize,
+}
+
+impl Capture {
+    fn open(gif_path: Option<&str>, png_dir: Option<&str>) -> std::io::Result<Self> {
+        let palette = snake::image::Palette::default();
+        let gif = match gif_path {
+            Some(path) => {
+                let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+                let delay_cs = (UPDATE_INTERVAL_MS / 10) as u16;
+                // 最多回退 HISTORY_LIMIT 个 tick，更早的帧可以直接写出
+                let keep = snake::game::HISTORY_LIMIT;
+                Some(snake::image::GifWriter::new(file, &palette, delay_cs, keep))
+            }
+            None => None,
+        };
+        if let Some(dir) = png_dir {
+            std::fs::create_dir_all(dir)?;
+        }
+        Ok(Capture { gif, png_dir: png_dir.map(Into::into), palette, frames: 0 })
+    }
+
+    fn png_path(&self, i: usize) -> Option<std::path::PathBuf> {
+        self.png_dir.as_ref().map(|dir| dir.join(format!("frame_{i:05}.png")))
+    }
+
+    /// 采下当前局面
+    fn push(&mut self, game: &Game) -> std::io::Result<()> {
+        if self.gif.is_none() && self.png_dir.is_none() {
+            return Ok(());
+        }
+        let frame = snake::image::rasterize(game.snake(), CELL_PX);
+        if let Some(gif) = &mut self.gif {
+            gif.push(&frame)?;
+        }
+        if let Some(path) = self.png_path(self.frames) {
+            std::fs::write(path, snake::image::to_png(&frame, &self.palette))?;
+        }
+        self.frames += 1;
+        Ok(())
+    }
+
+    /// 撤掉最近一帧（对应一次成功的回退）
+    fn pop(&mut self) -> std::io::Result<()> {
+        if self.frames == 0 {
+            return Ok(());
+        }
+        self.frames -= 1;
+        if let Some(gif) = &mut self.gif {
+            gif.pop();
+        }
+        if let Some(path) = self.png_path(self.frames) {
+            std::fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        if let Some(gif) = self.gif {
+            gif.finish()?.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// 按 `--record` 的路径创建录像文件，终端尺寸比地图多出边框与状态行
+fn open_recorder(
+    path: Option<&str>,
+    config: &MapConfig,
+) -> std::io::Result<Option<Recorder<std::io::BufWriter<std::fs::File>>>> {
+    let Some(path) = path else { return Ok(None) };
+    let file = std::io::BufWriter::new(std::fs::File::create(path)?);
+    Ok(Some(Recorder::new(file, config.width + 8, config.height + 4)?))
+}
+
+/// 从参数中取出 `name <值>` 并移除这两项
+fn take_flag(args: &mut Vec<String>, name: &str) -> Option<String> {
+    match args.iter().position(|a| a == name) {
+        Some(i) if i + 1 < args.len() => Some(args.drain(i..i + 2).nth(1).unwrap()),
+        _ => None,
+    }
+}
+
+/// 对战中一条蛇的操控方式
+#[derive(Clone, Copy)]
+enum Controller {
+    /// 方向键
+    Arrows,
+    /// WASD
+    Wasd,
+    /// A* 寻路 AI
+    #[cfg(feature = "ai")]
+    Ai,
+}
+
+impl Controller {
+    fn label(self) -> &'static str {
+        match self {
+            Controller::Arrows => "Player 1",
+            Controller::Wasd => "Player 2",
+            #[cfg(feature = "ai")]
+            Controller::Ai => "AI",
+        }
+    }
+}
+
+/// `snake versus`：同一键盘双人对战，1 号玩家用方向键，2 号玩家用 WASD
+fn run_versus(record: Option<&str>) -> std::io::Result<()> {
+    run_match(MapConfig::new(24, 16), [Controller::Arrows, Controller::Wasd], record)
+}
+
+/// `snake race`：方向键玩家与 AI 在同一张地图上抢食物（地图需不超过 256 格）
+#[cfg(feature = "ai")]
+fn run_race(record: Option<&str>) -> std::io::Result<()> {
+    run_match(MapConfig::new(16, 16), [Controller::Arrows, Controller::Ai], record)
+}
+
+/// 两条蛇的对局主循环，结束后输出记分板
+fn run_match(config: MapConfig, controllers: [Controller; 2], record: Option<&str>) -> std::io::Result<()> {
+    use snake::multi::{MultiSnakeGame, Outcome};
+
+    let mut recorder = open_recorder(record, &config)?;
+    let mut rng = snake::rng::GameRng::from_entropy();
+    let mut game = MultiSnakeGame::new(config, 2, 3, 2, &mut rng);
+    let mut render_buf = String::new();
+
+    crossterm::terminal::enable_raw_mode()?;
+    snake::render::render_multi(&game, &mut render_buf);
+    show(&render_buf, &mut recorder)?;
+
+    let outcome = loop {
+        let mut inputs = [None; 2];
+        while crossterm::event::poll(Duration::from_millis(0))? {
+            if let Ok(crossterm::event::Event::Key(key_event)) = crossterm::event::read() {
+                if key_event.code == crossterm::event::KeyCode::Char('c')
+                    && key_event
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL)
+                {
+                    crossterm::terminal::disable_raw_mode()?;
+                    return Ok(());
+                }
+                for (input, controller) in inputs.iter_mut().zip(controllers) {
+                    let dir = match controller {
+                        Controller::Arrows => snake::game::key_to_direction(key_event.code),
+                        Controller::Wasd => snake::game::wasd_to_direction(key_event.code),
+                        #[cfg(feature = "ai")]
+                        Controller::Ai => None,
+                    };
+                    *input = dir.or(*input);
+                }
+            }
+        }
+        // AI 等人类玩家起步后才出发
+        #[cfg(feature = "ai")]
+        if game.state(0) != GameState::Ready || inputs[0].is_some() {
+            for (i, controller) in controllers.iter().enumerate() {
+                if matches!(controller, Controller::Ai) {
+                    inputs[i] = snake::pathfinding::next_dir_multi(&game, i);
+                }
+            }
+        }
+
+        game.update(&inputs, &mut rng);
+        snake::render::render_multi(&game, &mut render_buf);
+        show(&render_buf, &mut recorder)?;
+        if let Some(outcome) = game.outcome() {
+            break outcome;
+        }
+        // 对战稍慢一些，方便人类操作
+        std::thread::sleep(Duration::from_millis(UPDATE_INTERVAL_MS * 3));
+    };
+
+    crossterm::terminal::disable_raw_mode()?;
+    println!("{:<10}{:>7}{:>8}  Status", "", "Score", "Length");
+    for (i, controller) in controllers.iter().enumerate() {
+        let status = match game.state(i) {
+            GameState::Over => "out",
+            GameState::Won => "filled the board",
+            _ => "alive",
+        };
+        println!("{:<10}{:>7}{:>8}  {status}", controller.label(), game.score(i), game.length(i));
+    }
+    match outcome {
+        Outcome::Winner(i) => println!("{} wins", controllers[i].label()),
+        Outcome::Draw => println!("Draw"),
+    }
+    Ok(())
+}
+
+/// `snake serve [端口] [玩家数]`：在 127.0.0.1 上运行联机服务器（默认端口 7878、2 人）
+fn serve(args: &[String]) -> std::io::Result<()> {
+    use snake::net::{DEFAULT_PORT, Server};
+
+    let config = MapConfig::new(24, 16);
+    let port = args.first().and_then(|p| p.parse().ok()).unwrap_or(DEFAULT_PORT);
+    let players = args.get(1).and_then(|n| n.parse().ok()).unwrap_or(2);
+    if !(1..=snake::net::max_players(&config)).contains(&players) {
+        eprintln!("用法: snake serve [端口] [玩家数 1..={}]", snake::net::max_players(&config));
+        return Ok(());
+    }
+    let server = Server::bind(("127.0.0.1", port))?;
+    println!("Listening on {}, waiting for {players} players", server.local_addr()?);
+    let outcome = server.run(
+        config,
+        players,
+        Duration::from_millis(UPDATE_INTERVAL_MS * 3),
+        snake::rng::GameRng::from_entropy(),
+    )?;
+    println!("{outcome:?}");
+    Ok(())
+}
+
+/// `snake connect [地址]`：连接联机服务器（默认 127.0.0.1:7878），用方向键操控自己的蛇
+fn connect(args: &[String], record: Option<&str>) -> std::io::Result<()> {
+    use snake::multi::Outcome;
+    use snake::net::{ClientMessage, DEFAULT_PORT, ServerMessage};
+    use std::io::{BufRead, Write};
+
+    let addr = args.first().cloned().unwrap_or_else(|| format!("127.0.0.1:{DEFAULT_PORT}"));
+    let (mut board, reader, mut writer) = snake::net::connect(addr.as_str())?;
+    let mut recorder = open_recorder(record, board.config())?;
+    println!("Connected as player {}, waiting for other players...", board.player() + 1);
+
+    // 后台线程逐行读取服务器消息，连接关闭时发送 None
+    let (tx, rx) = std::sync::mpsc::channel();
+    std::thread::spawn(move || {
+        for line in reader.lines() {
+            let Ok(line) = line else { break };
+            if let Ok(msg) = ServerMessage::parse(&line)
+                && tx.send(Some(msg)).is_err()
+            {
+                return;
+            }
+        }
+        let _ = tx.send(None);
+    });
+
+    crossterm::terminal::enable_raw_mode()?;
+    let mut render_buf = String::new();
+    let result = loop {
+        while crossterm::event::poll(Duration::from_millis(0))? {
+            if let Ok(crossterm::event::Event::Key(key_event)) = crossterm::event::read() {
+                let msg = if key_event.code == crossterm::event::KeyCode::Char('c')
+                    && key_event
+                        .modifiers
+                        .contains(crossterm::event::KeyModifiers::CONTROL)
+                {
+                    ClientMessage::Quit
+                } else if let Some(dir) = snake::game::key_to_direction(key_event.code) {
+                    ClientMessage::Dir(dir)
+                } else {
+                    continue;
+                };
+                writeln!(writer, "{msg}")?;
+                if msg == ClientMessage::Quit {
+                    crossterm::terminal::disable_raw_mode()?;
+                    return Ok(());
+                }
+            }
+        }
+
+        let mut redraw = false;
+        let mut closed = false;
+        for msg in rx.try_iter() {
+            match msg {
+                Some(msg) => redraw |= board.apply(&msg),
+                None => closed = true,
+            }
+        }
+        if redraw {
+            board.render(&mut render_buf);
+            show(&render_buf, &mut recorder)?;
+        }
+        if let Some(outcome) = board.outcome() {
+            break Some(outcome);
+        }
+        if closed {
+            break None;
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+    crossterm::terminal::disable_raw_mode()?;
+
+    match result {
+        Some(Outcome::Winner(i)) if i == board.player() => println!("You win"),
+        Some(Outcome::Winner(i)) => println!("Player {} wins", i + 1),
+        Some(Outcome::Draw) => println!("Draw"),
+        None => println!("Connection closed"),
+    }
+    Ok(())
+}
+
+/// 输出一帧，同时写入录像（如有）
+fn show(frame: &str, recorder: &mut Option<Recorder<impl Write>>) -> std::io::Result<()> {
+    print!("{frame}");
+    if let Some(recorder) = recorder {
+        recorder.frame(frame)?;
+    }
+    Ok(())
+}
+
+/// `snake play <录像> [倍速]`：在终端回放 asciicast 录像
+fn play_cast(args: &[String]) -> std::io::Result<()> {
+    let Some(path) = args.first() else {
+        eprintln!("用法: snake play <录像.cast> [倍速]");
+        return Ok(());
+    };
+    let speed = args.get(1).and_then(|s| s.parse().ok()).filter(|&s: &f64| s > 0.0).unwrap_or(1.0);
+    let cast = snake::cast::parse(&std::fs::read_to_string(path)?).map_err(std::io::Error::other)?;
+    snake::cast::play(&cast, speed, &mut std::io::stdout().lock())
+}
+
+/// 外部 bot 每个 tick 的回复时限
+const BOT_TIMEOUT_MS: u64 = 100;
+
+/// `snake bot <程序> [参数...]`：由外部进程经 stdin/stdout 操控（协议见 `src/bot.rs`）
+fn run_bot(args: &[String], record: Option<&str>) -> std::io::Result<()> {
+    use snake::bot::{self, BotProcess};
+
+    let Some((program, bot_args)) = args.split_first() else {
+        eprintln!("用法: snake bot <程序> [参数...]");
+        return Ok(());
+    };
+    let mut process = BotProcess::spawn(std::process::Command::new(program).args(bot_args))?;
+    let mut game = Game::new(MapConfig::new(16, 16), 3, 1);
+    let mut recorder = open_recorder(record, game.snake().config())?;
+    let mut render_buf = String::new();
+    // bot 对局不限速，录像按每 tick 一个更新间隔计时，回放时与单人对局同速
+    let mut recorded = Ok(());
+    let mut record_frame = |game: &Game, render_buf: &mut String| {
+        if let Some(recorder) = &mut recorder
+            && recorded.is_ok()
+        {
+            game.render(render_buf);
+            let time = game.moves_count() as f64 * UPDATE_INTERVAL_MS as f64 / 1000.0;
+            recorded = recorder.frame_at(time, render_buf);
+        }
+    };
+    record_frame(&game, &mut render_buf);
+    let report = bot::run_with(
+        &mut game,
+        &mut process,
+        Duration::from_millis(BOT_TIMEOUT_MS),
+        100_000,
+        |game| record_frame(game, &mut render_buf),
+    );
+    recorded?;
+    game.render(&mut render_buf);
+    print!("{render_buf}");
+    println!(
+        "{:?} after {} moves, length {}, {} timeouts, {} invalid replies{}",
+        report.state,
+        report.moves,
+        report.length,
+        report.timeouts,
+        report.invalid_replies,
+        if report.exited { ", bot exited" } else { "" },
+    );
+    Ok(())
+}
+
+/// 阻塞等待一个按键：Backspace 返回 true，其他键返回 false
+fn wait_for_rewind() -> std::io::Result<bool> {
+    loop {
+        if let crossterm::event::Event::Key(key_event) = crossterm::event::read()? {
+            return Ok(key_event.code == crossterm::event::KeyCode::Backspace);
+        }
+    }
+}
+
+/// 解析可选的 `宽 高` 参数（缺省 16×16）
+fn parse_size(args: &[String]) -> Option<MapConfig> {
+    match args {
+        [w, h, ..] => {
+            let (w, h) = (w.parse().ok()?, h.parse().ok()?);
+            (w > 0 && h > 0).then(|| MapConfig::new(w, h))
+        }
+        _ => Some(MapConfig::new(16, 16)),
+    }
+}
+
+/// `snake asm [宽 高]`：输出 tick 逻辑的红石汇编清单（默认 16×16）
+fn print_asm(args: &[String]) -> std::io::Result<()> {
+    let Some(config) = parse_size(args) else {
+        eprintln!("用法: snake asm [宽 高]");
+        return Ok(());
+    };
+    if !snake::redstone::layout::supports(&config) {
+        eprintln!(
+            "RS8 布局要求总格数 ≤ 256 且宽度为 2 的幂（当前 {}×{}）",
+            config.width, config.height
+        );
+        return Ok(());
+    }
+    print!("{}", snake::redstone::codegen::generate(&config));
+    Ok(())
+}
+
+/// `snake export <前缀>`：把新开局的内存映像写成 `<前缀>.bin` / `.mcfunction` / `.schem`
+fn export_memory(args: &[String]) -> std::io::Result<()> {
+    use snake::redstone::export;
+
+    let Some(prefix) = args.first() else {
+        eprintln!("用法: snake export <输出文件前缀>");
+        return Ok(());
+    };
+    let game = Game::new(MapConfig::new(16, 16), 3, 1);
+    let image = export::memory_image(game.snake());
+    let layout = export::BlockLayout::default();
+    std::fs::write(format!("{prefix}.bin"), &image)?;
+    std::fs::write(format!("{prefix}.mcfunction"), export::to_mcfunction(&image, &layout))?;
+    std::fs::write(format!("{prefix}.schem"), export::to_schematic(&image, &layout))?;
+    Ok(())
+}
+
+/// `snake svg <快照> <输出.svg> [--traffic]`：把 F2 保存的局面画成 SVG，可叠加交规箭头（需 `ai` 特性）
+fn export_svg(args: &[String]) -> std::io::Result<()> {
+    let traffic = args.iter().any(|a| a == "--traffic");
+    let paths: Vec<&String> = args.iter().filter(|a| *a != "--traffic").collect();
+    let [snapshot, out] = paths[..] else {
+        eprintln!("用法: snake svg <快照> <输出.svg> [--traffic]");
+        return Ok(());
+    };
+    let game = Game::restore(&std::fs::read(snapshot)?).map_err(std::io::Error::other)?;
+    let palette = snake::image::Palette::default();
+    #[cfg(feature = "ai")]
+    let svg = {
+        use snake::pathfinding::TrafficRules;
+        let config = game.snake().config().clone();
+        let rules = move |hash| config.allowed(hash);
+        snake::svg::render(game.snake(), CELL_PX * 4, &palette, traffic.then_some(&rules as _))
+    };
+    #[cfg(not(feature = "ai"))]
+    let svg = {
+        if traffic {
+            eprintln!("--traffic 需要启用 ai 特性，已忽略");
+        }
+        snake::svg::render(game.snake(), CELL_PX * 4, &palette, None)
+    };
+    std::fs::write(out, svg)
+}
 
+/// `snake sim [局数] [--nodes N] [--deadline-ms M] [--heuristic traffic|manhattan] [--tie deep|shallow]
+/// [--strategy greedy|safe-tail] [--mcts 迭代数]`
+///
+/// 以种子 0..局数 无界面地跑 16×16 对局，输出胜负、长度与每次决策的展开数和耗时
+#[cfg(feature = "ai")]
+fn run_sim(mut args: Vec<String>) -> std::io::Result<()> {
+    use pathfinding::{AiConfig, Heuristic, Strategy, TieBreak};
+    use snake::mcts::MctsConfig;
+
+    let usage = || {
+        eprintln!(
+            "用法: snake sim [局数] [--nodes N] [--deadline-ms M] [--heuristic traffic|manhattan] [--tie deep|shallow] [--strategy greedy|safe-tail] [--mcts 迭代数]"
+        );
+        Ok(())
+    };
+    let mut ai = AiConfig::default();
+    if let Some(nodes) = take_flag(&mut args, "--nodes") {
+        let Ok(nodes) = nodes.parse() else { return usage() };
+        ai.max_expanded = nodes;
+    }
+    if let Some(ms) = take_flag(&mut args, "--deadline-ms") {
+        let Ok(ms) = ms.parse() else { return usage() };
+        ai.deadline = Some(Duration::from_millis(ms));
+    }
+    match take_flag(&mut args, "--heuristic").as_deref() {
+        None | Some("traffic") => {}
+        Some("manhattan") => ai.heuristic = Heuristic::Manhattan,
+        Some(_) => return usage(),
+    }
+    match take_flag(&mut args, "--tie").as_deref() {
+        None | Some("deep") => {}
+        Some("shallow") => ai.tie_break = TieBreak::Shallowest,
+        Some(_) => return usage(),
+    }
+    match take_flag(&mut args, "--strategy").as_deref() {
+        None | Some("greedy") => {}
+        Some("safe-tail") => ai.strategy = Strategy::SafeTail,
+        Some(_) => return usage(),
+    }
+    let mcts = match take_flag(&mut args, "--mcts").map(|n| n.parse()) {
+        None => None,
+        Some(Ok(iterations)) => Some(MctsConfig { iterations, ..MctsConfig::default() }),
+        Some(Err(_)) => return usage(),
+    };
+    let games = match args.first().map(|s| s.parse()) {
+        None => 20,
+        Some(Ok(n)) => n,
+        Some(Err(_)) => return usage(),
+    };
+
+    let options = snake::sim::SimOptions::default();
+    let results = match &mcts {
+        Some(mcts) => {
+            println!("{mcts:?}");
+            snake::sim::run_mcts(mcts, &options, games)
+        }
+        None => {
+            println!("{ai:?}");
+            snake::sim::run(&ai, &options, games)
+        }
+    };
+    println!("{}", snake::sim::Summary::new(&results));
+    Ok(())
+}
+
+/// `snake tournament [每个策略的局数] [--threads N] [--ticks N] [--mcts 迭代数] [--out 报告]`
+///
+/// greedy 与 safe-tail（以及给出 `--mcts` 时的 MCTS）在种子 0..局数 的 16×16 棋盘上各跑一局，
+/// 输出报告，`--out` 时同时写入文件
+#[cfg(feature = "ai")]
+fn run_tournament(mut args: Vec<String>) -> std::io::Result<()> {
+    use pathfinding::{AiConfig, Strategy};
+    use snake::mcts::MctsConfig;
+    use snake::tournament::{self, Contestant, TournamentOptions};
+
+    let usage = || {
+        eprintln!("用法: snake tournament [局数] [--threads N] [--ticks N] [--mcts 迭代数] [--out 报告文件]");
+        Ok(())
+    };
+    let mut options = TournamentOptions::default();
+    if let Some(threads) = take_flag(&mut args, "--threads") {
+        let Ok(threads @ 1..) = threads.parse() else { return usage() };
+        options.threads = threads;
+    }
+    if let Some(ticks) = take_flag(&mut args, "--ticks") {
+        let Ok(ticks) = ticks.parse() else { return usage() };
+        options.sim.max_ticks = ticks;
+    }
+    let mut contestants = vec![
+        Contestant::AStar(AiConfig::default()),
+        Contestant::AStar(AiConfig { strategy: Strategy::SafeTail, ..AiConfig::default() }),
+    ];
+    if let Some(iterations) = take_flag(&mut args, "--mcts") {
+        let Ok(iterations) = iterations.parse() else { return usage() };
+        contestants.push(Contestant::Mcts(MctsConfig { iterations, ..MctsConfig::default() }));
+    }
+    let out = take_flag(&mut args, "--out");
+    match args.first().map(|s| s.parse()) {
+        None => {}
+        Some(Ok(games)) => options.seeds = 0..games,
+        Some(Err(_)) => return usage(),
+    }
+
+    let report = tournament::report(&tournament::run(&contestants, &options), &options);
+    print!("{report}");
+    if let Some(out) = out {
+        std::fs::write(out, report)?;
+    }
+    Ok(())
+}
+
+/// `snake rom <前缀> [宽 高]`：把交规与邻接表写成 `<前缀>.bin` / `<前缀>.hex`
+#[cfg(feature = "ai")]
+fn export_traffic_rom(args: &[String]) -> std::io::Result<()> {
+    let (Some(prefix), Some(config)) = (args.first(), parse_size(args.get(1..).unwrap_or(&[])))
+    else {
+        eprintln!("用法: snake rom <输出文件前缀> [宽 高]");
+        return Ok(());
+    };
+    if config.total_size() > snake::redstone::isa::PAGE_SIZE {
+        eprintln!("用法: snake rom <输出文件前缀> [宽 高]（总格数 ≤ {}）", snake::redstone::isa::PAGE_SIZE);
+        return Ok(());
+    }
+    let rom = snake::traffic_rom::TrafficRom::generate(&config);
+    std::fs::write(format!("{prefix}.bin"), rom.as_bytes())?;
+    std::fs::write(format!("{prefix}.hex"), rom.to_intel_hex())?;
     Ok(())
 }
@@ -0,0 +1,295 @@
+//! 蒙特卡洛树搜索 AI
+//!
+//! 与只瞄准当前可见食物的确定性 A*（[`pathfinding`](crate::pathfinding)）不同，
+//! MCTS 在克隆出的 [`SnakeGame`] 上做随机 rollout，食物的随机刷新也一并模拟。
+//!
+//! 采用开环（open-loop）树：节点只记录动作序列，每次迭代从根局面重新模拟，
+//! 因此同一节点在不同迭代中会遇到不同的食物刷新。选择阶段使用 UCT，
+//! 最终选择访问次数最多的根动作。
+
+use crate::pathfinding::TrafficRules;
+use crate::rng::GameRng;
+use crate::snake::SnakeGame;
+use crate::types::{CellState, Direction, GameState};
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+
+/// 四个动作；下标即子节点表的下标
+const ACTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+
+/// 空子节点
+const NONE: u32 = u32::MAX;
+
+/// MCTS 的搜索参数
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MctsConfig {
+    /// 每次决策的迭代次数（每次迭代扩展一个节点并做一次 rollout）
+    pub iterations: u32,
+    /// 扩展节点之后 rollout 的最大步数
+    pub rollout_depth: u32,
+    /// UCT 探索系数
+    pub exploration: f64,
+    /// 每步的折扣因子：越早吃到食物、越晚死亡越好
+    pub discount: f64,
+    /// 模拟食物刷新与随机走子的种子
+    pub seed: u64,
+}
+
+impl Default for MctsConfig {
+    fn default() -> Self {
+        MctsConfig {
+            iterations: 1000,
+            rollout_depth: 32,
+            exploration: 1.0,
+            discount: 0.95,
+            seed: 0,
+        }
+    }
+}
+
+/// 一次决策的结果与诊断
+#[derive(Debug, Clone, PartialEq)]
+pub struct MctsResult {
+    /// 访问次数最多的根动作（`iterations` 为 0 时为 None）
+    pub direction: Option<Direction>,
+    /// 各根动作的访问次数与平均回报（未扩展的动作不列出）
+    pub children: Vec<(Direction, u32, f64)>,
+    /// 树中的节点数（含根）
+    pub nodes: usize,
+    pub elapsed: Duration,
+}
+
+/// 树节点（存放在 arena 中）
+#[derive(Debug, Clone)]
+struct Node {
+    /// 到达该节点后的朝向（根节点为游戏当前朝向）
+    dir: Option<Direction>,
+    children: [u32; 4],
+    visits: u32,
+    /// 回报之和
+    value: f64,
+}
+
+impl Node {
+    fn new(dir: Option<Direction>) -> Self {
+        Node { dir, children: [NONE; 4], visits: 0, value: 0.0 }
+    }
+
+    /// 该节点处的合法动作下标（排除 180 度掉头，它等同于继续直行）
+    fn legal(&self) -> impl Iterator<Item = usize> + '_ {
+        (0..4).filter(|&i| self.dir.is_none_or(|d| ACTIONS[i] != d.opposite()))
+    }
+}
+
+/// 带种子的 MCTS 控制器；RNG 在决策之间延续，同一种子的整局决策可复现
+#[derive(Debug, Clone)]
+pub struct Mcts {
+    config: MctsConfig,
+    rng: GameRng,
+}
+
+impl Mcts {
+    pub fn new(config: MctsConfig) -> Self {
+        Mcts { config, rng: GameRng::seed_from_u64(config.seed) }
+    }
+
+    pub fn config(&self) -> &MctsConfig {
+        &self.config
+    }
+
+    /// 返回下一步方向
+    pub fn next_dir(&mut self, snake: &SnakeGame) -> Option<Direction> {
+        self.search(snake).direction
+    }
+
+    /// 从当前局面搜索 `iterations` 次
+    pub fn search(&mut self, snake: &SnakeGame) -> MctsResult {
+        let start = Instant::now();
+        let mut nodes = vec![Node::new(snake.direction())];
+        let mut path = Vec::new();
+
+        for _ in 0..self.config.iterations {
+            let mut game = snake.clone();
+            let mut at = 0u32;
+            let mut depth = 0;
+            let mut ret = 0.0;
+            let mut alive = true;
+            path.clear();
+            path.push(at);
+
+            // 选择 + 扩展：沿 UCT 下降，遇到未尝试的动作就扩展它
+            loop {
+                let node = &nodes[at as usize];
+                let mut untried = [0; 4];
+                let mut count = 0;
+                for i in node.legal().filter(|&i| node.children[i] == NONE) {
+                    untried[count] = i;
+                    count += 1;
+                }
+                let (action, expand) = if count == 0 {
+                    (self.select(&nodes, at), false)
+                } else {
+                    (untried[self.rng.random_range(0..count)], true)
+                };
+                let (reward, done) = self.step(&mut game, ACTIONS[action], depth);
+                ret += reward;
+                depth += 1;
+
+                let child = nodes[at as usize].children[action];
+                at = if child == NONE {
+                    let child = nodes.len() as u32;
+                    nodes.push(Node::new(Some(ACTIONS[action])));
+                    nodes[at as usize].children[action] = child;
+                    child
+                } else {
+                    child
+                };
+                path.push(at);
+                if done {
+                    alive = false;
+                    break;
+                }
+                if expand {
+                    break;
+                }
+            }
+
+            // rollout：在不立即撞上的方向中随机走
+            if alive {
+                for _ in 0..self.config.rollout_depth {
+                    let Some(dir) = self.rollout_dir(&game) else {
+                        // 四面受困，下一步必死
+                        ret -= self.config.discount.powi(depth as i32);
+                        break;
+                    };
+                    let (reward, done) = self.step(&mut game, dir, depth);
+                    ret += reward;
+                    depth += 1;
+                    if done {
+                        break;
+                    }
+                }
+            }
+
+            // 回传：同一迭代中路径上所有节点共享整条回报（兄弟节点的前缀相同，不影响比较）
+            for &n in &path {
+                let node = &mut nodes[n as usize];
+                node.visits += 1;
+                node.value += ret;
+            }
+        }
+
+        let root = &nodes[0];
+        let children: Vec<(Direction, u32, f64)> = root
+            .legal()
+            .filter(|&i| root.children[i] != NONE)
+            .map(|i| {
+                let child = &nodes[root.children[i] as usize];
+                (ACTIONS[i], child.visits, child.value / child.visits.max(1) as f64)
+            })
+            .collect();
+        MctsResult {
+            direction: children.iter().max_by_key(|c| c.1).map(|c| c.0),
+            children,
+            nodes: nodes.len(),
+            elapsed: start.elapsed(),
+        }
+    }
+
+    /// UCT：平均回报 + 探索项，取最大者
+    fn select(&self, nodes: &[Node], at: u32) -> usize {
+        let node = &nodes[at as usize];
+        let ln_n = (node.visits.max(1) as f64).ln();
+        let uct = |i: usize| {
+            let child = &nodes[node.children[i] as usize];
+            let n = child.visits.max(1) as f64;
+            child.value / n + self.config.exploration * (ln_n / n).sqrt()
+        };
+        node.legal()
+            .max_by(|&a, &b| uct(a).total_cmp(&uct(b)))
+            .expect("至少有三个合法动作")
+    }
+
+    /// 走一步，返回 (折扣后的回报, 是否终局)
+    ///
+    /// 吃到食物 +1，死亡 −1，填满全图 +1 并终局；第 `depth` 步的回报乘以 `discount^depth`。
+    fn step(&mut self, game: &mut SnakeGame, dir: Direction, depth: u32) -> (f64, bool) {
+        let length = game.length();
+        let state = game.update(Some(dir), &mut self.rng);
+        let reward = match state {
+            GameState::Over => -1.0,
+            _ if game.length() > length => 1.0,
+            _ => 0.0,
+        };
+        let done = matches!(state, GameState::Over | GameState::Won);
+        (reward * self.config.discount.powi(depth as i32), done)
+    }
+
+    /// rollout 策略：在不出界、不撞蛇身的方向中均匀随机（都会撞上时为 None）
+    fn rollout_dir(&mut self, game: &SnakeGame) -> Option<Direction> {
+        let head = *game.snake_hashes().next_back()?;
+        let cfg = game.config();
+        let mut safe = [Direction::Up; 4];
+        let mut count = 0;
+        for dir in ACTIONS {
+            if game.direction().is_some_and(|d| dir == d.opposite()) {
+                continue;
+            }
+            if cfg.neighbor(head, dir).is_some_and(|h| game.cell_state(h) != CellState::Snake) {
+                safe[count] = dir;
+                count += 1;
+            }
+        }
+        (count > 0).then(|| safe[self.rng.random_range(0..count)])
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MapConfig;
+    use crate::game::Game;
+
+    fn small() -> MctsConfig {
+        MctsConfig { iterations: 150, rollout_depth: 16, ..MctsConfig::default() }
+    }
+
+    #[test]
+    fn test_search_is_reproducible() {
+        let game = Game::with_seed(MapConfig::new(10, 10), 3, 2, 1);
+        let a = Mcts::new(small()).search(game.snake());
+        let b = Mcts::new(small()).search(game.snake());
+        assert_eq!((a.direction, &a.children, a.nodes), (b.direction, &b.children, b.nodes));
+        // 开局朝右：三个合法根动作都被扩展，访问次数之和等于迭代次数
+        assert_eq!(a.children.len(), 3);
+        assert_eq!(a.children.iter().map(|c| c.1).sum::<u32>(), 150);
+        assert!(a.children.iter().all(|c| c.0 != Direction::Left));
+    }
+
+    #[test]
+    fn test_avoids_wall() {
+        // 开局蛇 (2,2)(3,2)(4,2) 朝右，先走到右墙边；继续向右必死
+        let mut game = Game::with_seed(MapConfig::new(6, 6), 3, 0, 3);
+        game.tick(Some(Direction::Right));
+        let mut mcts = Mcts::new(small());
+        let result = mcts.search(game.snake());
+        assert_ne!(result.direction, Some(Direction::Right));
+        let right = result.children.iter().find(|c| c.0 == Direction::Right).unwrap();
+        assert!(right.2 < 0.0, "{result:?}");
+    }
+
+    #[test]
+    fn test_plays_and_eats() {
+        let mut game = Game::with_seed(MapConfig::new(10, 10), 3, 2, 7);
+        let mut mcts = Mcts::new(small());
+        for _ in 0..150 {
+            let state = game.tick(mcts.next_dir(game.snake()));
+            assert_ne!(state, GameState::Over, "moves={}", game.moves_count());
+        }
+        assert!(game.snake().length() > 6, "length={}", game.snake().length());
+    }
+}
@@ -0,0 +1,575 @@
+//! 多蛇同图引擎
+//!
+//! N 条蛇共享一张地图与一组食物，每个 tick 同时移动。与 [`SnakeGame`](crate::snake::SnakeGame)
+//! 一样，空格与食物用 swap-remove 列表 + 反向下标维护，单条蛇的更新为 O(1)，整个 tick 为 O(N)。
+//!
+//! 碰撞规则（全部以 tick 开始时的地图判定，移动顺序无关）：
+//!
+//! 1. 撞墙，或蛇头进入任何蛇身格（包括本 tick 将要离开的蛇尾）→ 该蛇死亡
+//! 2. 两条及以上的蛇头进入同一格（正面相撞）→ 这些蛇全部死亡
+//! 3. 死亡的蛇不再移动，蛇身原地保留为障碍物
+//! 4. 地图上不再有空格与食物时，所有存活的蛇胜利
+//!
+//! 尚未收到方向输入的蛇（[`GameState::Ready`]）原地不动，但仍可被撞。
+
+use crate::config::MapConfig;
+use crate::types::{CellState, Direction, GameState, Position};
+use rand::Rng;
+use std::collections::VecDeque;
+
+/// 无主格子（空格或食物）的 owner 标记
+const NO_OWNER: u8 = u8::MAX;
+
+/// 同局最多的蛇数（owner 用 u8 存储，`u8::MAX` 保留）
+pub const MAX_SNAKES: usize = NO_OWNER as usize;
+
+/// 单条蛇的状态
+#[derive(Debug, Clone)]
+struct Snake {
+    body: VecDeque<usize>,
+    direction: Option<Direction>,
+    state: GameState,
+    /// 吃到的食物数
+    score: u32,
+    /// 本 tick 的目标格（仅在 `update` 内部使用）
+    target: Option<usize>,
+}
+
+/// 对战结果（见 [`MultiSnakeGame::outcome`]）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Outcome {
+    /// 第 n 条蛇获胜
+    Winner(usize),
+    /// 平局
+    Draw,
+}
+
+/// 多蛇游戏状态
+#[derive(Debug, Clone)]
+pub struct MultiSnakeGame {
+    config: MapConfig,
+    map: Vec<CellState>,
+    /// 蛇身格所属的蛇编号，其余为 `NO_OWNER`
+    owner: Vec<u8>,
+    snakes: Vec<Snake>,
+    empty_cells: Vec<usize>,
+    empty_indices: Vec<usize>,
+    food_hashes: Vec<usize>,
+    food_indices: Vec<usize>,
+    /// 正面相撞检测的暂存表：目标格 → 认领次数（tick 结束时复位）
+    claims: Vec<u8>,
+}
+
+impl MultiSnakeGame {
+    /// 创建新的多蛇游戏
+    ///
+    /// 蛇按编号分布在均匀间隔的行上：偶数号从左侧出发朝右，奇数号从右侧出发朝左，
+    /// 各行互不相同，因此出生位置不会重叠。
+    ///
+    /// # Panics
+    /// - `snake_count` 为 0 或超过 [`MAX_SNAKES`]
+    /// - `snake_count > height`（无法分到不同的行）
+    /// - `initial_length` 为 0 或超过 `width`
+    /// - 蛇身总格数 + 食物数超过地图总格数
+    pub fn new(
+        config: MapConfig,
+        snake_count: usize,
+        initial_length: usize,
+        food_count: usize,
+        rng: &mut impl Rng,
+    ) -> Self {
+        let total = config.total_size();
+        assert!(
+            (1..=MAX_SNAKES).contains(&snake_count),
+            "蛇数({snake_count})必须在 1..={MAX_SNAKES} 之间"
+        );
+        assert!(
+            snake_count <= config.height as usize,
+            "蛇数({snake_count})超过地图高度({})",
+            config.height
+        );
+        assert!(
+            initial_length > 0 && initial_length <= config.width as usize,
+            "初始长度({initial_length})必须在 1..={} 之间",
+            config.width
+        );
+        assert!(
+            snake_count * initial_length + food_count <= total,
+            "蛇身({}) + 食物({food_count}) 超过地图总格数({total})",
+            snake_count * initial_length
+        );
+
+        let mut game = Self {
+            map: vec![CellState::Empty; total],
+            owner: vec![NO_OWNER; total],
+            snakes: Vec::with_capacity(snake_count),
+            empty_cells: (0..total).collect(),
+            empty_indices: (0..total).collect(),
+            food_hashes: Vec::with_capacity(food_count),
+            food_indices: vec![usize::MAX; total],
+            claims: vec![0; total],
+            config,
+        };
+
+        let len = initial_length as u32;
+        for i in 0..snake_count {
+            let y = ((i + 1) * game.config.height as usize / (snake_count + 1)) as u32;
+            // 从尾到头的 x 坐标
+            let (xs, direction): (Vec<u32>, _) = if i % 2 == 0 {
+                let head_x = (game.config.width / 4).max(len - 1);
+                ((head_x + 1 - len..=head_x).collect(), Direction::Right)
+            } else {
+                let head_x = (game.config.width - 1 - game.config.width / 4).min(game.config.width - len);
+                ((head_x..head_x + len).rev().collect(), Direction::Left)
+            };
+            let mut body = VecDeque::with_capacity(initial_length + 16);
+            for x in xs {
+                let hash = game.config.to_hash(Position { x, y });
+                game.occupy(hash, i);
+                body.push_back(hash);
+            }
+            game.snakes.push(Snake {
+                body,
+                direction: Some(direction),
+                state: GameState::Ready,
+                score: 0,
+                target: None,
+            });
+        }
+
+        for _ in 0..food_count {
+            game.spawn_food(rng);
+        }
+
+        game.check_invariants();
+        game
+    }
+
+    // ========================================================================
+    // 访问器
+    // ========================================================================
+
+    /// 返回地图配置的引用
+    pub fn config(&self) -> &MapConfig {
+        &self.config
+    }
+
+    /// 蛇的数量（含已死亡）
+    pub fn snake_count(&self) -> usize {
+        self.snakes.len()
+    }
+
+    /// 仍存活（Ready 或 Running）的蛇数
+    pub fn alive_count(&self) -> usize {
+        self.snakes.iter().filter(|s| is_alive(s.state)).count()
+    }
+
+    /// 第 `i` 条蛇的状态
+    pub fn state(&self, i: usize) -> GameState {
+        self.snakes[i].state
+    }
+
+    /// 第 `i` 条蛇吃到的食物数
+    pub fn score(&self, i: usize) -> u32 {
+        self.snakes[i].score
+    }
+
+    /// 对战是否结束及结果；进行中返回 None
+    ///
+    /// 有蛇胜利（填满地图），或多蛇局只剩不超过一条蛇存活（单蛇局为全部死亡）时结束。
+    /// 仍存活的蛇中最长者获胜；全部死亡时比较所有蛇的长度。最长者不唯一时为平局。
+    pub fn outcome(&self) -> Option<Outcome> {
+        let alive = self.alive_count();
+        let won = self.snakes.iter().any(|s| s.state == GameState::Won);
+        let finished = won || alive == 0 || (self.snakes.len() > 1 && alive == 1);
+        if !finished {
+            return None;
+        }
+        let finalists = |s: &Snake| (alive == 0 && !won) || s.state != GameState::Over;
+        let best = self.snakes.iter().filter(|s| finalists(s)).map(|s| s.body.len()).max()?;
+        let mut leaders = (0..self.snakes.len())
+            .filter(|&i| finalists(&self.snakes[i]) && self.snakes[i].body.len() == best);
+        match (leaders.next(), leaders.next()) {
+            (Some(i), None) => Some(Outcome::Winner(i)),
+            _ => Some(Outcome::Draw),
+        }
+    }
+
+    /// 第 `i` 条蛇的当前方向
+    pub fn direction(&self, i: usize) -> Option<Direction> {
+        self.snakes[i].direction
+    }
+
+    /// 第 `i` 条蛇的长度
+    pub fn length(&self, i: usize) -> usize {
+        self.snakes[i].body.len()
+    }
+
+    /// 第 `i` 条蛇的蛇头位置
+    pub fn head_position(&self, i: usize) -> Position {
+        self.config.from_hash(*self.snakes[i].body.back().unwrap())
+    }
+
+    /// 第 `i` 条蛇的蛇身（从尾到头）
+    pub fn snake_hashes(&self, i: usize) -> impl DoubleEndedIterator<Item = &usize> + '_ {
+        self.snakes[i].body.iter()
+    }
+
+    /// 食物位置列表
+    pub fn food_hashes(&self) -> &[usize] {
+        &self.food_hashes
+    }
+
+    /// 获取指定哈希位置的格子状态
+    pub fn cell_state(&self, hash: usize) -> CellState {
+        self.map[hash]
+    }
+
+    /// 占据指定格子的蛇编号
+    pub fn owner(&self, hash: usize) -> Option<usize> {
+        (self.owner[hash] != NO_OWNER).then_some(self.owner[hash] as usize)
+    }
+
+    /// 第 `i` 条蛇认输（例如联机玩家断开）：立即判为出局，蛇身保留为障碍
+    pub fn resign(&mut self, i: usize) {
+        if is_alive(self.snakes[i].state) {
+            self.snakes[i].state = GameState::Over;
+        }
+    }
+
+    // ========================================================================
+    // 内部操作
+    // ========================================================================
+
+    /// 空格 → 第 `i` 条蛇的蛇身（O(1) swap-remove）
+    fn occupy(&mut self, hash: usize, i: usize) {
+        let idx = self.empty_indices[hash];
+        let last = self.empty_cells.pop().unwrap();
+        if last != hash {
+            self.empty_cells[idx] = last;
+            self.empty_indices[last] = idx;
+        }
+        self.empty_indices[hash] = usize::MAX;
+        self.map[hash] = CellState::Snake;
+        self.owner[hash] = i as u8;
+    }
+
+    /// 蛇身 → 空格
+    fn vacate(&mut self, hash: usize) {
+        self.empty_indices[hash] = self.empty_cells.len();
+        self.empty_cells.push(hash);
+        self.map[hash] = CellState::Empty;
+        self.owner[hash] = NO_OWNER;
+    }
+
+    /// 食物 → 第 `i` 条蛇的蛇头
+    fn eat(&mut self, hash: usize, i: usize) {
+        let idx = self.food_indices[hash];
+        let last = self.food_hashes.pop().unwrap();
+        if last != hash {
+            self.food_hashes[idx] = last;
+            self.food_indices[last] = idx;
+        }
+        self.food_indices[hash] = usize::MAX;
+        self.map[hash] = CellState::Snake;
+        self.owner[hash] = i as u8;
+    }
+
+    /// 在随机空格上生成一份食物（O(1)）
+    fn spawn_food(&mut self, rng: &mut impl Rng) {
+        if self.empty_cells.is_empty() {
+            return;
+        }
+        let idx = rng.random_range(0..self.empty_cells.len());
+        let hash = self.empty_cells.swap_remove(idx);
+        if let Some(&moved) = self.empty_cells.get(idx) {
+            self.empty_indices[moved] = idx;
+        }
+        self.empty_indices[hash] = usize::MAX;
+        self.food_indices[hash] = self.food_hashes.len();
+        self.food_hashes.push(hash);
+        self.map[hash] = CellState::Food;
+    }
+
+    /// 按方向计算下一格；撞墙返回 None
+    fn step(&self, hash: usize, dir: Direction) -> Option<usize> {
+        let pos = self.config.from_hash(hash);
+        let (dx, dy) = dir.delta();
+        let next = Position {
+            x: pos.x.checked_add_signed(dx)?,
+            y: pos.y.checked_add_signed(dy)?,
+        };
+        self.config.contains(next).then(|| self.config.to_hash(next))
+    }
+
+    // ========================================================================
+    // 更新
+    // ========================================================================
+
+    /// 推进一个 tick：`inputs[i]` 为第 `i` 条蛇的方向输入（None 表示保持方向）
+    ///
+    /// 已死亡的蛇忽略输入；180 度掉头的输入同样被忽略。更新后用 [`state`](Self::state) 查询各蛇结果。
+    ///
+    /// # Panics
+    /// `inputs.len() != snake_count()`
+    pub fn update(&mut self, inputs: &[Option<Direction>], rng: &mut impl Rng) {
+        assert_eq!(inputs.len(), self.snakes.len(), "输入数必须等于蛇数");
+
+        // 1. 确定方向与目标格，标记撞墙、撞身
+        for (i, &input) in inputs.iter().enumerate() {
+            let snake = &mut self.snakes[i];
+            snake.target = None;
+            if !is_alive(snake.state) {
+                continue;
+            }
+            if let Some(new_dir) = input
+                && snake.direction != Some(new_dir.opposite())
+            {
+                snake.direction = Some(new_dir);
+                snake.state = GameState::Running;
+            }
+            if snake.state == GameState::Ready {
+                continue;
+            }
+            let head = *snake.body.back().unwrap();
+            let dir = snake.direction.unwrap();
+            match self.step(head, dir) {
+                Some(next) if self.map[next] != CellState::Snake => {
+                    self.snakes[i].target = Some(next);
+                    self.claims[next] += 1;
+                }
+                _ => self.snakes[i].state = GameState::Over,
+            }
+        }
+
+        // 2. 正面相撞
+        for snake in &mut self.snakes {
+            if snake.target.is_some_and(|next| self.claims[next] > 1) {
+                snake.state = GameState::Over;
+            }
+        }
+
+        // 3. 存活的蛇移动；被认领的格子互不相同，且都不是任何蛇身，因此顺序无关
+        let mut eaten = 0;
+        for i in 0..self.snakes.len() {
+            let Some(next) = self.snakes[i].target.take() else {
+                continue;
+            };
+            self.claims[next] = 0;
+            if self.snakes[i].state == GameState::Over {
+                continue;
+            }
+            if self.map[next] == CellState::Food {
+                self.eat(next, i);
+                self.snakes[i].score += 1;
+                eaten += 1;
+            } else {
+                let tail = self.snakes[i].body.pop_front().unwrap();
+                self.vacate(tail);
+                self.occupy(next, i);
+            }
+            self.snakes[i].body.push_back(next);
+        }
+
+        // 4. 补充食物，检查是否填满
+        for _ in 0..eaten {
+            self.spawn_food(rng);
+        }
+        if self.empty_cells.is_empty() && self.food_hashes.is_empty() {
+            for snake in &mut self.snakes {
+                if snake.state == GameState::Running {
+                    snake.state = GameState::Won;
+                }
+            }
+        }
+
+        self.check_invariants();
+    }
+
+    /// 调试构建下校验各表一致性
+    fn check_invariants(&self) {
+        if !cfg!(debug_assertions) {
+            return;
+        }
+        let total = self.config.total_size();
+        let mut seen = vec![0u8; total];
+        for (i, snake) in self.snakes.iter().enumerate() {
+            for &h in &snake.body {
+                debug_assert_eq!(self.map[h], CellState::Snake, "蛇 {i} 的 hash {h} 不是 Snake");
+                debug_assert_eq!(self.owner[h] as usize, i, "hash {h} 的 owner 应为 {i}");
+                seen[h] += 1;
+            }
+        }
+        for (idx, &h) in self.food_hashes.iter().enumerate() {
+            debug_assert_eq!(self.map[h], CellState::Food, "食物 hash {h} 不是 Food");
+            debug_assert_eq!(self.food_indices[h], idx, "food_indices[{h}] 应为 {idx}");
+            seen[h] += 1;
+        }
+        for (idx, &h) in self.empty_cells.iter().enumerate() {
+            debug_assert_eq!(self.map[h], CellState::Empty, "空格 hash {h} 不是 Empty");
+            debug_assert_eq!(self.empty_indices[h], idx, "empty_indices[{h}] 应为 {idx}");
+            seen[h] += 1;
+        }
+        for (h, &count) in seen.iter().enumerate() {
+            debug_assert_eq!(count, 1, "hash {h} 被分类了 {count} 次");
+        }
+        debug_assert!(self.claims.iter().all(|&c| c == 0), "claims 未复位");
+    }
+}
+
+/// Ready 与 Running 的蛇仍在场上
+const fn is_alive(state: GameState) -> bool {
+    matches!(state, GameState::Ready | GameState::Running)
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    fn rng() -> SmallRng {
+        SmallRng::seed_from_u64(1)
+    }
+
+    #[test]
+    fn test_spawn_positions_do_not_overlap() {
+        for (w, h, n, food) in [(16, 16, 2, 2), (10, 4, 4, 2), (3, 3, 3, 0), (8, 8, 1, 2)] {
+            let game = MultiSnakeGame::new(MapConfig::new(w, h), n, 3, food, &mut rng());
+            assert_eq!(game.snake_count(), n);
+            for i in 0..n {
+                assert_eq!(game.length(i), 3);
+                assert_eq!(game.state(i), GameState::Ready);
+                for &hash in game.snake_hashes(i) {
+                    assert_eq!(game.owner(hash), Some(i));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_independent_movement_and_eating() {
+        let mut game = MultiSnakeGame::new(MapConfig::new(16, 16), 2, 3, 5, &mut rng());
+        let before = [game.head_position(0), game.head_position(1)];
+        game.update(&[Some(Direction::Up), Some(Direction::Down)], &mut rng());
+        assert_eq!(game.state(0), GameState::Running);
+        assert_eq!(game.state(1), GameState::Running);
+        assert_eq!(game.head_position(0).y, before[0].y - 1);
+        assert_eq!(game.head_position(1).y, before[1].y + 1);
+
+        // 只有一条蛇出发时另一条原地等待
+        let mut game = MultiSnakeGame::new(MapConfig::new(16, 16), 2, 3, 0, &mut rng());
+        let waiting = game.head_position(1);
+        game.update(&[Some(Direction::Right), None], &mut rng());
+        assert_eq!(game.state(1), GameState::Ready);
+        assert_eq!(game.head_position(1), waiting);
+        assert_eq!(game.food_hashes().len(), 0);
+    }
+
+    #[test]
+    fn test_head_to_head_kills_both() {
+        // 8×3：蛇 0 在第 1 行 x=1..2 朝右，蛇 1 在第 2 行 x=5..6 朝左
+        let mut game = MultiSnakeGame::new(MapConfig::new(8, 3), 2, 2, 0, &mut rng());
+        assert_eq!(game.head_position(0), Position { x: 2, y: 1 });
+        assert_eq!(game.head_position(1), Position { x: 5, y: 2 });
+        game.update(&[Some(Direction::Right), Some(Direction::Left)], &mut rng());
+        // 两蛇同时进入 (3, 2)
+        game.update(&[Some(Direction::Down), Some(Direction::Left)], &mut rng());
+        assert_eq!(game.state(0), GameState::Over);
+        assert_eq!(game.state(1), GameState::Over);
+        assert_eq!(game.alive_count(), 0);
+        // 死亡的蛇身原地保留为障碍
+        assert_eq!(game.head_position(0), Position { x: 3, y: 1 });
+        assert_eq!(game.owner(game.config().to_hash(Position { x: 4, y: 2 })), Some(1));
+        assert_eq!(game.cell_state(game.config().to_hash(Position { x: 3, y: 2 })), CellState::Empty);
+    }
+
+    #[test]
+    fn test_entering_vacating_tail_is_a_collision() {
+        // 蛇 1 向左移动时，蛇 0 向下进入蛇 1 正在离开的尾格，仍判定为撞身
+        let mut game = MultiSnakeGame::new(MapConfig::new(8, 3), 2, 2, 0, &mut rng());
+        game.update(&[Some(Direction::Right), Some(Direction::Left)], &mut rng());
+        game.update(&[Some(Direction::Right), None], &mut rng());
+        // 蛇 0 头在 (4, 1)，蛇 1 占 (4, 2)-(3, 2)，尾在 (4, 2)
+        assert_eq!(game.head_position(0), Position { x: 4, y: 1 });
+        assert_eq!(game.head_position(1), Position { x: 3, y: 2 });
+        game.update(&[Some(Direction::Down), None], &mut rng());
+        assert_eq!(game.state(0), GameState::Over);
+        assert_eq!(game.state(1), GameState::Running);
+    }
+
+    #[test]
+    fn test_filling_board_wins() {
+        let mut game = MultiSnakeGame::new(MapConfig::new(2, 1), 1, 1, 1, &mut rng());
+        game.update(&[Some(Direction::Right)], &mut rng());
+        assert_eq!(game.state(0), GameState::Won);
+    }
+
+    #[test]
+    fn test_reverse_input_is_ignored() {
+        let mut game = MultiSnakeGame::new(MapConfig::new(8, 3), 2, 2, 0, &mut rng());
+        game.update(&[Some(Direction::Left), Some(Direction::Right)], &mut rng());
+        assert_eq!(game.state(0), GameState::Ready);
+        assert_eq!(game.state(1), GameState::Ready);
+    }
+
+    #[test]
+    fn test_head_to_body_kills_only_mover() {
+        let mut game = MultiSnakeGame::new(MapConfig::new(8, 3), 2, 3, 0, &mut rng());
+        // 蛇 1 静止在第 1 行，蛇 0 从第 0 行向下撞进它的身体
+        let target_x = game.head_position(1).x;
+        while game.head_position(0).x < target_x {
+            game.update(&[Some(Direction::Right), None], &mut rng());
+        }
+        game.update(&[Some(Direction::Down), None], &mut rng());
+        assert_eq!(game.state(0), GameState::Over);
+        assert_eq!(game.state(1), GameState::Ready);
+        assert_eq!(game.alive_count(), 1);
+    }
+
+    #[test]
+    fn test_outcome() {
+        // 正面相撞、长度相同 → 平局
+        let mut game = MultiSnakeGame::new(MapConfig::new(8, 3), 2, 2, 0, &mut rng());
+        assert_eq!(game.outcome(), None);
+        game.update(&[Some(Direction::Right), Some(Direction::Left)], &mut rng());
+        game.update(&[Some(Direction::Down), Some(Direction::Left)], &mut rng());
+        assert_eq!(game.outcome(), Some(Outcome::Draw));
+
+        // 撞身的一方输，幸存者获胜
+        let mut game = MultiSnakeGame::new(MapConfig::new(8, 3), 2, 3, 0, &mut rng());
+        game.update(&[Some(Direction::Up), None], &mut rng());
+        game.update(&[Some(Direction::Up), None], &mut rng());
+        assert_eq!(game.outcome(), Some(Outcome::Winner(1)));
+
+        // 单蛇局填满地图
+        let mut game = MultiSnakeGame::new(MapConfig::new(2, 1), 1, 1, 1, &mut rng());
+        game.update(&[Some(Direction::Right)], &mut rng());
+        assert_eq!(game.score(0), 1);
+        assert_eq!(game.outcome(), Some(Outcome::Winner(0)));
+    }
+
+    #[test]
+    fn test_random_play_keeps_invariants() {
+        let mut rng = SmallRng::seed_from_u64(9);
+        for _ in 0..20 {
+            let mut game = MultiSnakeGame::new(MapConfig::new(8, 8), 3, 2, 4, &mut rng);
+            for _ in 0..200 {
+                let inputs: Vec<_> = (0..3)
+                    .map(|_| {
+                        [None, Some(Direction::Up), Some(Direction::Down), Some(Direction::Left), Some(Direction::Right)]
+                            [rng.random_range(0..5)]
+                    })
+                    .collect();
+                game.update(&inputs, &mut rng);
+                if game.alive_count() == 0 {
+                    break;
+                }
+            }
+        }
+    }
+}
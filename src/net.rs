@@ -0,0 +1,832 @@
+//! 本地 TCP 联机
+//!
+//! 服务器持有权威的 [`MultiSnakeGame`] 并驱动 tick 循环，每个连接的客户端操控一条蛇。
+//! 客户端只发送方向，服务器每个 tick 广播一行增量，客户端据此维护镜像（[`RemoteBoard`]）。
+//!
+//! # 协议（版本 1）
+//!
+//! UTF-8 文本，每条消息一行（`\n` 结尾），字段以空格分隔。格子用 hash（`y * 宽 + x`）表示，
+//! 方向为 `U`/`D`/`L`/`R`，蛇状态为 `0` 等待、`1` 进行中、`2` 出局、`3` 胜利。
+//!
+//! 服务器 → 客户端：
+//!
+//! ```text
+//! HELLO <版本> <你的编号> <玩家数> <宽> <高>     连接后立即发送
+//! FULL <tick> S <状态> <hash>... S ... FOOD <hash>...
+//!                                               全部玩家到齐后发送完整局面，每个 S 段为一条蛇（从尾到头）
+//! TICK <tick> <蛇增量>... FOOD <hash>...        每个 tick 一行，蛇增量按编号排列，食物为完整列表
+//! OVER <获胜编号>|DRAW                         对局结束，随后服务器关闭连接
+//! ```
+//!
+//! 蛇增量为 `<状态>`（未移动）、`<状态>:<新蛇头>`（移动，蛇尾前进一格）
+//! 或 `<状态>:<新蛇头>+`（吃到食物，蛇尾不动）。
+//!
+//! 客户端 → 服务器：
+//!
+//! ```text
+//! DIR <U|D|L|R>    改变方向（同一 tick 内以最后一条为准）
+//! QUIT             认输并断开；直接断开连接效果相同
+//! ```
+
+use crate::config::MapConfig;
+use crate::multi::{MAX_SNAKES, MultiSnakeGame, Outcome};
+use crate::render::{self, Glyph};
+use crate::rng::GameRng;
+use crate::snapshot::MAX_CELLS;
+use crate::types::{Direction, GameState};
+use std::collections::VecDeque;
+use std::fmt;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::mpsc;
+use std::time::Duration;
+
+/// 协议版本
+pub const PROTOCOL_VERSION: u32 = 1;
+
+/// 默认端口
+pub const DEFAULT_PORT: u16 = 7878;
+
+/// 服务器开局时每条蛇的长度
+const INITIAL_LENGTH: usize = 3;
+
+/// 服务器向单个客户端写一行的时限；不读数据的客户端超时后判为认输，不拖住其他玩家
+const WRITE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// `config` 上 [`Server::run`] 最多能容纳的玩家数（地图放不下一条蛇时为 0）
+///
+/// 每条蛇独占一行且不超过 [`MAX_SNAKES`]，蛇身与每人一份的食物之和不超过总格数。
+pub fn max_players(config: &MapConfig) -> usize {
+    if (config.width as usize) < INITIAL_LENGTH {
+        return 0;
+    }
+    MAX_SNAKES
+        .min(config.height as usize)
+        .min(config.total_size() / (INITIAL_LENGTH + 1))
+}
+
+/// 无法解析的消息
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProtocolError {
+    pub line: String,
+}
+
+impl fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "无法解析的消息: {:?}", self.line)
+    }
+}
+
+impl std::error::Error for ProtocolError {}
+
+/// 客户端 → 服务器
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientMessage {
+    Dir(Direction),
+    Quit,
+}
+
+/// 单条蛇在一个 tick 内的变化
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SnakeDelta {
+    pub state: GameState,
+    /// 新蛇头与是否变长；None 表示未移动
+    pub head: Option<(usize, bool)>,
+}
+
+/// 服务器 → 客户端
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ServerMessage {
+    Hello {
+        version: u32,
+        player: usize,
+        players: usize,
+        width: u32,
+        height: u32,
+    },
+    Full {
+        tick: u64,
+        /// 每条蛇的状态与蛇身（从尾到头）
+        snakes: Vec<(GameState, Vec<usize>)>,
+        foods: Vec<usize>,
+    },
+    Tick {
+        tick: u64,
+        snakes: Vec<SnakeDelta>,
+        foods: Vec<usize>,
+    },
+    Over(Outcome),
+}
+
+const fn direction_char(dir: Direction) -> char {
+    match dir {
+        Direction::Up => 'U',
+        Direction::Down => 'D',
+        Direction::Left => 'L',
+        Direction::Right => 'R',
+    }
+}
+
+const fn state_code(state: GameState) -> u8 {
+    match state {
+        GameState::Ready => 0,
+        GameState::Running => 1,
+        GameState::Over => 2,
+        GameState::Won => 3,
+    }
+}
+
+fn parse_state(token: &str) -> Option<GameState> {
+    match token {
+        "0" => Some(GameState::Ready),
+        "1" => Some(GameState::Running),
+        "2" => Some(GameState::Over),
+        "3" => Some(GameState::Won),
+        _ => None,
+    }
+}
+
+impl fmt::Display for ClientMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ClientMessage::Dir(dir) => write!(f, "DIR {}", direction_char(*dir)),
+            ClientMessage::Quit => write!(f, "QUIT"),
+        }
+    }
+}
+
+impl ClientMessage {
+    /// 解析一行（不含换行符）
+    pub fn parse(line: &str) -> Result<Self, ProtocolError> {
+        let err = || ProtocolError { line: line.to_string() };
+        let mut tokens = line.split_whitespace();
+        let msg = match (tokens.next(), tokens.next()) {
+            (Some("DIR"), Some(d)) => ClientMessage::Dir(match d {
+                "U" => Direction::Up,
+                "D" => Direction::Down,
+                "L" => Direction::Left,
+                "R" => Direction::Right,
+                _ => return Err(err()),
+            }),
+            (Some("QUIT"), None) => ClientMessage::Quit,
+            _ => return Err(err()),
+        };
+        match tokens.next() {
+            None => Ok(msg),
+            Some(_) => Err(err()),
+        }
+    }
+}
+
+impl fmt::Display for ServerMessage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ServerMessage::Hello {
+                version,
+                player,
+                players,
+                width,
+                height,
+            } => write!(f, "HELLO {version} {player} {players} {width} {height}"),
+            ServerMessage::Full { tick, snakes, foods } => {
+                write!(f, "FULL {tick}")?;
+                for (state, body) in snakes {
+                    write!(f, " S {}", state_code(*state))?;
+                    for h in body {
+                        write!(f, " {h}")?;
+                    }
+                }
+                write_foods(f, foods)
+            }
+            ServerMessage::Tick { tick, snakes, foods } => {
+                write!(f, "TICK {tick}")?;
+                for delta in snakes {
+                    write!(f, " {}", state_code(delta.state))?;
+                    if let Some((head, grew)) = delta.head {
+                        write!(f, ":{head}{}", if grew { "+" } else { "" })?;
+                    }
+                }
+                write_foods(f, foods)
+            }
+            ServerMessage::Over(Outcome::Winner(i)) => write!(f, "OVER {i}"),
+            ServerMessage::Over(Outcome::Draw) => write!(f, "OVER DRAW"),
+        }
+    }
+}
+
+fn write_foods(f: &mut fmt::Formatter<'_>, foods: &[usize]) -> fmt::Result {
+    write!(f, " FOOD")?;
+    for h in foods {
+        write!(f, " {h}")?;
+    }
+    Ok(())
+}
+
+impl ServerMessage {
+    /// 解析一行（不含换行符）
+    pub fn parse(line: &str) -> Result<Self, ProtocolError> {
+        Self::parse_tokens(line).ok_or_else(|| ProtocolError { line: line.to_string() })
+    }
+
+    fn parse_tokens(line: &str) -> Option<Self> {
+        let mut tokens = line.split_whitespace();
+        let msg = match tokens.next()? {
+            "HELLO" => {
+                let mut num = || tokens.next()?.parse::<u64>().ok();
+                let msg = ServerMessage::Hello {
+                    version: num()?.try_into().ok()?,
+                    player: num()?.try_into().ok()?,
+                    players: num()?.try_into().ok()?,
+                    width: num()?.try_into().ok()?,
+                    height: num()?.try_into().ok()?,
+                };
+                tokens.next().is_none().then_some(msg)?
+            }
+            "FULL" => {
+                let tick = tokens.next()?.parse().ok()?;
+                let mut snakes: Vec<(GameState, Vec<usize>)> = Vec::new();
+                let mut foods = None;
+                while let Some(token) = tokens.next() {
+                    match (token, &mut foods) {
+                        ("S", None) => snakes.push((parse_state(tokens.next()?)?, Vec::new())),
+                        ("FOOD", None) => foods = Some(Vec::new()),
+                        (_, Some(foods)) => foods.push(token.parse().ok()?),
+                        (_, None) => snakes.last_mut()?.1.push(token.parse().ok()?),
+                    }
+                }
+                ServerMessage::Full { tick, snakes, foods: foods? }
+            }
+            "TICK" => {
+                let tick = tokens.next()?.parse().ok()?;
+                let mut snakes = Vec::new();
+                let mut foods = None;
+                for token in tokens {
+                    match (token, &mut foods) {
+                        ("FOOD", None) => foods = Some(Vec::new()),
+                        (_, Some(foods)) => foods.push(token.parse().ok()?),
+                        (_, None) => snakes.push(parse_delta(token)?),
+                    }
+                }
+                ServerMessage::Tick { tick, snakes, foods: foods? }
+            }
+            "OVER" => {
+                let outcome = match tokens.next()? {
+                    "DRAW" => Outcome::Draw,
+                    i => Outcome::Winner(i.parse().ok()?),
+                };
+                tokens.next().is_none().then_some(ServerMessage::Over(outcome))?
+            }
+            _ => return None,
+        };
+        Some(msg)
+    }
+}
+
+/// 解析 `<状态>[:<蛇头>[+]]`
+fn parse_delta(token: &str) -> Option<SnakeDelta> {
+    let (state, head) = match token.split_once(':') {
+        None => (token, None),
+        Some((state, head)) => {
+            let (head, grew) = match head.strip_suffix('+') {
+                Some(head) => (head, true),
+                None => (head, false),
+            };
+            (state, Some((head.parse().ok()?, grew)))
+        }
+    };
+    Some(SnakeDelta {
+        state: parse_state(state)?,
+        head,
+    })
+}
+
+// ============================================================================
+// 客户端镜像
+// ============================================================================
+
+/// 单条蛇的镜像
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct RemoteSnake {
+    state: GameState,
+    body: VecDeque<usize>,
+    score: u32,
+}
+
+/// 客户端根据服务器消息维护的局面镜像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemoteBoard {
+    config: MapConfig,
+    player: usize,
+    tick: u64,
+    snakes: Vec<RemoteSnake>,
+    foods: Vec<usize>,
+    outcome: Option<Outcome>,
+}
+
+impl RemoteBoard {
+    /// 由 HELLO 消息创建空镜像；其他消息或服务器不可能发出的参数返回 None
+    ///
+    /// HELLO 来自不受信任的对端：地图尺寸受与快照相同的上限约束，
+    /// 玩家数不超过 [`max_players`]，自己的编号必须小于玩家数，以免镜像分配失控。
+    pub fn from_hello(msg: &ServerMessage) -> Option<Self> {
+        match *msg {
+            ServerMessage::Hello {
+                version: PROTOCOL_VERSION,
+                player,
+                players,
+                width,
+                height,
+            } if (1..=u16::MAX as u32).contains(&width)
+                && (1..=u16::MAX as u32).contains(&height)
+                && width as usize * height as usize <= MAX_CELLS
+                && (1..=max_players(&MapConfig::new(width, height))).contains(&players)
+                && player < players =>
+            {
+                Some(Self {
+                    config: MapConfig::new(width, height),
+                    player,
+                    tick: 0,
+                    snakes: vec![
+                        RemoteSnake {
+                            state: GameState::Ready,
+                            body: VecDeque::new(),
+                            score: 0,
+                        };
+                        players
+                    ],
+                    foods: Vec::new(),
+                    outcome: None,
+                })
+            }
+            _ => None,
+        }
+    }
+
+    /// 应用一条服务器消息；与镜像不一致时返回 false（镜像不变）
+    pub fn apply(&mut self, msg: &ServerMessage) -> bool {
+        let total = self.config.total_size();
+        match msg {
+            ServerMessage::Hello { .. } => false,
+            ServerMessage::Full { tick, snakes, foods } => {
+                if snakes.len() != self.snakes.len()
+                    || snakes.iter().flat_map(|(_, body)| body).chain(foods).any(|&h| h >= total)
+                {
+                    return false;
+                }
+                for (snake, (state, body)) in self.snakes.iter_mut().zip(snakes) {
+                    snake.state = *state;
+                    snake.body = body.iter().copied().collect();
+                }
+                self.tick = *tick;
+                self.foods.clone_from(foods);
+                true
+            }
+            ServerMessage::Tick { tick, snakes, foods } => {
+                let valid = snakes.len() == self.snakes.len()
+                    && foods.iter().all(|&h| h < total)
+                    && snakes.iter().zip(&self.snakes).all(|(delta, snake)| match delta.head {
+                        None => true,
+                        Some((head, _)) => head < total && !snake.body.is_empty(),
+                    });
+                if !valid {
+                    return false;
+                }
+                for (snake, delta) in self.snakes.iter_mut().zip(snakes) {
+                    snake.state = delta.state;
+                    if let Some((head, grew)) = delta.head {
+                        if grew {
+                            snake.score += 1;
+                        } else {
+                            snake.body.pop_front();
+                        }
+                        snake.body.push_back(head);
+                    }
+                }
+                self.tick = *tick;
+                self.foods.clone_from(foods);
+                true
+            }
+            ServerMessage::Over(outcome) => {
+                self.outcome = Some(*outcome);
+                true
+            }
+        }
+    }
+
+    /// 地图配置
+    pub fn config(&self) -> &MapConfig {
+        &self.config
+    }
+
+    /// 本客户端操控的蛇编号
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    /// 最近一次更新的 tick
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    /// 第 `i` 条蛇的状态
+    pub fn state(&self, i: usize) -> GameState {
+        self.snakes[i].state
+    }
+
+    /// 第 `i` 条蛇的蛇身（从尾到头）
+    pub fn snake_hashes(&self, i: usize) -> impl DoubleEndedIterator<Item = &usize> + '_ {
+        self.snakes[i].body.iter()
+    }
+
+    /// 第 `i` 条蛇吃到的食物数（从收到 FULL 起计）
+    pub fn score(&self, i: usize) -> u32 {
+        self.snakes[i].score
+    }
+
+    /// 食物位置
+    pub fn food_hashes(&self) -> &[usize] {
+        &self.foods
+    }
+
+    /// 对局结果（收到 OVER 后）
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
+    /// 以与 [`render::render_multi`] 相同的样式绘制
+    pub fn render(&self, output: &mut String) {
+        let mut cells = vec![Glyph::Empty; self.config.total_size()];
+        for &h in &self.foods {
+            cells[h] = Glyph::Food;
+        }
+        for (i, snake) in self.snakes.iter().enumerate() {
+            for &h in &snake.body {
+                cells[h] = Glyph::Body(i);
+            }
+            if let Some(&head) = snake.body.back() {
+                cells[head] = Glyph::Head(i);
+            }
+        }
+        let scores: Vec<u32> = self.snakes.iter().map(|s| s.score).collect();
+        render::render_players(&self.config, |h| cells[h], &scores, output);
+    }
+}
+
+// ============================================================================
+// 服务器
+// ============================================================================
+
+/// 联机服务器
+pub struct Server {
+    listener: TcpListener,
+}
+
+/// 向所有仍连着的客户端发送一行，返回本次写失败或超时的玩家
+///
+/// 这些连接被关闭并置为 None，之后不再写入（可能只写出了半行）。
+fn broadcast(clients: &mut [Option<TcpStream>], msg: &ServerMessage) -> Vec<usize> {
+    let line = format!("{msg}\n");
+    let mut dropped = Vec::new();
+    for (player, slot) in clients.iter_mut().enumerate() {
+        if let Some(client) = slot
+            && client.write_all(line.as_bytes()).is_err()
+        {
+            let _ = client.shutdown(std::net::Shutdown::Both);
+            *slot = None;
+            dropped.push(player);
+        }
+    }
+    dropped
+}
+
+impl Server {
+    /// 监听给定地址（例如 `127.0.0.1:7878`；端口 0 表示由系统分配）
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+        })
+    }
+
+    /// 实际监听的地址
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// 等待 `players` 个客户端连入，然后以 `interval` 为周期运行一局，返回结果
+    ///
+    /// 每个连接由独立线程读取，消息经 channel 汇入 tick 循环；
+    /// 断开或发送 `QUIT` 的玩家判为认输，无法解析的行被忽略。
+    /// 写入超过 [`WRITE_TIMEOUT`] 仍未完成（客户端不再读取）的玩家同样判为认输，不再向其发送。
+    ///
+    /// # Panics
+    /// `players` 为 0 或超过 [`max_players`]
+    pub fn run(
+        &self,
+        config: MapConfig,
+        players: usize,
+        interval: Duration,
+        mut rng: GameRng,
+    ) -> io::Result<Outcome> {
+        assert!(
+            (1..=max_players(&config)).contains(&players),
+            "玩家数({players})必须在 1..={} 之间",
+            max_players(&config)
+        );
+        let (tx, rx) = mpsc::channel();
+        let mut clients = Vec::with_capacity(players);
+        for player in 0..players {
+            let (mut stream, _) = self.listener.accept()?;
+            stream.set_nodelay(true)?;
+            stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+            let hello = ServerMessage::Hello {
+                version: PROTOCOL_VERSION,
+                player,
+                players,
+                width: config.width,
+                height: config.height,
+            };
+            stream.write_all(format!("{hello}\n").as_bytes())?;
+
+            let reader = BufReader::new(stream.try_clone()?);
+            let tx = tx.clone();
+            std::thread::spawn(move || {
+                for line in reader.lines() {
+                    let Ok(line) = line else { break };
+                    if let Ok(msg) = ClientMessage::parse(&line)
+                        && (tx.send((player, msg)).is_err() || msg == ClientMessage::Quit)
+                    {
+                        return;
+                    }
+                }
+                let _ = tx.send((player, ClientMessage::Quit));
+            });
+            clients.push(Some(stream));
+        }
+
+        let mut game = MultiSnakeGame::new(config, players, INITIAL_LENGTH, players, &mut rng);
+        let dropped = broadcast(
+            &mut clients,
+            &ServerMessage::Full {
+                tick: 0,
+                snakes: (0..players)
+                    .map(|i| (game.state(i), game.snake_hashes(i).copied().collect()))
+                    .collect(),
+                foods: game.food_hashes().to_vec(),
+            },
+        );
+        for player in dropped {
+            game.resign(player);
+        }
+
+        let mut inputs = vec![None; players];
+        let mut tick = 0;
+        loop {
+            tick += 1;
+            std::thread::sleep(interval);
+            inputs.fill(None);
+            for (player, msg) in rx.try_iter() {
+                match msg {
+                    ClientMessage::Dir(dir) => inputs[player] = Some(dir),
+                    ClientMessage::Quit => game.resign(player),
+                }
+            }
+
+            let before: Vec<(usize, usize)> = (0..players)
+                .map(|i| (*game.snake_hashes(i).next_back().unwrap(), game.length(i)))
+                .collect();
+            game.update(&inputs, &mut rng);
+            let snakes = before
+                .iter()
+                .enumerate()
+                .map(|(i, &(head, len))| {
+                    let new_head = *game.snake_hashes(i).next_back().unwrap();
+                    SnakeDelta {
+                        state: game.state(i),
+                        head: (new_head != head).then_some((new_head, game.length(i) > len)),
+                    }
+                })
+                .collect();
+            let dropped = broadcast(
+                &mut clients,
+                &ServerMessage::Tick {
+                    tick,
+                    snakes,
+                    foods: game.food_hashes().to_vec(),
+                },
+            );
+            for player in dropped {
+                game.resign(player);
+            }
+
+            if let Some(outcome) = game.outcome() {
+                broadcast(&mut clients, &ServerMessage::Over(outcome));
+                for client in clients.iter().flatten() {
+                    let _ = client.shutdown(std::net::Shutdown::Both);
+                }
+                return Ok(outcome);
+            }
+        }
+    }
+}
+
+/// 连接服务器并读取 HELLO：返回 (镜像, 消息读取端, 写入端)
+pub fn connect(
+    addr: impl ToSocketAddrs,
+) -> io::Result<(RemoteBoard, BufReader<TcpStream>, TcpStream)> {
+    let stream = TcpStream::connect(addr)?;
+    stream.set_nodelay(true)?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut line = String::new();
+    reader.read_line(&mut line)?;
+    let hello = ServerMessage::parse(line.trim_end()).map_err(io::Error::other)?;
+    let board = RemoteBoard::from_hello(&hello)
+        .ok_or_else(|| io::Error::other(format!("不支持的握手: {}", line.trim_end())))?;
+    Ok((board, reader, stream))
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    #[test]
+    fn test_message_roundtrip() {
+        let messages = [
+            ServerMessage::Hello {
+                version: 1,
+                player: 1,
+                players: 2,
+                width: 24,
+                height: 16,
+            },
+            ServerMessage::Full {
+                tick: 0,
+                snakes: vec![(GameState::Ready, vec![1, 2, 3]), (GameState::Over, vec![9])],
+                foods: vec![7],
+            },
+            ServerMessage::Tick {
+                tick: 42,
+                snakes: vec![
+                    SnakeDelta {
+                        state: GameState::Running,
+                        head: Some((4, false)),
+                    },
+                    SnakeDelta {
+                        state: GameState::Running,
+                        head: Some((10, true)),
+                    },
+                    SnakeDelta {
+                        state: GameState::Over,
+                        head: None,
+                    },
+                ],
+                foods: vec![],
+            },
+            ServerMessage::Over(Outcome::Winner(1)),
+            ServerMessage::Over(Outcome::Draw),
+        ];
+        for msg in messages {
+            let line = msg.to_string();
+            assert_eq!(ServerMessage::parse(&line).unwrap(), msg, "{line}");
+        }
+        assert_eq!(
+            ServerMessage::parse("TICK 3 1:4 1:10+ 2 FOOD").unwrap().to_string(),
+            "TICK 3 1:4 1:10+ 2 FOOD"
+        );
+
+        for msg in [ClientMessage::Dir(Direction::Left), ClientMessage::Quit] {
+            assert_eq!(ClientMessage::parse(&msg.to_string()).unwrap(), msg);
+        }
+        for bad in ["", "DIR", "DIR X", "QUIT now", "JUMP"] {
+            assert!(ClientMessage::parse(bad).is_err(), "{bad}");
+        }
+        for bad in ["", "HELLO 1 2", "FULL 0 FOOD x", "TICK 1 9", "TICK 1 1:a FOOD", "OVER"] {
+            assert!(ServerMessage::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_from_hello_rejects_hostile_peers() {
+        let hello = |player, players, width, height| ServerMessage::Hello {
+            version: PROTOCOL_VERSION,
+            player,
+            players,
+            width,
+            height,
+        };
+        assert!(RemoteBoard::from_hello(&hello(1, 2, 24, 16)).is_some());
+        assert_eq!(max_players(&MapConfig::new(24, 16)), 16);
+        for bad in [
+            hello(0, 2, 0, 16),
+            hello(0, 2, u32::MAX, u32::MAX),
+            hello(0, 2, 60000, 60000),
+            hello(0, 0, 24, 16),
+            hello(0, usize::MAX, 24, 16),
+            hello(0, 17, 24, 16),
+            hello(2, 2, 24, 16),
+            hello(0, 1, 2, 16),
+        ] {
+            assert!(RemoteBoard::from_hello(&bad).is_none(), "{bad}");
+        }
+    }
+
+    /// 读取下一条消息并应用到镜像
+    fn next(reader: &mut BufReader<TcpStream>, board: &mut RemoteBoard) -> ServerMessage {
+        let mut line = String::new();
+        assert!(reader.read_line(&mut line).unwrap() > 0, "连接提前关闭");
+        let msg = ServerMessage::parse(line.trim_end()).unwrap();
+        assert!(board.apply(&msg), "{line}");
+        msg
+    }
+
+    #[test]
+    fn test_server_session_on_localhost() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            server.run(
+                MapConfig::new(8, 4),
+                2,
+                Duration::from_millis(5),
+                GameRng::seed_from_u64(1),
+            )
+        });
+
+        let (mut board0, mut reader0, mut writer0) = connect(addr).unwrap();
+        let (mut board1, mut reader1, _writer1) = connect(addr).unwrap();
+        assert_eq!((board0.player(), board1.player()), (0, 1));
+
+        // 玩家 0 一直向上，两步后撞墙；玩家 1 原地等待
+        writeln!(writer0, "{}", ClientMessage::Dir(Direction::Up)).unwrap();
+        let start = next(&mut reader0, &mut board0);
+        assert!(matches!(start, ServerMessage::Full { .. }));
+        let idle: Vec<usize> = board0.snake_hashes(1).copied().collect();
+        loop {
+            if let ServerMessage::Over(outcome) = next(&mut reader0, &mut board0) {
+                assert_eq!(outcome, Outcome::Winner(1));
+                break;
+            }
+        }
+        while !matches!(next(&mut reader1, &mut board1), ServerMessage::Over(_)) {}
+
+        assert_eq!(handle.join().unwrap().unwrap(), Outcome::Winner(1));
+        assert_eq!(board0.state(0), GameState::Over);
+        assert_eq!(board0.state(1), GameState::Ready);
+        assert!(board0.snake_hashes(1).copied().eq(idle));
+        // 两个客户端看到的局面（除编号外）一致
+        board1.player = 0;
+        assert_eq!(board0, board1);
+    }
+
+    #[test]
+    fn test_disconnect_counts_as_resignation() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let addr = server.local_addr().unwrap();
+        let handle = std::thread::spawn(move || {
+            server.run(
+                MapConfig::new(8, 4),
+                2,
+                Duration::from_millis(5),
+                GameRng::seed_from_u64(2),
+            )
+        });
+        let (mut board0, mut reader0, _writer0) = connect(addr).unwrap();
+        let (_, reader1, writer1) = connect(addr).unwrap();
+        drop((reader1, writer1));
+
+        while !matches!(next(&mut reader0, &mut board0), ServerMessage::Over(_)) {}
+        assert_eq!(handle.join().unwrap().unwrap(), Outcome::Winner(0));
+    }
+
+    #[test]
+    fn test_broadcast_drops_clients_that_stop_reading() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let reader = TcpStream::connect(addr).unwrap();
+        let (reading, _) = listener.accept().unwrap();
+        let _stalled = TcpStream::connect(addr).unwrap();
+        let (stalled, _) = listener.accept().unwrap();
+        reading.set_write_timeout(Some(Duration::from_secs(5))).unwrap();
+        stalled.set_write_timeout(Some(Duration::from_millis(50))).unwrap();
+        // 另一端持续读取的连接不会被丢弃
+        std::thread::spawn(move || io::copy(&mut &reader, &mut io::sink()));
+
+        // 每行约 7 MB，几行之内就会填满不读取一方的发送缓冲区
+        let big = ServerMessage::Tick { tick: 1, snakes: Vec::new(), foods: vec![999_999; 1 << 20] };
+        let mut clients = [Some(reading), Some(stalled)];
+        let mut dropped = Vec::new();
+        for _ in 0..100 {
+            dropped = broadcast(&mut clients, &big);
+            if !dropped.is_empty() {
+                break;
+            }
+        }
+        assert_eq!(dropped, [1]);
+        assert!(clients[0].is_some() && clients[1].is_none());
+        assert!(broadcast(&mut clients, &big).is_empty());
+    }
+}
@@ -5,11 +5,14 @@
 //! 找到食物返回最优路径，超时/超限返回 best-so-far（离食物最近的方向）。
 
 use crate::config::MapConfig;
+use crate::multi::MultiSnakeGame;
 use crate::snake::SnakeGame;
+use crate::traffic_rom::TrafficRom;
 use crate::types::{Direction, Position};
 use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashSet, VecDeque};
-use std::hash::{Hash, Hasher};
+use std::hash::{BuildHasherDefault, Hasher};
+use std::time::{Duration, Instant};
 
 // ============================================================================
 // 交规 (Traffic Rules)
@@ -36,6 +39,59 @@ fn step(hash: usize, dir: Direction, cfg: &MapConfig) -> Option<usize> {
     Some(cfg.to_hash(Position { x: nx as u32, y: ny as u32 }))
 }
 
+/// 搜索用到的全部地图查询：交规方向与四邻接。
+///
+/// [`MapConfig`] 按坐标奇偶性现算；[`TrafficRom`]
+/// 只读预计算的 ROM 表，供硬件移植验证。
+pub trait TrafficRules {
+    /// 地图总格数
+    fn total_size(&self) -> usize;
+    /// 该格交规允许的两个方向
+    fn allowed(&self, hash: usize) -> [Direction; 2];
+    /// 向给定方向走一步（出界返回 None）
+    fn neighbor(&self, hash: usize, dir: Direction) -> Option<usize>;
+
+    /// 依次访问四邻（右、左、下、上，跳过出界者）；连通性守卫的热路径
+    #[inline]
+    fn for_each_neighbor(&self, hash: usize, mut visit: impl FnMut(usize)) {
+        for d in [Direction::Right, Direction::Left, Direction::Down, Direction::Up] {
+            if let Some(next) = self.neighbor(hash, d) {
+                visit(next);
+            }
+        }
+    }
+}
+
+impl TrafficRules for MapConfig {
+    #[inline]
+    fn total_size(&self) -> usize {
+        MapConfig::total_size(self)
+    }
+
+    #[inline]
+    fn allowed(&self, hash: usize) -> [Direction; 2] {
+        traffic_dirs(self.from_hash(hash))
+    }
+
+    #[inline]
+    fn neighbor(&self, hash: usize, dir: Direction) -> Option<usize> {
+        step(hash, dir, self)
+    }
+
+    #[inline]
+    fn for_each_neighbor(&self, cur: usize, mut visit: impl FnMut(usize)) {
+        let w = self.width as usize;
+        let h = self.height as usize;
+        let cx = cur % w;
+        let cy = cur / w;
+
+        if cx + 1 < w { visit(cur + 1); }       // Right
+        if cx > 0     { visit(cur - 1); }       // Left
+        if cy + 1 < h { visit(cur + w); }       // Down
+        if cy > 0     { visit(cur - w); }       // Up
+    }
+}
+
 // ============================================================================
 // BodyMask — 256 位位图，O(1) 碰撞检测
 // ============================================================================
@@ -43,10 +99,13 @@ fn step(hash: usize, dir: Direction, cfg: &MapConfig) -> Option<usize> {
 /// 蛇身占位位图（256 bits，覆盖 16×16 地图）。
 ///
 /// 用于 O(1) 碰撞检测，避免每次 O(L) 线性扫描 `body`。
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 struct BodyMask([u64; 4]);
 
 impl BodyMask {
+    /// 空位图
+    const EMPTY: BodyMask = BodyMask([0; 4]);
+
     fn from_body(body: &[usize]) -> Self {
         let mut bits = [0u64; 4];
         for &h in body {
@@ -75,90 +134,163 @@ impl BodyMask {
 // A* 状态空间搜索
 // ============================================================================
 
-/// A* 搜索中的状态：完整蛇身 + 当前方向。
+/// Zobrist 键：把一对值映射为 64 位伪随机数
 ///
-/// `body` 顺序：尾在 front (index 0)，头在 back (last index)。
-#[derive(Clone, Debug)]
-struct SearchState {
-    body: Vec<usize>,
-    dir: Direction,
-    /// 身体占位位图（派生自 body，用于 O(1) 碰撞检测，不参与哈希/判等）
-    mask: BodyMask,
+/// 相当于以 (a, b) 为下标的随机数表，但用 SplitMix64 的终结函数现算，
+/// 免去 256×256 的表。
+#[inline]
+fn zobrist_key(a: usize, b: usize) -> u64 {
+    let mut z = ((a as u64) << 32 | b as u64).wrapping_add(0x9E37_79B9_7F4A_7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+    z ^ (z >> 31)
 }
 
-impl SearchState {
-    fn new(body: Vec<usize>, dir: Direction) -> Self {
-        let mask = BodyMask::from_body(&body);
-        SearchState { body, dir, mask }
+/// 蛇头与方向的键用超出格子范围的第二分量区分
+const HEAD_TAG: usize = 1 << 16;
+const DIR_TAG: usize = HEAD_TAG + 1;
+
+/// 方向的 2 位编码
+#[inline]
+const fn dir_code(dir: Direction) -> usize {
+    match dir {
+        Direction::Up => 0,
+        Direction::Down => 1,
+        Direction::Left => 2,
+        Direction::Right => 3,
     }
+}
+
+const DIRECTIONS: [Direction; 4] = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
 
-    fn head(&self) -> usize {
-        *self.body.last().unwrap()
+/// 每格 2 位的方向表（256 格共 64 字节）：蛇身每一节指向下一节（朝头）的方向
+#[derive(Clone, Copy, Debug)]
+struct Links([u64; 8]);
+
+impl Links {
+    const EMPTY: Links = Links([0; 8]);
+
+    #[inline]
+    fn get(&self, h: usize) -> Direction {
+        DIRECTIONS[(self.0[h / 32] >> (h % 32 * 2)) as usize & 3]
     }
-}
 
-// mask 是 body 的派生，只比较 body + dir
-impl PartialEq for SearchState {
-    fn eq(&self, other: &Self) -> bool {
-        self.body == other.body && self.dir == other.dir
+    #[inline]
+    fn set(&mut self, h: usize, dir: Direction) {
+        let shift = h % 32 * 2;
+        self.0[h / 32] = self.0[h / 32] & !(3 << shift) | (dir_code(dir) as u64) << shift;
     }
 }
 
-impl Eq for SearchState {}
+/// A* 搜索中的状态：定长的蛇身表示 + 当前方向，每步 O(1) 更新、可直接复制。
+///
+/// 蛇身由占位位图 `mask`、尾 `tail` 与每节指向下一节的 `links` 表示，尾前进一步即沿 `links` 走一格。
+/// 出发时的蛇身不写入 `links`，前 L 步的尾改从初始蛇身读取（见 [`successors`]）。
+/// `hash` 是各节 (该节, 下一节)、蛇头、方向的 Zobrist 键的异或，随移动增量维护，作为判重依据。
+#[derive(Clone, Copy, Debug)]
+struct SearchState {
+    head: usize,
+    tail: usize,
+    dir: Direction,
+    /// 身体占位位图，O(1) 碰撞检测
+    mask: BodyMask,
+    links: Links,
+    hash: u64,
+}
 
-impl Hash for SearchState {
-    fn hash<H: Hasher>(&self, state: &mut H) {
-        self.body.hash(state);
-        self.dir.hash(state);
+impl SearchState {
+    /// 由完整蛇身（尾 → 头）构造初始状态
+    fn new(body: &[usize], dir: Direction) -> Self {
+        let head = *body.last().unwrap();
+        let mut hash = zobrist_key(head, HEAD_TAG) ^ zobrist_key(dir_code(dir), DIR_TAG);
+        for pair in body.windows(2) {
+            hash ^= zobrist_key(pair[0], pair[1]);
+        }
+        SearchState {
+            head,
+            tail: body[0],
+            dir,
+            mask: BodyMask::from_body(body),
+            links: Links::EMPTY,
+            hash,
+        }
     }
 }
 
-/// A* 搜索节点
-#[derive(Clone, Debug)]
-struct AStarNode {
+/// A* 搜索节点，存放在 arena 中，open set 只保存下标
+#[derive(Clone, Copy, Debug)]
+struct Node {
     state: SearchState,
+    /// 父节点下标（初始状态的后继为 `u32::MAX`）
+    parent: u32,
     /// 已走步数（g 值）
     g: u32,
+}
+
+/// open set 条目
+#[derive(Clone, Copy, Debug)]
+struct OpenEntry {
     /// f = g + h
     f: u32,
-    /// 从初始状态开始的第一步方向（最终返回值）
-    first_move: Direction,
+    /// f 相同时的次序键，越大越先展开（由 [`TieBreak`] 决定）
+    tie: u32,
+    /// 节点在 arena 中的下标
+    node: u32,
 }
 
-// BinaryHeap 是 max-heap，反转比较实现 min-heap（按 f 升序，f 相同按 g 降序）
-impl PartialEq for AStarNode {
+// BinaryHeap 是 max-heap，反转比较实现 min-heap（按 f 升序，f 相同按 tie 降序）
+impl PartialEq for OpenEntry {
     fn eq(&self, other: &Self) -> bool {
         self.f == other.f
     }
 }
 
-impl Eq for AStarNode {}
+impl Eq for OpenEntry {}
 
-impl Ord for AStarNode {
+impl Ord for OpenEntry {
     fn cmp(&self, other: &Self) -> Ordering {
-        other.f.cmp(&self.f).then_with(|| self.g.cmp(&other.g))
+        other.f.cmp(&self.f).then_with(|| self.tie.cmp(&other.tie))
     }
 }
 
-impl PartialOrd for AStarNode {
+impl PartialOrd for OpenEntry {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
+/// closed set 的哈希器：键已是均匀分布的 Zobrist 值，原样使用
+#[derive(Default)]
+struct ZobristHasher(u64);
+
+impl Hasher for ZobristHasher {
+    fn finish(&self) -> u64 {
+        self.0
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        for &b in bytes {
+            self.0 = self.0.rotate_left(8) ^ b as u64;
+        }
+    }
+
+    fn write_u64(&mut self, n: u64) {
+        self.0 = n;
+    }
+}
+
 /// 预计算：交规图上每个格子到最近食物的最短距离（忽略蛇身）。
 ///
 /// BFS 从所有食物出发，沿反向交规边传播。使用固定大小邻接表（[Option<usize>; 2]），
 /// 单次大分配替代 256 次小分配。
-fn traffic_dist_map(foods: &[usize], config: &MapConfig) -> Vec<u32> {
-    let n = config.total_size();
+fn traffic_dist_map(foods: &[usize], rules: &impl TrafficRules) -> Vec<u32> {
+    let n = rules.total_size();
     // 反向邻接表：每个格最多 2 个入边（交规保证），单次分配
     let mut rev_adj: Vec<[Option<usize>; 2]> = vec![[None; 2]; n];
     let mut rev_cnt = vec![0u8; n];
     for i in 0..n {
-        let pos = config.from_hash(i);
-        for &d in &traffic_dirs(pos) {
-            if let Some(j) = step(i, d, config) {
+        for &d in &rules.allowed(i) {
+            if let Some(j) = rules.neighbor(i, d) {
                 let k = rev_cnt[j] as usize;
                 rev_adj[j][k] = Some(i);
                 rev_cnt[j] += 1;
@@ -184,127 +316,293 @@ fn traffic_dist_map(foods: &[usize], config: &MapConfig) -> Vec<u32> {
     dist
 }
 
-/// 从 `state` 生成所有合法后继状态。
+/// 预计算：四邻接网格上每个格子到最近食物的距离（忽略交规与蛇身），即曼哈顿距离。
 ///
-/// 每步三个约束：交规 → 不撞身 → 连通性守卫。
-fn successors(state: &SearchState, config: &MapConfig) -> Vec<SearchState> {
-    let head = state.head();
-    let head_pos = config.from_hash(head);
-    let mut result = Vec::with_capacity(2);
-    let tail = state.body[0]; // 将被释放的尾
-
-    for &d in &traffic_dirs(head_pos) {
+/// 只依赖 [`TrafficRules::for_each_neighbor`]，所以 ROM 表同样适用。
+fn grid_dist_map(foods: &[usize], rules: &impl TrafficRules) -> Vec<u32> {
+    let mut dist = vec![u32::MAX; rules.total_size()];
+    let mut q = VecDeque::new();
+    for &f in foods {
+        dist[f] = 0;
+        q.push_back(f);
+    }
+    while let Some(cur) = q.pop_front() {
+        let d = dist[cur] + 1;
+        rules.for_each_neighbor(cur, |next| {
+            if dist[next] == u32::MAX {
+                dist[next] = d;
+                q.push_back(next);
+            }
+        });
+    }
+    dist
+}
+
+/// 从第 `g` 步的 `state` 生成所有合法后继状态（最多两个，不分配）。
+///
+/// 每步三个约束：交规 → 不撞身、不撞障碍 → 连通性守卫。
+/// `initial` 是出发时的蛇身，前 L 步释放的尾从中读取。
+/// `obstacles` 是其他蛇的身体，搜索期间视为静止；它们终将移走，所以不参与连通性守卫。
+fn successors(
+    state: &SearchState,
+    g: u32,
+    initial: &[usize],
+    obstacles: &BodyMask,
+    rules: &impl TrafficRules,
+) -> [Option<SearchState>; 2] {
+    let head = state.head;
+    let tail = state.tail; // 将被释放的尾
+    let mut result = [None; 2];
+
+    for (slot, &d) in result.iter_mut().zip(&rules.allowed(head)) {
         if d == state.dir.opposite() { continue; }
-        let new_head = match step(head, d, config) {
+        let new_head = match rules.neighbor(head, d) {
             Some(h) => h, None => continue,
         };
-        if state.mask.contains(new_head) { continue; }
+        if state.mask.contains(new_head) || obstacles.contains(new_head) { continue; }
 
         // 连通性守卫（bitmask 版，零分配）
-        if !keeps_empty_connected(new_head, &state.mask, tail, config) { continue; }
+        if !keeps_empty_connected(new_head, &state.mask, tail, rules) { continue; }
 
-        let mut mask = state.mask.clone();
-        mask.remove(tail);
-        mask.insert(new_head);
-
-        let new_body = {
-            let mut b = state.body[1..].to_vec();
-            b.push(new_head);
-            b
+        let mut next = *state;
+        next.links.set(head, d);
+        let new_tail = match initial.get(g as usize + 1) {
+            Some(&t) => t,
+            None => rules.neighbor(tail, next.links.get(tail)).expect("蛇身各节相邻"),
         };
-
-        result.push(SearchState { body: new_body, dir: d, mask });
+        next.mask.remove(tail);
+        next.mask.insert(new_head);
+        next.hash ^= zobrist_key(head, HEAD_TAG)
+            ^ zobrist_key(new_head, HEAD_TAG)
+            ^ zobrist_key(dir_code(state.dir), DIR_TAG)
+            ^ zobrist_key(dir_code(d), DIR_TAG)
+            ^ zobrist_key(head, new_head)
+            ^ zobrist_key(tail, new_tail);
+        next.head = new_head;
+        next.tail = new_tail;
+        next.dir = d;
+        *slot = Some(next);
     }
     result
 }
 
+/// 一次 A* 决策的完整结果：计划路径与搜索诊断，供可视化、日志与基准测试使用
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Plan {
+    /// 计划的方向序列，首项即本 tick 的决策（无合法后继时为空）
+    pub directions: Vec<Direction>,
+    /// 与 `directions` 一一对应，蛇头依次经过的格子（不含当前蛇头）
+    pub path: Vec<usize>,
+    /// 路径终点的食物；退回 best-so-far 时为 None
+    pub target: Option<usize>,
+    /// 各格到最近食物的启发距离（见 [`Heuristic`]；不可达为 `u32::MAX`）
+    pub dist: Vec<u32>,
+    /// 展开的状态数
+    pub expanded: usize,
+    /// 是否因展开数或时间超过上限而提前结束
+    pub budget_exhausted: bool,
+    /// 搜索耗时
+    pub elapsed: Duration,
+    /// [`Strategy::SafeTail`] 判定路径不安全，本步改为追尾
+    pub chasing_tail: bool,
+}
+
+impl Plan {
+    /// 本 tick 的决策
+    pub fn direction(&self) -> Option<Direction> {
+        self.directions.first().copied()
+    }
+
+    /// 是否找到了通往食物的路径（否则为离食物最近的 best-so-far 方向）
+    pub fn found(&self) -> bool {
+        self.target.is_some()
+    }
+}
+
+/// 从初始状态的后继回溯到 `at`，返回依次走的方向与经过的蛇头
+fn node_path(nodes: &[Node], mut at: u32) -> (Vec<Direction>, Vec<usize>) {
+    let (mut dirs, mut heads) = (Vec::new(), Vec::new());
+    while at != u32::MAX {
+        let node = &nodes[at as usize];
+        dirs.push(node.state.dir);
+        heads.push(node.state.head);
+        at = node.parent;
+    }
+    dirs.reverse();
+    heads.reverse();
+    (dirs, heads)
+}
+
+/// A* 启发函数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Heuristic {
+    /// 交规图距离（忽略蛇身），比曼哈顿更紧 → 展开更少状态
+    Traffic,
+    /// 曼哈顿距离（忽略交规与蛇身）
+    Manhattan,
+}
+
+/// f 值相同时先展开哪个节点
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TieBreak {
+    /// g 大者优先：更接近目标，通常展开更少
+    Deepest,
+    /// g 小者优先：接近广度优先
+    Shallowest,
+}
+
+/// A* AI 的搜索参数
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AiConfig {
+    /// 展开状态数上限，超出后返回 best-so-far
+    pub max_expanded: usize,
+    /// 单次决策的时间上限（None 表示不限）
+    pub deadline: Option<Duration>,
+    pub heuristic: Heuristic,
+    pub tie_break: TieBreak,
+    pub strategy: Strategy,
+}
+
+impl Default for AiConfig {
+    fn default() -> Self {
+        AiConfig {
+            max_expanded: 10_000,
+            deadline: None,
+            heuristic: Heuristic::Traffic,
+            tie_break: TieBreak::Deepest,
+            strategy: Strategy::Greedy,
+        }
+    }
+}
+
+/// 如何使用 A* 的结果
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    /// 直接采用 A* 找到的路径（或 best-so-far）
+    Greedy,
+    /// 让虚拟蛇沿路径吃到食物，确认之后仍能走到自己的尾巴才采用，否则改为追尾
+    SafeTail,
+}
+
 /// A* 搜索最优路径到食物（渐进式：超限返回 best-so-far）。
 ///
-/// 启发函数用交规图距离（忽略蛇身），比曼哈顿更紧 → 展开更少状态。
-/// 超 10k 展开状态或 open set 耗尽时返回离食物最近的方向，不返回 None。
+/// 超过 `ai.max_expanded` 个展开状态、超过 `ai.deadline` 或 open set 耗尽时
+/// 返回离食物最近的方向，不返回 None。
 fn astar_search(
     initial_body: Vec<usize>,
     initial_dir: Direction,
-    config: &MapConfig,
+    obstacles: &BodyMask,
+    rules: &impl TrafficRules,
     foods: &[usize],
-) -> Option<Direction> {
-    const MAX_EXPANDED: usize = 10_000;
-
-    // 预计算交规图距离（忽略蛇身），作为 A* 启发函数
-    let tdist = traffic_dist_map(foods, config);
+    ai: &AiConfig,
+) -> Plan {
+    let start = Instant::now();
+
+    // 预计算启发函数（忽略蛇身）
+    let tdist = match ai.heuristic {
+        Heuristic::Traffic => traffic_dist_map(foods, rules),
+        Heuristic::Manhattan => grid_dist_map(foods, rules),
+    };
+    let tie = |g: u32| match ai.tie_break {
+        TieBreak::Deepest => g,
+        TieBreak::Shallowest => u32::MAX - g,
+    };
 
-    let initial_state = SearchState::new(initial_body, initial_dir);
+    let initial_state = SearchState::new(&initial_body, initial_dir);
 
     let mut open = BinaryHeap::with_capacity(1024);
-    let mut closed = HashSet::with_capacity(1024);
+    let mut closed: HashSet<u64, BuildHasherDefault<ZobristHasher>> =
+        HashSet::with_capacity_and_hasher(1024, Default::default());
+    let mut nodes: Vec<Node> = Vec::with_capacity(1024);
     let mut expanded: usize = 0;
 
     // 渐进式：跟踪搜索到的最接近食物的状态
     let mut best_h: u32 = u32::MAX;
-    let mut best_move: Option<Direction> = None;
-
-    // 从初始状态展开一步，每个后继的方向就是第一步方向
-    for succ in successors(&initial_state, config) {
-        let succ_head = succ.head();
-        let succ_dir = succ.dir;
-        if foods.contains(&succ_head) {
-            return Some(succ_dir);
-        }
-        let h = tdist[succ_head];
-        if h < best_h { best_h = h; best_move = Some(succ_dir); }
-        open.push(AStarNode {
-            state: succ,
-            g: 1,
-            f: 1u32.saturating_add(h),
-            first_move: succ_dir,
-        });
+    let mut best: Option<u32> = None;
+
+    // 回溯 `end` 处的路径；找到食物时 `target` 即路径终点
+    let finish = |dist, nodes: &[Node], expanded, found: bool, exhausted, end: Option<u32>| {
+        let (directions, path) = end.map_or_else(Default::default, |at| node_path(nodes, at));
+        Plan {
+            target: if found { path.last().copied() } else { None },
+            directions,
+            path,
+            dist,
+            expanded,
+            budget_exhausted: exhausted,
+            elapsed: start.elapsed(),
+            chasing_tail: false,
+        }
+    };
+
+    // 从初始状态展开一步
+    for succ in successors(&initial_state, 0, &initial_body, obstacles, rules).into_iter().flatten() {
+        let at = nodes.len() as u32;
+        nodes.push(Node { state: succ, parent: u32::MAX, g: 1 });
+        if foods.contains(&succ.head) {
+            return finish(tdist, &nodes, expanded, true, false, Some(at));
+        }
+        let h = tdist[succ.head];
+        if h < best_h { best_h = h; best = Some(at); }
+        open.push(OpenEntry { f: 1u32.saturating_add(h), tie: tie(1), node: at });
     }
 
-    while let Some(node) = open.pop() {
-        // 状态去重（clone 成本 ≈ hash lookup 成本，两者都 O(L)）
-        if !closed.insert(node.state.clone()) { continue; }
+    while let Some(entry) = open.pop() {
+        let Node { state, g, .. } = nodes[entry.node as usize];
+        // 状态去重：只比较 Zobrist 哈希
+        if !closed.insert(state.hash) { continue; }
 
         expanded += 1;
-        let node_h = tdist[node.state.head()];
-        if node_h < best_h { best_h = node_h; best_move = Some(node.first_move); }
+        let node_h = tdist[state.head];
+        if node_h < best_h { best_h = node_h; best = Some(entry.node); }
 
-        if expanded > MAX_EXPANDED { return best_move; }
-        if foods.contains(&node.state.head()) { return Some(node.first_move); }
+        let out_of_time = ai.deadline.is_some_and(|d| start.elapsed() > d);
+        if expanded > ai.max_expanded || out_of_time {
+            return finish(tdist, &nodes, expanded, false, true, best);
+        }
+        if foods.contains(&state.head) {
+            return finish(tdist, &nodes, expanded, true, false, Some(entry.node));
+        }
 
         // 展开后继
-        for succ in successors(&node.state, config) {
-            if closed.contains(&succ) { continue; }
-            let succ_head = succ.head();
-            if foods.contains(&succ_head) { return Some(node.first_move); }
-            let h = tdist[succ_head];
-            open.push(AStarNode {
-                state: succ,
-                g: node.g + 1,
-                f: (node.g + 1).saturating_add(h),
-                first_move: node.first_move,
-            });
+        for succ in successors(&state, g, &initial_body, obstacles, rules).into_iter().flatten() {
+            if closed.contains(&succ.hash) { continue; }
+            let at = nodes.len() as u32;
+            nodes.push(Node { state: succ, parent: entry.node, g: g + 1 });
+            if foods.contains(&succ.head) {
+                return finish(tdist, &nodes, expanded, true, false, Some(at));
+            }
+            let h = tdist[succ.head];
+            open.push(OpenEntry { f: (g + 1).saturating_add(h), tie: tie(g + 1), node: at });
         }
     }
 
-    // open set 耗尽：返回最佳近似方向，或降级到 BFS fallback
-    best_move
+    // open set 耗尽：返回最佳近似方向
+    finish(tdist, &nodes, expanded, false, false, best)
 }
 
 /// 空白区连通性 — 模拟一步（尾释放 `tail`，头占据 `new_head`）后，空白区是否单连通。
 ///
 /// 全栈上操作，零堆分配：bitmask 记录空格/访问状态，固定数组做 BFS 栈。
 fn keeps_empty_connected(
-    new_head: usize, body_mask: &BodyMask, tail: usize, config: &MapConfig,
+    new_head: usize, body_mask: &BodyMask, tail: usize, rules: &impl TrafficRules,
 ) -> bool {
-    let w = config.width as usize;
-    let h = config.height as usize;
-    let n = config.total_size();
+    let n = rules.total_size();
     debug_assert!(n <= 256, "stack is sized for 16×16");
 
     // 构建空位 bitmask：!body | tail & !new_head
     let mut empty = [!body_mask.0[0], !body_mask.0[1], !body_mask.0[2], !body_mask.0[3]];
     empty[tail / 64] |= 1u64 << (tail % 64);
     empty[new_head / 64] &= !(1u64 << (new_head % 64));
+    // 地图不足 256 格时，超出 n 的位不是格子
+    for (i, bits) in empty.iter_mut().enumerate() {
+        let lo = i * 64;
+        if n <= lo {
+            *bits = 0;
+        } else if n < lo + 64 {
+            *bits &= (1u64 << (n - lo)) - 1;
+        }
+    }
 
     // 找第一个空格作为 BFS 起点
     let start = empty.iter()
@@ -320,20 +618,6 @@ fn keeps_empty_connected(
     let mut seen = [0u64; 4];
     let mut seen_cnt = 1u16;
 
-    // 内联辅助：试探格子 n，若为空且未访问则入栈
-    macro_rules! try_visit {
-        ($n:expr) => {
-            let idx = $n / 64;
-            let bit = 1u64 << ($n % 64);
-            if (empty[idx] & bit) != 0 && (seen[idx] & bit) == 0 {
-                seen[idx] |= bit;
-                seen_cnt += 1;
-                stack[sp as usize] = $n;
-                sp += 1;
-            }
-        };
-    }
-
     seen[start / 64] |= 1u64 << (start % 64);
     stack[sp as usize] = start;
     sp += 1;
@@ -341,13 +625,17 @@ fn keeps_empty_connected(
     while sp > 0 {
         sp -= 1;
         let cur = stack[sp as usize];
-        let cx = cur % w;
-        let cy = cur / w;
-
-        if cx + 1 < w { try_visit!(cur + 1); }       // Right
-        if cx > 0     { try_visit!(cur - 1); }       // Left
-        if cy + 1 < h { try_visit!(cur + w); }       // Down
-        if cy > 0     { try_visit!(cur - w); }       // Up
+        // 试探格子 n，若为空且未访问则入栈
+        rules.for_each_neighbor(cur, |n| {
+            let idx = n / 64;
+            let bit = 1u64 << (n % 64);
+            if (empty[idx] & bit) != 0 && (seen[idx] & bit) == 0 {
+                seen[idx] |= bit;
+                seen_cnt += 1;
+                stack[sp as usize] = n;
+                sp += 1;
+            }
+        });
     }
 
     let empty_cnt: u16 = empty[0].count_ones() as u16
@@ -367,6 +655,16 @@ fn keeps_empty_connected(
 /// 找到食物返回最优路径，超 10k 状态返回 best-so-far（离食物最近的方向）。
 /// 交规保证强连通——只要不撞身就永远有路，连通性守卫在 `successors` 中保证路径质量。
 pub fn next_dir(snake: &SnakeGame) -> Option<Direction> {
+    next_dir_with(snake, &AiConfig::default())
+}
+
+/// 按给定的搜索参数选择下一步方向
+pub fn next_dir_with(snake: &SnakeGame, ai: &AiConfig) -> Option<Direction> {
+    plan(snake, ai)?.direction()
+}
+
+/// 与 [`next_dir_with`] 相同的搜索，但返回完整的计划与诊断（无食物或尚未出发时为 None）
+pub fn plan(snake: &SnakeGame, ai: &AiConfig) -> Option<Plan> {
     let cfg = snake.config();
     let foods = snake.food_hashes();
     if foods.is_empty() {
@@ -375,7 +673,219 @@ pub fn next_dir(snake: &SnakeGame) -> Option<Direction> {
     let cur = snake.direction()?;
     let body: Vec<usize> = snake.snake_hashes().copied().collect();
 
-    astar_search(body, cur, cfg, foods)
+    Some(match ai.strategy {
+        Strategy::Greedy => astar_search(body, cur, &BodyMask::EMPTY, cfg, foods, ai),
+        Strategy::SafeTail => {
+            let plan = astar_search(body.clone(), cur, &BodyMask::EMPTY, cfg, foods, ai);
+            check_tail_safety(plan, &body, cur, foods, cfg)
+        }
+    })
+}
+
+// ============================================================================
+// 追尾安全检查
+// ============================================================================
+
+/// 蛇头沿交规走到尾巴所在格的最短步数（蛇身视为静止；走不到为 None）
+///
+/// 尾巴要在下一 tick 才离开，紧挨着的一步会撞上（见 `SnakeGame::update`）；
+/// 途经食物时蛇身变长、尾巴也不动。因此路线在踏上尾巴之前必须至少有一步没有吃到食物。
+fn tail_distance(body: &[usize], foods: &[usize], rules: &impl TrafficRules) -> Option<u32> {
+    let (&tail, &head) = (body.first()?, body.last()?);
+    if body.len() == 1 {
+        return Some(0);
+    }
+    let body_mask = BodyMask::from_body(body);
+    let food_mask = BodyMask::from_body(foods);
+    // 按"尾巴是否已经离开"分开记录访问过的格子
+    let mut seen = [body_mask; 2];
+    let mut q = VecDeque::from([(head, 0u32, false)]);
+    while let Some((cur, d, moved)) = q.pop_front() {
+        for dir in rules.allowed(cur) {
+            let Some(next) = rules.neighbor(cur, dir) else { continue };
+            if next == tail {
+                if moved {
+                    return Some(d + 1);
+                }
+                continue;
+            }
+            let moved = moved || !food_mask.contains(next);
+            if !seen[moved as usize].contains(next) {
+                seen[moved as usize].insert(next);
+                q.push_back((next, d + 1, moved));
+            }
+        }
+    }
+    None
+}
+
+/// [`Strategy::SafeTail`]：路径安全时原样采用，否则选走一步之后离尾巴最远、且仍能走到尾巴的方向
+///
+/// 路径安全指虚拟蛇沿路径吃到食物（长度 +1）后 [`tail_distance`] 有解。
+/// 没有任何一步能保持追尾时退回原计划。
+fn check_tail_safety(
+    mut plan: Plan,
+    body: &[usize],
+    dir: Direction,
+    foods: &[usize],
+    rules: &impl TrafficRules,
+) -> Plan {
+    if plan.found() {
+        let grown = body.len() + 1;
+        let virtual_body: Vec<usize> = body.iter().chain(&plan.path).copied().collect();
+        let virtual_body = &virtual_body[virtual_body.len().saturating_sub(grown)..];
+        let remaining: Vec<usize> = foods.iter().copied().filter(|&f| Some(f) != plan.target).collect();
+        if grown >= rules.total_size() || tail_distance(virtual_body, &remaining, rules).is_some() {
+            return plan;
+        }
+    }
+
+    // 候选只受交规与蛇身约束，不经过 `successors` 的连通性守卫：追尾本身就是安全判据
+    let start = Instant::now();
+    let &head = body.last().expect("蛇身非空");
+    let mask = BodyMask::from_body(body);
+    let chase = rules
+        .allowed(head)
+        .into_iter()
+        .filter(|&d| d != dir.opposite())
+        .filter_map(|d| {
+            let next = rules.neighbor(head, d).filter(|&h| !mask.contains(h))?;
+            // 这一步吃到食物时尾巴不动
+            let keep = if foods.contains(&next) { 0 } else { 1 };
+            let moved: Vec<usize> = body[keep..].iter().copied().chain([next]).collect();
+            Some((tail_distance(&moved, foods, rules)?, d, next))
+        })
+        .max_by_key(|&(dist, ..)| dist);
+    if let Some((_, d, next)) = chase {
+        plan.directions = vec![d];
+        plan.path = vec![next];
+        plan.target = None;
+        plan.chasing_tail = true;
+        plan.elapsed += start.elapsed();
+    }
+    plan
+}
+
+/// 缓存计划的 AI：找到通往食物的路径后逐 tick 重放，不再每 tick 重新搜索
+///
+/// 每次决策前核对实际局面与计划的模拟是否一致（食物未变、蛇身与按计划爬行的结果相同），
+/// 不一致（被回退、外部改动等）或计划走完（吃到食物）时才重新搜索。
+/// 退回 best-so-far 的计划不缓存，与 [`next_dir`] 一样每 tick 重搜。
+#[derive(Debug, Clone, Default)]
+pub struct Planner {
+    ai: AiConfig,
+    cached: Option<CachedPlan>,
+    replans: u64,
+}
+
+/// 缓存的计划及其出发时的局面
+#[derive(Debug, Clone)]
+struct CachedPlan {
+    plan: Plan,
+    /// 出发时的蛇身（尾 → 头）
+    body: Vec<usize>,
+    foods: Vec<usize>,
+    /// 已重放的步数
+    taken: usize,
+}
+
+impl CachedPlan {
+    /// 实际局面是否恰好是计划走了 `taken` 步之后的样子
+    fn matches(&self, snake: &SnakeGame) -> bool {
+        let k = self.taken;
+        if k == 0 || k >= self.plan.directions.len() {
+            return false;
+        }
+        let len = self.body.len();
+        // 期望蛇身：出发时蛇身与计划路径前 k 格拼接后的末尾 len 格
+        let expected = self.body.iter().chain(&self.plan.path[..k]).skip(k);
+        snake.length() == len
+            && snake.direction() == Some(self.plan.directions[k - 1])
+            && snake.food_hashes() == self.foods.as_slice()
+            && snake.snake_hashes().eq(expected)
+    }
+}
+
+impl Planner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 使用给定搜索参数的 Planner
+    pub fn with_config(ai: AiConfig) -> Self {
+        Planner {
+            ai,
+            ..Self::default()
+        }
+    }
+
+    /// 与 [`next_dir`] 相同的接口；局面与缓存的计划一致时直接取计划的下一步
+    pub fn next_dir(&mut self, snake: &SnakeGame) -> Option<Direction> {
+        if let Some(cached) = &mut self.cached
+            && cached.matches(snake)
+        {
+            let dir = cached.plan.directions[cached.taken];
+            cached.taken += 1;
+            return Some(dir);
+        }
+
+        self.cached = None;
+        let plan = plan(snake, &self.ai)?;
+        self.replans += 1;
+        let dir = plan.direction();
+        if plan.found() && dir.is_some() {
+            self.cached = Some(CachedPlan {
+                plan,
+                body: snake.snake_hashes().copied().collect(),
+                foods: snake.food_hashes().to_vec(),
+                taken: 1,
+            });
+        }
+        dir
+    }
+
+    /// 当前缓存的计划（无缓存时为 None）
+    pub fn plan(&self) -> Option<&Plan> {
+        self.cached.as_ref().map(|c| &c.plan)
+    }
+
+    /// 实际执行搜索的次数
+    pub fn replans(&self) -> u64 {
+        self.replans
+    }
+}
+
+/// 与 [`next_dir`] 相同的决策，但交规与邻接只从预计算的 ROM 表读取。
+///
+/// 用于验证硬件移植时只需查表即可复现 AI 行为。
+pub fn next_dir_rom(snake: &SnakeGame, rom: &TrafficRom) -> Option<Direction> {
+    let foods = snake.food_hashes();
+    if foods.is_empty() {
+        return None;
+    }
+    let cur = snake.direction()?;
+    let body: Vec<usize> = snake.snake_hashes().copied().collect();
+
+    astar_search(body, cur, &BodyMask::EMPTY, rom, foods, &AiConfig::default()).direction()
+}
+
+/// 多蛇局中为第 `i` 条蛇选择方向，其余蛇（含已死亡的）的身体都视为障碍
+///
+/// 与 [`next_dir`] 相同的搜索；地图不超过 256 格。
+pub fn next_dir_multi(game: &MultiSnakeGame, i: usize) -> Option<Direction> {
+    let foods = game.food_hashes();
+    if foods.is_empty() {
+        return None;
+    }
+    let cur = game.direction(i)?;
+    let body: Vec<usize> = game.snake_hashes(i).copied().collect();
+    let others: Vec<usize> = (0..game.snake_count())
+        .filter(|&j| j != i)
+        .flat_map(|j| game.snake_hashes(j).copied())
+        .collect();
+
+    let ai = AiConfig::default();
+    astar_search(body, cur, &BodyMask::from_body(&others), game.config(), foods, &ai).direction()
 }
 
 // ============================================================================
@@ -448,15 +958,49 @@ mod tests {
     // A* 状态空间搜索
     // -----------------------------------------------------------------------
 
+    /// 以 `body` 为初始蛇身生成第一步的后继
+    fn first_successors(body: &[usize], dir: Direction, obstacles: &BodyMask, cfg: &MapConfig) -> Vec<SearchState> {
+        successors(&SearchState::new(body, dir), 0, body, obstacles, cfg).into_iter().flatten().collect()
+    }
+
     #[test]
-    fn test_search_state_eq_is_body_and_dir_only() {
-        let s1 = SearchState::new(vec![0, 1, 2], Direction::Right);
-        let s2 = SearchState::new(vec![0, 1, 2], Direction::Right);
-        let s3 = SearchState::new(vec![0, 1, 2], Direction::Up);
-        let s4 = SearchState::new(vec![0, 1, 3], Direction::Right);
-        assert_eq!(s1, s2);
-        assert_ne!(s1, s3);
-        assert_ne!(s1, s4);
+    fn test_search_state_hash_is_body_and_dir_only() {
+        let s1 = SearchState::new(&[0, 1, 2], Direction::Right);
+        let s2 = SearchState::new(&[0, 1, 2], Direction::Right);
+        let s3 = SearchState::new(&[0, 1, 2], Direction::Up);
+        let s4 = SearchState::new(&[0, 1, 3], Direction::Right);
+        let s5 = SearchState::new(&[1, 0, 2], Direction::Right);
+        assert_eq!(s1.hash, s2.hash);
+        assert_ne!(s1.hash, s3.hash);
+        assert_ne!(s1.hash, s4.hash);
+        // 同样的格子、不同的顺序是不同的蛇身
+        assert_ne!(s1.hash, s5.hash);
+    }
+
+    #[test]
+    fn test_incremental_state_matches_rebuilt() {
+        // 沿一条绕圈的路线连走多步（超过蛇长，尾进入 links 管理的部分），
+        // 每步的增量哈希、位图、头尾都应与按完整蛇身重建的状态一致
+        let cfg = MapConfig::new(16, 16);
+        let mut body: Vec<usize> = [(2, 4), (3, 4), (4, 4), (5, 4)]
+            .map(|(x, y)| cfg.to_hash(Position { x, y }))
+            .to_vec();
+        let initial = body.clone();
+        let mut state = SearchState::new(&body, Direction::Right);
+        for g in 0..12 {
+            let next = successors(&state, g, &initial, &BodyMask::EMPTY, &cfg)
+                .into_iter()
+                .flatten()
+                .next()
+                .expect("空地图上总有后继");
+            body.remove(0);
+            body.push(next.head);
+            let rebuilt = SearchState::new(&body, next.dir);
+            assert_eq!(next.hash, rebuilt.hash, "g={g}");
+            assert_eq!(next.mask.0, rebuilt.mask.0, "g={g}");
+            assert_eq!((next.head, next.tail), (rebuilt.head, rebuilt.tail), "g={g}");
+            state = next;
+        }
     }
 
     #[test]
@@ -471,7 +1015,7 @@ mod tests {
             "交规图上所有格都应能到达食物");
         // 可采纳性：交规距离 ≥ 曼哈顿距离
         let pos = Position { x: 5, y: 5 };
-        let manhattan = (pos.x.abs_diff(8) + pos.y.abs_diff(10)) as u32;
+        let manhattan = pos.x.abs_diff(8) + pos.y.abs_diff(10);
         assert!(tdist[cfg.to_hash(pos)] >= manhattan,
             "交规距离({})应 ≥ 曼哈顿距离({})", tdist[cfg.to_hash(pos)], manhattan);
     }
@@ -496,21 +1040,18 @@ mod tests {
     fn test_successors_basic() {
         let cfg = MapConfig::new(16, 16);
         // 长度 3 的蛇，水平在偶数行 y=4 → 交规允许 Right 和 (Up/Down)
-        let state = SearchState::new(
-            vec![
-                cfg.to_hash(Position { x: 3, y: 4 }), // tail
-                cfg.to_hash(Position { x: 4, y: 4 }),
-                cfg.to_hash(Position { x: 5, y: 4 }), // head
-            ],
-            Direction::Right,
-        );
-        let succs = successors(&state, &cfg);
+        let body = [
+            cfg.to_hash(Position { x: 3, y: 4 }), // tail
+            cfg.to_hash(Position { x: 4, y: 4 }),
+            cfg.to_hash(Position { x: 5, y: 4 }), // head
+        ];
+        let succs = first_successors(&body, Direction::Right, &BodyMask::EMPTY, &cfg);
         // 偶数行(y=4)→Right + 偶数列(x=5)→Up；Right 不是 opposite，Up 不是 opposite
         assert_eq!(succs.len(), 2, "两个交规方向都应合法");
         // 校验不包含 180°
         for s in &succs {
             assert_ne!(s.dir, Direction::Left, "不应 180° 掉头");
-            assert_eq!(s.body.len(), 3, "长度不变");
+            assert_eq!(s.mask.0.iter().map(|w| w.count_ones()).sum::<u32>(), 3, "长度不变");
         }
     }
 
@@ -520,15 +1061,12 @@ mod tests {
         // 蛇身形成"墙" → 只有一个方向能走
         // 头朝右，(5,4)→Right 到 (6,4)，但 (6,4) 被身体占据
         // (5,4) 奇列 → Up (not Down)，应只剩 Up
-        let state = SearchState::new(
-            vec![
-                cfg.to_hash(Position { x: 4, y: 4 }), // tail
-                cfg.to_hash(Position { x: 5, y: 4 }), // mid
-                cfg.to_hash(Position { x: 6, y: 4 }), // head
-            ],
-            Direction::Right,
-        );
-        let succs = successors(&state, &cfg);
+        let body = [
+            cfg.to_hash(Position { x: 4, y: 4 }), // tail
+            cfg.to_hash(Position { x: 5, y: 4 }), // mid
+            cfg.to_hash(Position { x: 6, y: 4 }), // head
+        ];
+        let succs = first_successors(&body, Direction::Right, &BodyMask::EMPTY, &cfg);
         // (6,4): x=6 偶列→Up, y=4 偶行→Right. Right next=(7,4) OK, Up next=(6,3) OK
         // 但 head 朝 Right，检查 successor: Right→(7,4) 不在 body[1..]=[(5,4),(6,4)]? (7,4) OK
         // Up→(6,3) 不在 body[1..] OK
@@ -538,15 +1076,12 @@ mod tests {
     #[test]
     fn test_successors_respects_180_rule() {
         let cfg = MapConfig::new(16, 16);
-        let state = SearchState::new(
-            vec![
-                cfg.to_hash(Position { x: 3, y: 4 }),
-                cfg.to_hash(Position { x: 4, y: 4 }),
-                cfg.to_hash(Position { x: 5, y: 4 }),
-            ],
-            Direction::Right,
-        );
-        let succs = successors(&state, &cfg);
+        let body = [
+            cfg.to_hash(Position { x: 3, y: 4 }),
+            cfg.to_hash(Position { x: 4, y: 4 }),
+            cfg.to_hash(Position { x: 5, y: 4 }),
+        ];
+        let succs = first_successors(&body, Direction::Right, &BodyMask::EMPTY, &cfg);
         for s in &succs {
             assert_ne!(s.dir, Direction::Left); // 180°
         }
@@ -562,11 +1097,89 @@ mod tests {
             cfg.to_hash(Position { x: 3, y: 2 }),
         ];
         let foods = [cfg.to_hash(Position { x: 8, y: 2 })]; // 同行，偶数行 → Right 可达
-        let result = astar_search(body, Direction::Right, &cfg, &foods);
+        let result = astar_search(body, Direction::Right, &BodyMask::EMPTY, &cfg, &foods, &AiConfig::default()).direction();
         assert!(result.is_some());
         assert_ne!(result.unwrap(), Direction::Right.opposite());
     }
 
+    #[test]
+    fn test_plan_path_reaches_food() {
+        let cfg = MapConfig::new(16, 16);
+        let body: Vec<usize> = [(1, 3), (2, 3), (3, 3)]
+            .map(|(x, y)| cfg.to_hash(Position { x, y }))
+            .to_vec();
+        let food = cfg.to_hash(Position { x: 9, y: 10 });
+        let plan = astar_search(body.clone(), Direction::Left, &BodyMask::EMPTY, &cfg, &[food], &AiConfig::default());
+        assert!(plan.found());
+        assert!(!plan.budget_exhausted);
+        assert!(plan.expanded > 0);
+        assert_eq!(plan.target, Some(food));
+        assert_eq!(plan.dist[food], 0);
+        assert_eq!(plan.path.last(), Some(&food));
+        assert_eq!(plan.directions.len(), plan.path.len());
+        // 路径从蛇头出发，每步都沿交规允许的方向走到下一格
+        let mut prev = body[2];
+        for (&dir, &cell) in plan.directions.iter().zip(&plan.path) {
+            assert!(cfg.allowed(prev).contains(&dir));
+            assert_eq!(step(prev, dir, &cfg), Some(cell));
+            prev = cell;
+        }
+    }
+
+    #[test]
+    fn test_plan_fallback_when_food_unreachable() {
+        // 食物四周都是障碍，只能退回离食物最近的方向
+        let cfg = MapConfig::new(16, 16);
+        let body: Vec<usize> = [(0, 0), (1, 0), (2, 0)]
+            .map(|(x, y)| cfg.to_hash(Position { x, y }))
+            .to_vec();
+        let food = cfg.to_hash(Position { x: 4, y: 4 });
+        let walls: Vec<usize> = [(3, 4), (5, 4), (4, 3), (4, 5)]
+            .map(|(x, y)| cfg.to_hash(Position { x, y }))
+            .to_vec();
+        let plan = astar_search(body.clone(), Direction::Right, &BodyMask::from_body(&walls), &cfg, &[food], &AiConfig::default());
+        assert!(!plan.found());
+        assert_eq!(plan.target, None);
+        let dir = plan.direction().unwrap();
+        assert_eq!(step(body[2], dir, &cfg), Some(plan.path[0]));
+        assert!(!plan.path.contains(&food));
+    }
+
+    #[test]
+    fn test_ai_config_budget_and_heuristics() {
+        let cfg = MapConfig::new(16, 16);
+        let body: Vec<usize> = [(1, 3), (2, 3), (3, 3)]
+            .map(|(x, y)| cfg.to_hash(Position { x, y }))
+            .to_vec();
+        let food = [cfg.to_hash(Position { x: 12, y: 11 })];
+        let search = |ai: AiConfig| astar_search(body.clone(), Direction::Left, &BodyMask::EMPTY, &cfg, &food, &ai);
+
+        // 两种启发函数都可采纳，配合两种次序都应找到同样长度的最短路径
+        let best = search(AiConfig::default());
+        assert!(best.found());
+        for heuristic in [Heuristic::Traffic, Heuristic::Manhattan] {
+            for tie_break in [TieBreak::Deepest, TieBreak::Shallowest] {
+                let plan = search(AiConfig { heuristic, tie_break, ..AiConfig::default() });
+                assert!(plan.found(), "{heuristic:?} {tie_break:?}");
+                assert_eq!(plan.path.len(), best.path.len(), "{heuristic:?} {tie_break:?}");
+            }
+        }
+        // 曼哈顿距离比交规距离宽松，展开更多状态
+        let manhattan = search(AiConfig { heuristic: Heuristic::Manhattan, ..AiConfig::default() });
+        assert!(manhattan.expanded >= best.expanded);
+
+        // 预算耗尽与超时都退回 best-so-far，仍给出方向
+        for ai in [
+            AiConfig { max_expanded: 0, ..AiConfig::default() },
+            AiConfig { deadline: Some(Duration::ZERO), ..AiConfig::default() },
+        ] {
+            let plan = search(ai);
+            assert!(plan.budget_exhausted && !plan.found());
+            assert_eq!(plan.expanded, 1);
+            assert!(plan.direction().is_some());
+        }
+    }
+
     #[test]
     fn test_astar_no_self_collision() {
         // 模拟 A* 返回的路径，逐帧验证无自撞
@@ -578,7 +1191,7 @@ mod tests {
         ];
         let foods = [cfg.to_hash(Position { x: 10, y: 2 })];
 
-        let dir = astar_search(initial_body.clone(), Direction::Right, &cfg, &foods);
+        let dir = astar_search(initial_body.clone(), Direction::Right, &BodyMask::EMPTY, &cfg, &foods, &AiConfig::default()).direction();
         assert!(dir.is_some());
 
         // 手动模拟几步验证
@@ -614,7 +1227,7 @@ mod tests {
             cfg.to_hash(Position { x: 2, y: 0 }),
         ];
         let foods = [cfg.to_hash(Position { x: 5, y: 0 })]; // 同行偶数行，但前面是蛇身
-        let _result = astar_search(body, Direction::Right, &cfg, &foods);
+        let _result = astar_search(body, Direction::Right, &BodyMask::EMPTY, &cfg, &foods, &AiConfig::default());
         // body[1..] 不包含 (3,0)，所以 A* 应该能找到路（偶数行 Right 直线可达）
         // 重测：构造一个真正 blocked 的场景
         // 蛇朝右，前面一堆身体挡住
@@ -627,7 +1240,7 @@ mod tests {
             cfg.to_hash(Position { x: 0, y: 0 }), // head (wrap around conceptually...)
         ];
         // 正常调用不 panic 即可
-        let _ = astar_search(blocked_body, Direction::Right, &cfg, &foods);
+        let _ = astar_search(blocked_body, Direction::Right, &BodyMask::EMPTY, &cfg, &foods, &AiConfig::default());
     }
 
     #[test]
@@ -695,4 +1308,242 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_successors_blocked_by_obstacle() {
+        let cfg = MapConfig::new(16, 16);
+        let body = [
+            cfg.to_hash(Position { x: 3, y: 4 }),
+            cfg.to_hash(Position { x: 4, y: 4 }),
+            cfg.to_hash(Position { x: 5, y: 4 }), // head: 交规 Right/Down
+        ];
+        let obstacles = BodyMask::from_body(&[cfg.to_hash(Position { x: 6, y: 4 })]);
+        let succs = first_successors(&body, Direction::Right, &obstacles, &cfg);
+        assert_eq!(succs.len(), 1);
+        assert_eq!(succs[0].dir, Direction::Down);
+    }
+
+    #[test]
+    fn test_multi_ai_avoids_other_snake() {
+        // 蛇 1 原地不动，蛇 0 由 AI 驱动绕开它吃食物
+        for seed in 0..5 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut game = MultiSnakeGame::new(MapConfig::new(16, 16), 2, 3, 3, &mut rng);
+            for step in 0..200 {
+                // 被障碍困住时搜索无解，不算撞上
+                let Some(dir) = next_dir_multi(&game, 0) else {
+                    break;
+                };
+                game.update(&[Some(dir), None], &mut rng);
+                assert_eq!(
+                    game.state(0),
+                    crate::types::GameState::Running,
+                    "seed={seed} step={step}"
+                );
+            }
+            assert!(game.score(0) > 0);
+        }
+    }
+
+    #[test]
+    fn test_planner_replays_optimal_plan() {
+        use crate::game::Game;
+        use crate::types::GameState;
+
+        // 同长度的路径可能不止一条，重放与重搜的具体走法可以不同，
+        // 但每个 tick 缓存计划剩余的步数都应等于从当前局面重新搜索的最短步数
+        for (seed, food) in [(0, 1), (2, 3)] {
+            let mut game = Game::with_seed(MapConfig::new(16, 16), 3, food, seed);
+            let mut planner = Planner::new();
+            let mut ticks = 0;
+            while ticks < 300 {
+                let dir = planner.next_dir(game.snake());
+                if let Some(cached) = &planner.cached
+                    && cached.taken > 1
+                {
+                    let fresh = plan(game.snake(), &AiConfig::default()).unwrap();
+                    assert!(fresh.found(), "seed={seed} tick={ticks}");
+                    assert_eq!(fresh.path.len(), cached.plan.path.len() - (cached.taken - 1), "seed={seed} tick={ticks}");
+                    assert_eq!(fresh.target, cached.plan.target, "seed={seed} tick={ticks}");
+                }
+                ticks += 1;
+                if matches!(game.tick(dir), GameState::Over | GameState::Won) {
+                    break;
+                }
+            }
+            assert!(planner.replans() * 2 < ticks, "seed={seed}: {} replans in {ticks} ticks", planner.replans());
+        }
+    }
+
+    #[test]
+    fn test_planner_plays_the_same_games_as_next_dir() {
+        use crate::game::Game;
+        use crate::types::GameState;
+
+        // 整局逐 tick 对比 Planner 与每 tick 重搜的 next_dir，两局都必须获胜。
+        // 两者走的都是最短路径，但同长度的路线可能不止一条：重放出发时的计划与从中途重搜
+        // 可能选了不同的一条。路线一旦分开，蛇身不同导致空格顺序不同，此后生成的食物也不同，
+        // 整局不可能再逐格相同。因此：没有分开的对局要求吃到每份食物的 tick 完全相同；
+        // 分开的对局要求分开之前逐格一致，且分开时正赶往的那份食物仍在同一 tick 吃到。
+        let mut identical = 0;
+        for seed in 0..8 {
+            let mut cached = Game::with_seed(MapConfig::new(10, 10), 3, 3, seed);
+            let mut fresh = Game::with_seed(MapConfig::new(10, 10), 3, 3, seed);
+            let mut planner = Planner::new();
+            let (mut cached_eats, mut fresh_eats) = (Vec::new(), Vec::new());
+            let mut split: Option<(u64, usize)> = None;
+            let (mut a, mut b) = (GameState::Running, GameState::Running);
+            while a == GameState::Running || b == GameState::Running {
+                let tick = cached.moves_count().max(fresh.moves_count());
+                if split.is_none() {
+                    assert!(cached.snake().snake_hashes().eq(fresh.snake().snake_hashes()), "seed={seed} tick={tick}");
+                    assert_eq!(cached.snake().food_hashes(), fresh.snake().food_hashes(), "seed={seed} tick={tick}");
+                }
+                if a == GameState::Running {
+                    let length = cached.snake().length();
+                    a = cached.tick(planner.next_dir(cached.snake()));
+                    if cached.snake().length() > length {
+                        cached_eats.push(tick);
+                    }
+                }
+                if b == GameState::Running {
+                    let length = fresh.snake().length();
+                    b = fresh.tick(next_dir(fresh.snake()));
+                    if fresh.snake().length() > length {
+                        fresh_eats.push(tick);
+                    }
+                }
+                if split.is_none() && cached.snake().snake_hashes().ne(fresh.snake().snake_hashes()) {
+                    split = Some((tick, cached_eats.len()));
+                }
+            }
+            assert_eq!((a, b), (GameState::Won, GameState::Won), "seed={seed}");
+            match split {
+                None => {
+                    assert_eq!(cached_eats, fresh_eats, "seed={seed}");
+                    identical += 1;
+                }
+                Some((tick, eaten)) => {
+                    // 分开时双方到下一份食物的步数相同，这一份也在同一 tick 吃到
+                    let same = eaten + 1;
+                    assert_eq!(cached_eats[..same], fresh_eats[..same], "seed={seed} split at tick {tick}");
+                }
+            }
+        }
+        // 8 个种子中 6 个整局逐格相同，其余 2 个在同长度路线的平局处分开
+        assert_eq!(identical, 6, "整局逐格相同的种子数");
+    }
+
+    #[test]
+    fn test_planner_replans_after_rewind() {
+        use crate::game::Game;
+
+        let mut game = Game::with_seed(MapConfig::new(16, 16), 3, 1, 9);
+        let mut planner = Planner::new();
+        for _ in 0..30 {
+            let dir = planner.next_dir(game.snake());
+            game.tick(dir);
+        }
+        for _ in 0..3 {
+            game.rewind();
+        }
+        // 回退后局面与缓存不一致，必须重新搜索，结果与无缓存的决策相同
+        let replans = planner.replans();
+        assert_eq!(planner.next_dir(game.snake()), next_dir(game.snake()));
+        assert_eq!(planner.replans(), replans + 1);
+    }
+
+    #[test]
+    fn test_tail_distance() {
+        let cfg = MapConfig::new(16, 16);
+        let head = cfg.to_hash(Position { x: 8, y: 8 });
+        assert_eq!(tail_distance(&[head], &[], &cfg), Some(0));
+
+        // 交规允许的两个邻格一个是尾、一个是蛇身：尾巴不能一步踩上，走不到
+        let [a, b] = cfg.allowed(head).map(|d| cfg.neighbor(head, d).unwrap());
+        assert_eq!(tail_distance(&[a, b, head], &[], &cfg), None);
+
+        let body: Vec<usize> = [(1, 3), (2, 3), (3, 3)]
+            .map(|(x, y)| cfg.to_hash(Position { x, y }))
+            .to_vec();
+        assert!(tail_distance(&body, &[], &cfg).is_some_and(|d| d >= 2));
+
+        // 其余格子全是食物：每一步都变长，尾巴永远不动，踏上去只会撞上
+        let foods: Vec<usize> = (0..cfg.total_size()).filter(|h| !body.contains(h)).collect();
+        assert_eq!(tail_distance(&body, &foods, &cfg), None);
+        // 留出一格空地，绕经它之后尾巴才离开
+        let spare = cfg.to_hash(Position { x: 1, y: 2 });
+        let foods: Vec<usize> = foods.into_iter().filter(|&h| h != spare).collect();
+        assert!(tail_distance(&body, &foods, &cfg).is_some());
+    }
+
+    #[test]
+    fn test_safe_tail_keeps_safe_plan() {
+        use crate::game::Game;
+
+        // 开局蛇很短，吃到食物后必然还能追尾：SafeTail 与 Greedy 的计划相同
+        let game = Game::with_seed(MapConfig::new(16, 16), 3, 1, 4);
+        let safe = AiConfig { strategy: Strategy::SafeTail, ..AiConfig::default() };
+        let greedy = plan(game.snake(), &AiConfig::default()).unwrap();
+        let checked = plan(game.snake(), &safe).unwrap();
+        assert!(checked.found() && !checked.chasing_tail);
+        assert_eq!(checked.directions, greedy.directions);
+    }
+
+    #[test]
+    fn test_safe_tail_survives_where_greedy_dies() {
+        use crate::game::Game;
+        use crate::types::GameState;
+
+        // 食物很多的 16×16 开局：Greedy 吃下一份食物后被自己与周围的食物围住，
+        // 尾巴就在附近却无路可走；SafeTail 提前改为追尾，最终填满地图
+        let safe = AiConfig { strategy: Strategy::SafeTail, ..AiConfig::default() };
+        for (food, seed, trapped_at) in [(20, 6, 236), (12, 28, 228)] {
+            let run = |ai: &AiConfig| {
+                let mut game = Game::with_seed(MapConfig::new(16, 16), 3, food, seed);
+                let mut chased = false;
+                loop {
+                    let plan = plan(game.snake(), ai);
+                    chased |= plan.as_ref().is_some_and(|p| p.chasing_tail);
+                    let length = game.snake().length();
+                    let state = game.tick(plan.and_then(|p| p.direction()));
+                    if state != GameState::Running {
+                        return (game, state, length, chased);
+                    }
+                }
+            };
+
+            let (game, state, length, _) = run(&AiConfig::default());
+            assert_eq!((state, length), (GameState::Over, trapped_at), "seed={seed}");
+            // 撞上之前的一 tick 刚吃到食物，且蛇头已没有可走的格子
+            let snake = game.snake();
+            assert_eq!(snake.length(), trapped_at, "seed={seed}");
+            let body: Vec<usize> = snake.snake_hashes().copied().collect();
+            let head = *body.last().unwrap();
+            let mask = BodyMask::from_body(&body);
+            let cfg = snake.config();
+            let stuck = cfg.allowed(head).iter().all(|&d| cfg.neighbor(head, d).is_none_or(|n| mask.contains(n)));
+            assert!(stuck, "seed={seed}");
+
+            let (_, state, _, chased) = run(&safe);
+            assert_eq!(state, GameState::Won, "seed={seed}");
+            assert!(chased, "seed={seed}");
+        }
+    }
+
+    #[test]
+    fn test_keeps_empty_connected_on_small_board() {
+        // 小于 16×16 的地图：位图中超出总格数的位不能算作空格
+        let cfg = MapConfig::new(8, 8);
+        let body: Vec<usize> = [(2, 4), (3, 4), (4, 4)].map(|(x, y)| cfg.to_hash(Position { x, y })).to_vec();
+        let new_head = cfg.to_hash(Position { x: 5, y: 4 });
+        assert!(keeps_empty_connected(new_head, &BodyMask::from_body(&body), body[0], &cfg));
+
+        // 竖直一整列的蛇身把地图切成两半
+        let tail = cfg.to_hash(Position { x: 0, y: 0 });
+        let mut wall: Vec<usize> = (0..8).map(|y| cfg.to_hash(Position { x: 3, y })).collect();
+        wall.push(tail);
+        let new_head = cfg.to_hash(Position { x: 4, y: 7 });
+        assert!(!keeps_empty_connected(new_head, &BodyMask::from_body(&wall), tail, &cfg));
+    }
 }
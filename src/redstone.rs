@@ -0,0 +1,12 @@
+//! 红石计算机移植工具
+//!
+//! 本项目的最终目标是把引擎移植到 Minecraft 中的 8 位红石计算机上。
+//! 这里提供一个小型 8 位指令集（[`isa`]）及其解释器、引擎内存布局（[`layout`]），
+//! 以及把 [`SnakeGame::update`](crate::snake::SnakeGame::update) 翻译成该指令集汇编的代码生成器（[`codegen`]）。
+//! 生成的程序与 Rust 引擎逐 tick 对拍，保证移植前算法与数据结构已被验证。
+//! [`export`] 再把内存映像导出为 `.mcfunction` / schematic，用于初始化游戏世界。
+
+pub mod codegen;
+pub mod export;
+pub mod isa;
+pub mod layout;
@@ -0,0 +1,417 @@
+//! 把 `SnakeGame::update` 翻译为 RS8 汇编
+//!
+//! 生成的程序执行恰好一个 tick：从 `PORT_DIR` 读入方向（`NONE` 表示无输入），
+//! 按 [`layout`] 中的布局更新内存，向 `PORT_STATE` 输出新状态后 HLT。
+//! 所有表操作与引擎一一对应（同样的 swap-remove 顺序、同样的随机抽样下标），
+//! 因此在相同随机数序列下，两者每个 tick 后的数据结构逐字节一致。
+//!
+//! 程序没有循环，每个 tick 的指令数有常数上界——这正是引擎 O(1) 设计的目的。
+
+use crate::config::MapConfig;
+use crate::redstone::layout::{self, *};
+use crate::types::{CellState, GameState};
+use std::fmt::Write;
+
+/// 汇编文本构建器
+struct Emitter {
+    out: String,
+}
+
+impl Emitter {
+    fn label(&mut self, name: &str) {
+        let _ = writeln!(self.out, "{name}:");
+    }
+
+    fn op(&mut self, text: &str) {
+        let _ = writeln!(self.out, "    {text}");
+    }
+
+    fn comment(&mut self, text: &str) {
+        let _ = writeln!(self.out, "    ; {text}");
+    }
+
+    /// 读取变量：`rd ← VARS[var]`（借用 R7 作地址寄存器）
+    fn load_var(&mut self, rd: &str, var: &str) {
+        self.op(&format!("LDI R7, {var}"));
+        self.op(&format!("LD {rd}, [VARS + R7]"));
+    }
+
+    /// 写回变量：`VARS[var] ← rs`（借用 R7 作地址寄存器）
+    fn store_var(&mut self, var: &str, rs: &str) {
+        self.op(&format!("LDI R7, {var}"));
+        self.op(&format!("ST [VARS + R7], {rs}"));
+    }
+
+    /// 从 `list` / `index` 表中 swap-remove 寄存器 `rh` 中的 hash（对应引擎的
+    /// `remove_from_empty` / `consume_food`）。占用 R4–R7，`tag` 用于生成唯一标签。
+    fn swap_remove(&mut self, list: &str, index: &str, len_var: &str, rh: &str, tag: &str) {
+        self.comment(&format!("swap-remove {rh} from {list}"));
+        self.op(&format!("LD R4, [{index} + {rh}]"));
+        self.op("CMPI R4, NONE");
+        self.op(&format!("JZ {tag}_done"));
+        self.load_var("R5", len_var);
+        self.op("SUBI R5, 1");
+        self.op("ST [VARS + R7], R5");
+        self.op(&format!("LD R6, [{list} + R5]"));
+        self.op(&format!("ST [{list} + R4], R6"));
+        self.op(&format!("ST [{index} + R6], R4"));
+        self.op("LDI R6, NONE");
+        self.op(&format!("ST [{index} + {rh}], R6"));
+        self.label(&format!("{tag}_done"));
+    }
+
+    /// 追加寄存器 `rh` 到 `list` 末尾并登记反向索引。占用 R6、R7。
+    fn push(&mut self, list: &str, index: &str, len_var: &str, rh: &str) {
+        self.load_var("R6", len_var);
+        self.op(&format!("ST [{index} + {rh}], R6"));
+        self.op(&format!("ST [{list} + R6], {rh}"));
+        self.op("ADDI R6, 1");
+        self.op("ST [VARS + R7], R6");
+    }
+
+    /// 设置格子状态 `MAP[rh] ← state`。占用 R6。
+    fn set_cell(&mut self, rh: &str, state: &str) {
+        self.op(&format!("LDI R6, {state}"));
+        self.op(&format!("ST [MAP + {rh}], R6"));
+    }
+
+    /// 蛇头前进到寄存器 `rh`（对应 `advance_head`）
+    fn advance_head(&mut self, rh: &str, tag: &str) {
+        self.swap_remove("EMPTY", "EMPTY_IDX", "VAR_EMPTY_LEN", rh, tag);
+        self.set_cell(rh, "SNAKE");
+        self.load_var("R6", "VAR_HEAD");
+        self.op("ADDI R6, 1");
+        self.op("ST [VARS + R7], R6");
+        self.op(&format!("ST [BODY + R6], {rh}"));
+        self.load_var("R6", "VAR_LEN");
+        self.op("ADDI R6, 1");
+        self.op("ST [VARS + R7], R6");
+    }
+
+    /// 输出状态并停机
+    fn finish(&mut self, state: &str) {
+        self.op(&format!("LDI R0, {state}"));
+        self.op("OUT PORT_STATE, R0");
+        self.op("HLT");
+    }
+}
+
+/// 生成单 tick 的 RS8 汇编清单
+///
+/// 寄存器约定：R0 方向，R1 蛇头 hash，R3 新蛇头 hash，R4–R6 临时，R7 变量地址。
+///
+/// # Panics
+/// 地图不满足 [`layout::supports`]
+pub fn generate(config: &MapConfig) -> String {
+    assert!(
+        layout::supports(config),
+        "RS8 布局要求总格数 ≤ 256 且宽度为 2 的幂（当前 {}×{}）",
+        config.width,
+        config.height,
+    );
+    let w = config.width as usize;
+    let total = config.total_size();
+
+    let mut e = Emitter { out: String::new() };
+    let _ = writeln!(e.out, "; RS8 snake tick — {}×{} map", config.width, config.height);
+    let _ = writeln!(e.out, "; generated from SnakeGame::update, see redstone::layout");
+    for (name, value) in [
+        ("MAP", MAP),
+        ("BODY", BODY),
+        ("EMPTY", EMPTY),
+        ("EMPTY_IDX", EMPTY_IDX),
+        ("FOOD", FOOD),
+        ("FOOD_IDX", FOOD_IDX),
+        ("VARS", VARS),
+        ("VAR_DIR", VAR_DIR),
+        ("VAR_TAIL", VAR_TAIL),
+        ("VAR_HEAD", VAR_HEAD),
+        ("VAR_LEN", VAR_LEN),
+        ("VAR_EMPTY_LEN", VAR_EMPTY_LEN),
+        ("VAR_FOOD_LEN", VAR_FOOD_LEN),
+        ("NONE", NONE),
+        ("PORT_DIR", PORT_DIR),
+        ("PORT_STATE", PORT_STATE),
+        ("EMPTY_CELL", CellState::Empty as u8),
+        ("FOOD_CELL", CellState::Food as u8),
+        ("SNAKE", CellState::Snake as u8),
+        ("READY", encode_state(GameState::Ready)),
+        ("RUNNING", encode_state(GameState::Running)),
+        ("OVER", encode_state(GameState::Over)),
+        ("WON", encode_state(GameState::Won)),
+    ] {
+        let _ = writeln!(e.out, ".equ {name} {value}");
+    }
+    let _ = writeln!(e.out, ".equ WIDTH_MASK {}", w - 1);
+    e.out.push('\n');
+
+    // ------------------------------------------------------------------
+    // 方向输入：更新方向，防止 180 度掉头
+    // ------------------------------------------------------------------
+    e.comment("direction input");
+    e.op("IN R0, PORT_DIR");
+    e.op("CMPI R0, NONE");
+    e.op("JZ keep_dir");
+    e.load_var("R1", "VAR_DIR");
+    e.op("CMPI R1, NONE");
+    e.op("JZ set_dir");
+    e.op("XORI R1, 1");
+    e.op("CMP R0, R1");
+    e.op("JZ keep_dir");
+    e.label("set_dir");
+    e.store_var("VAR_DIR", "R0");
+    e.label("keep_dir");
+    e.load_var("R0", "VAR_DIR");
+    e.op("CMPI R0, NONE");
+    e.op("JNZ have_dir");
+    e.finish("READY");
+    e.label("have_dir");
+
+    // ------------------------------------------------------------------
+    // 新蛇头位置与边界检查（宽度为 2 的幂：列号 = hash & (w-1)）
+    // ------------------------------------------------------------------
+    e.comment("new head position");
+    e.load_var("R2", "VAR_HEAD");
+    e.op("LD R1, [BODY + R2]");
+    e.op("CMPI R0, 0");
+    e.op("JZ go_up");
+    e.op("CMPI R0, 1");
+    e.op("JZ go_down");
+    e.op("CMPI R0, 2");
+    e.op("JZ go_left");
+
+    e.op("MOV R4, R1");
+    e.op("ANDI R4, WIDTH_MASK");
+    e.op("CMPI R4, WIDTH_MASK");
+    e.op("JZ over");
+    e.op("MOV R3, R1");
+    e.op("ADDI R3, 1");
+    e.op("JMP moved");
+
+    e.label("go_left");
+    e.op("MOV R4, R1");
+    e.op("ANDI R4, WIDTH_MASK");
+    e.op("JZ over");
+    e.op("MOV R3, R1");
+    e.op("SUBI R3, 1");
+    e.op("JMP moved");
+
+    // 单行地图（w == total）时上下移动必然出界，且 w 可能为 256 不能作立即数
+    e.label("go_up");
+    if w == total {
+        e.op("JMP over");
+    } else {
+        e.op(&format!("CMPI R1, {w}"));
+        e.op("JC over");
+        e.op("MOV R3, R1");
+        e.op(&format!("SUBI R3, {w}"));
+        e.op("JMP moved");
+    }
+
+    e.label("go_down");
+    if w == total {
+        e.op("JMP over");
+    } else {
+        e.op(&format!("CMPI R1, {}", total - w));
+        e.op("JNC over");
+        e.op("MOV R3, R1");
+        e.op(&format!("ADDI R3, {w}"));
+    }
+
+    // ------------------------------------------------------------------
+    // 碰撞检测与处理
+    // ------------------------------------------------------------------
+    e.label("moved");
+    e.op("LD R4, [MAP + R3]");
+    e.op("CMPI R4, EMPTY_CELL");
+    e.op("JZ move_empty");
+    e.op("CMPI R4, FOOD_CELL");
+    e.op("JZ eat");
+    e.label("over");
+    e.finish("OVER");
+
+    // 普通移动：蛇尾前进一步，再蛇头前进一步
+    e.label("move_empty");
+    e.comment("advance tail");
+    e.load_var("R2", "VAR_TAIL");
+    e.op("LD R5, [BODY + R2]");
+    e.op("ADDI R2, 1");
+    e.op("ST [VARS + R7], R2");
+    e.load_var("R6", "VAR_LEN");
+    e.op("SUBI R6, 1");
+    e.op("ST [VARS + R7], R6");
+    e.set_cell("R5", "EMPTY_CELL");
+    e.push("EMPTY", "EMPTY_IDX", "VAR_EMPTY_LEN", "R5");
+    e.comment("advance head");
+    e.advance_head("R3", "move_head");
+    e.finish("RUNNING");
+
+    // 吃食物：即将填满地图时直接胜利，否则补充一份食物
+    e.label("eat");
+    e.load_var("R4", "VAR_LEN");
+    e.op(&format!("CMPI R4, {}", total - 1));
+    e.op("JNC eat_last");
+    e.swap_remove("FOOD", "FOOD_IDX", "VAR_FOOD_LEN", "R3", "eat_food");
+    e.advance_head("R3", "eat_head");
+    e.comment("spawn food");
+    e.load_var("R4", "VAR_EMPTY_LEN");
+    e.op("CMPI R4, 0");
+    e.op("JZ spawned");
+    e.op("RND R5, R4");
+    e.op("LD R3, [EMPTY + R5]");
+    e.swap_remove("EMPTY", "EMPTY_IDX", "VAR_EMPTY_LEN", "R3", "spawn");
+    e.set_cell("R3", "FOOD_CELL");
+    e.push("FOOD", "FOOD_IDX", "VAR_FOOD_LEN", "R3");
+    e.label("spawned");
+    e.finish("RUNNING");
+
+    e.label("eat_last");
+    e.swap_remove("FOOD", "FOOD_IDX", "VAR_FOOD_LEN", "R3", "last_food");
+    e.advance_head("R3", "last_head");
+    e.finish("WON");
+
+    e.out
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::redstone::isa::{self, Instr, Io, Machine, Memory};
+    use crate::snake::SnakeGame;
+    use crate::types::Direction;
+    use rand::rngs::SmallRng;
+    use rand::{Rng, SeedableRng};
+
+    /// 一个 tick 允许的最大周期数（程序无循环，远低于此值）
+    const MAX_CYCLES: u64 = 1_000;
+
+    /// 把方向输入喂给程序，并用与引擎同种子的 RNG 回答 RND
+    struct TickIo {
+        dir: u8,
+        state: Option<u8>,
+        rng: SmallRng,
+    }
+
+    impl Io for TickIo {
+        fn input(&mut self, _port: u8) -> u8 {
+            self.dir
+        }
+        fn output(&mut self, _port: u8, value: u8) {
+            self.state = Some(value);
+        }
+        fn random(&mut self, bound: u8) -> u8 {
+            // 与引擎完全相同的调用：random_range(0..len as usize)
+            self.rng.random_range(0..bound as usize) as u8
+        }
+    }
+
+    /// 比较内存中的活跃数据与引擎状态
+    fn assert_same(mem: &Memory, game: &SnakeGame, tick: usize) {
+        let expected = layout::load_game(game);
+        let page = |p: u8| &mem[p as usize];
+        let vars = page(VARS);
+        assert_eq!(page(MAP), &expected[MAP as usize], "tick {tick}: MAP");
+        assert_eq!(page(EMPTY_IDX), &expected[EMPTY_IDX as usize], "tick {tick}: EMPTY_IDX");
+        assert_eq!(page(FOOD_IDX), &expected[FOOD_IDX as usize], "tick {tick}: FOOD_IDX");
+        assert_eq!(vars[VAR_DIR as usize], encode_direction(game.direction()));
+        assert_eq!(vars[VAR_LEN as usize], game.length() as u8, "tick {tick}: LEN");
+
+        let empty_len = vars[VAR_EMPTY_LEN as usize] as usize;
+        assert_eq!(&page(EMPTY)[..empty_len], &expected[EMPTY as usize][..empty_len]);
+        assert_eq!(empty_len, game.empty_cells().len(), "tick {tick}: EMPTY_LEN");
+        let food_len = vars[VAR_FOOD_LEN as usize] as usize;
+        assert_eq!(&page(FOOD)[..food_len], &expected[FOOD as usize][..food_len]);
+        assert_eq!(food_len, game.food_count(), "tick {tick}: FOOD_LEN");
+
+        let tail = vars[VAR_TAIL as usize];
+        let body: Vec<u8> = (0..game.length())
+            .map(|i| page(BODY)[tail.wrapping_add(i as u8) as usize])
+            .collect();
+        let expected_body: Vec<u8> = game.snake_hashes().map(|&h| h as u8).collect();
+        assert_eq!(body, expected_body, "tick {tick}: BODY");
+        assert_eq!(
+            vars[VAR_HEAD as usize],
+            tail.wrapping_add((game.length() - 1) as u8),
+            "tick {tick}: HEAD"
+        );
+    }
+
+    /// 引擎与 RS8 程序对拍 `ticks` 步（方向随机，含无输入）
+    fn lockstep(width: u32, height: u32, length: usize, food: usize, seed: u64, ticks: usize) {
+        let config = MapConfig::new(width, height);
+        let program: Vec<Instr> = isa::assemble(&generate(&config)).unwrap();
+
+        let mut engine_rng = SmallRng::seed_from_u64(seed);
+        let mut game = SnakeGame::new(config, length, food, &mut engine_rng);
+        let mut machine = Machine::new(layout::load_game(&game));
+        let mut io = TickIo {
+            dir: NONE,
+            state: None,
+            rng: engine_rng.clone(),
+        };
+        let mut input_rng = SmallRng::seed_from_u64(seed ^ 0xD1CE);
+
+        for tick in 0..ticks {
+            let dir = match input_rng.random_range(0..6) {
+                0 => Some(Direction::Up),
+                1 => Some(Direction::Down),
+                2 => Some(Direction::Left),
+                3 => Some(Direction::Right),
+                _ => None,
+            };
+            let expected = game.update(dir, &mut engine_rng);
+
+            io.dir = encode_direction(dir);
+            io.state = None;
+            machine.run(&program, &mut io, MAX_CYCLES).unwrap();
+            let actual = io.state.and_then(decode_state);
+            assert_eq!(actual, Some(expected), "seed {seed} tick {tick}: 状态不一致");
+            assert_same(&machine.mem, &game, tick);
+
+            if expected != GameState::Running {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_program_fits_rom() {
+        let program = isa::assemble(&generate(&MapConfig::new(16, 16))).unwrap();
+        assert!(program.len() <= isa::ROM_SIZE, "程序 {} 条指令", program.len());
+        assert!(!program.iter().any(|i| matches!(i, Instr::Jmp(a) if *a as usize >= program.len())));
+    }
+
+    #[test]
+    fn test_lockstep_16x16() {
+        for seed in 0..40 {
+            lockstep(16, 16, 3, 1 + seed as usize % 8, seed, 300);
+        }
+    }
+
+    #[test]
+    fn test_lockstep_dense_food_until_won() {
+        // 4×4 满地食物：很快吃满全图，覆盖 Won 分支与空格耗尽
+        for seed in 0..20 {
+            lockstep(4, 4, 2, 14, seed, 100);
+        }
+    }
+
+    #[test]
+    fn test_lockstep_odd_shapes() {
+        for seed in 0..10 {
+            lockstep(1, 16, 1, 3, seed, 100);
+            lockstep(256, 1, 3, 20, seed, 100);
+            lockstep(8, 32, 4, 5, seed, 200);
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "宽度为 2 的幂")]
+    fn test_rejects_non_power_of_two_width() {
+        generate(&MapConfig::new(10, 10));
+    }
+}
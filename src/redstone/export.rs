@@ -0,0 +1,324 @@
+//! 把 RS8 内存映像导出为 Minecraft 世界数据
+//!
+//! 字节映像按页平铺（见 [`layout`]），每个比特对应世界中的一个方块：
+//! 比特位、字节、页各沿 [`BlockLayout`] 中可配置的步长排布。
+//! 支持两种输出：
+//!
+//! - `.mcfunction`：逐比特 `setblock` 命令，可在运行中的世界里直接执行
+//! - Sponge Schematic v2（`.schem`）：gzip 压缩的 NBT，可由 WorldEdit 等工具粘贴
+//!
+//! 同一套函数也可导出任意只读表（ROM）的字节，只要按 [`PAGE_SIZE`] 分页。
+
+use crate::codec;
+use crate::redstone::isa::PAGE_SIZE;
+use crate::redstone::layout;
+use crate::snake::SnakeGame;
+use std::fmt::Write;
+
+/// 世界坐标 (x, y, z)
+pub type BlockPos = [i32; 3];
+
+/// 存储器在世界中的方块排布
+#[derive(Debug, Clone)]
+pub struct BlockLayout {
+    /// 第 0 页第 0 字节第 0 位所在方块
+    pub origin: BlockPos,
+    /// 相邻比特（bit 0 → bit 7）之间的位移
+    pub bit_step: BlockPos,
+    /// 相邻字节之间的位移
+    pub byte_step: BlockPos,
+    /// 相邻页之间的位移
+    pub page_step: BlockPos,
+    /// 比特为 1 时放置的方块
+    pub one: String,
+    /// 比特为 0 时放置的方块；None 表示不输出（假定区域已清空）
+    pub zero: Option<String>,
+}
+
+impl Default for BlockLayout {
+    /// 比特沿 +y、字节沿 +x、页沿 +z，间隔一格以便铺设红石线
+    fn default() -> Self {
+        Self {
+            origin: [0, 0, 0],
+            bit_step: [0, 2, 0],
+            byte_step: [2, 0, 0],
+            page_step: [0, 0, 4],
+            one: "minecraft:redstone_block".to_string(),
+            zero: Some("minecraft:air".to_string()),
+        }
+    }
+}
+
+impl BlockLayout {
+    /// 第 `index` 字节（按页平铺）第 `bit` 位所在方块
+    pub fn position(&self, index: usize, bit: u32) -> BlockPos {
+        let page = (index / PAGE_SIZE) as i32;
+        let byte = (index % PAGE_SIZE) as i32;
+        let bit = bit as i32;
+        std::array::from_fn(|axis| {
+            self.origin[axis]
+                + self.page_step[axis] * page
+                + self.byte_step[axis] * byte
+                + self.bit_step[axis] * bit
+        })
+    }
+
+    /// 遍历映像中每个比特：(方块位置, 比特值)
+    fn bits<'a>(&'a self, image: &'a [u8]) -> impl Iterator<Item = (BlockPos, bool)> + 'a {
+        image.iter().enumerate().flat_map(move |(index, &byte)| {
+            (0..8).map(move |bit| (self.position(index, bit), byte >> bit & 1 == 1))
+        })
+    }
+}
+
+/// 引擎状态的字节级内存映像（各页依次拼接）
+///
+/// # Panics
+/// 地图不满足 [`layout::supports`]
+pub fn memory_image(game: &SnakeGame) -> Vec<u8> {
+    layout::load_game(game).concat()
+}
+
+/// 生成逐比特 `setblock` 命令
+pub fn to_mcfunction(image: &[u8], layout: &BlockLayout) -> String {
+    let mut out = String::with_capacity(image.len() * 8 * 48);
+    let _ = writeln!(out, "# snake memory image: {} bytes", image.len());
+    for ([x, y, z], bit) in layout.bits(image) {
+        let block = if bit { Some(&layout.one) } else { layout.zero.as_ref() };
+        if let Some(block) = block {
+            let _ = writeln!(out, "setblock {x} {y} {z} {block}");
+        }
+    }
+    out
+}
+
+/// 生成 Sponge Schematic v2 文件内容（gzip 压缩的 NBT）
+///
+/// 区域为所有比特方块的包围盒，`Offset` 记录其最小角相对 `origin` 的世界坐标；
+/// 未被比特占据的方块为空气。`zero` 为 None 时 0 比特也写作空气。
+///
+/// # Panics
+/// 包围盒任一维超过 65535 格
+pub fn to_schematic(image: &[u8], layout: &BlockLayout) -> Vec<u8> {
+    let mut min = [i32::MAX; 3];
+    let mut max = [i32::MIN; 3];
+    for (pos, _) in layout.bits(image) {
+        for axis in 0..3 {
+            min[axis] = min[axis].min(pos[axis]);
+            max[axis] = max[axis].max(pos[axis]);
+        }
+    }
+    if image.is_empty() {
+        min = layout.origin;
+        max = layout.origin;
+    }
+    let size: [usize; 3] = std::array::from_fn(|axis| (max[axis] - min[axis] + 1) as usize);
+    assert!(
+        size.iter().all(|&s| s <= u16::MAX as usize),
+        "schematic 尺寸 {size:?} 超过 65535"
+    );
+    let [width, height, length] = size;
+
+    // 调色板：0 空气，比特 1、比特 0 的方块依次追加；同名方块共用一个 id，
+    // 否则 Palette compound 中会出现重复的键
+    let (one, zero) = (layout.one.as_str(), layout.zero.as_deref().unwrap_or("minecraft:air"));
+    let mut palette = vec!["minecraft:air"];
+    for name in [one, zero] {
+        if !palette.contains(&name) {
+            palette.push(name);
+        }
+    }
+    let id_of = |name| palette.iter().position(|&p| p == name).unwrap() as u8;
+    let (one_id, zero_id) = (id_of(one), id_of(zero));
+    let mut blocks = vec![0u8; width * height * length];
+    for (pos, bit) in layout.bits(image) {
+        let [x, y, z]: [usize; 3] = std::array::from_fn(|axis| (pos[axis] - min[axis]) as usize);
+        blocks[x + z * width + y * width * length] = if bit { one_id } else { zero_id };
+    }
+
+    // 调色板 id < 128，varint 即单字节
+    let mut nbt = Nbt::default();
+    nbt.begin_compound("Schematic");
+    nbt.int("Version", 2);
+    nbt.int("DataVersion", SCHEMATIC_DATA_VERSION);
+    nbt.short("Width", width as u16);
+    nbt.short("Height", height as u16);
+    nbt.short("Length", length as u16);
+    nbt.int_array("Offset", &min);
+    nbt.int("PaletteMax", palette.len() as i32);
+    nbt.begin_compound("Palette");
+    for (id, name) in palette.iter().enumerate() {
+        nbt.int(name, id as i32);
+    }
+    nbt.end_compound();
+    nbt.byte_array("BlockData", &blocks);
+    nbt.end_compound();
+    codec::gzip(&nbt.0)
+}
+
+/// 写入 schematic 的数据版本（Minecraft 1.16.5）
+const SCHEMATIC_DATA_VERSION: i32 = 2586;
+
+/// 最小 NBT 写入器（大端序，仅覆盖 schematic 用到的标签类型）
+#[derive(Default)]
+struct Nbt(Vec<u8>);
+
+impl Nbt {
+    fn header(&mut self, tag: u8, name: &str) {
+        self.0.push(tag);
+        self.0.extend_from_slice(&(name.len() as u16).to_be_bytes());
+        self.0.extend_from_slice(name.as_bytes());
+    }
+
+    fn begin_compound(&mut self, name: &str) {
+        self.header(10, name);
+    }
+
+    fn end_compound(&mut self) {
+        self.0.push(0);
+    }
+
+    fn short(&mut self, name: &str, value: u16) {
+        self.header(2, name);
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn int(&mut self, name: &str, value: i32) {
+        self.header(3, name);
+        self.0.extend_from_slice(&value.to_be_bytes());
+    }
+
+    fn byte_array(&mut self, name: &str, bytes: &[u8]) {
+        self.header(7, name);
+        self.0.extend_from_slice(&(bytes.len() as i32).to_be_bytes());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn int_array(&mut self, name: &str, values: &[i32]) {
+        self.header(11, name);
+        self.0.extend_from_slice(&(values.len() as i32).to_be_bytes());
+        for v in values {
+            self.0.extend_from_slice(&v.to_be_bytes());
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MapConfig;
+    use crate::redstone::isa::PAGE_COUNT;
+    use crate::redstone::layout::{MAP, VARS, VAR_LEN};
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    fn sample_game() -> SnakeGame {
+        let mut rng = SmallRng::seed_from_u64(42);
+        SnakeGame::new(MapConfig::new(16, 16), 3, 2, &mut rng)
+    }
+
+    #[test]
+    fn test_memory_image_matches_layout() {
+        let game = sample_game();
+        let image = memory_image(&game);
+        assert_eq!(image.len(), PAGE_SIZE * PAGE_COUNT);
+        let head = game.config().to_hash(game.head_position().unwrap());
+        assert_eq!(image[MAP as usize * PAGE_SIZE + head], 2);
+        assert_eq!(image[VARS as usize * PAGE_SIZE + VAR_LEN as usize], 3);
+    }
+
+    #[test]
+    fn test_position_steps() {
+        let layout = BlockLayout {
+            origin: [10, 64, -5],
+            ..BlockLayout::default()
+        };
+        assert_eq!(layout.position(0, 0), [10, 64, -5]);
+        assert_eq!(layout.position(3, 7), [16, 78, -5]);
+        assert_eq!(layout.position(PAGE_SIZE + 1, 0), [12, 64, -1]);
+    }
+
+    #[test]
+    fn test_mcfunction_commands() {
+        let layout = BlockLayout::default();
+        let out = to_mcfunction(&[0b0000_0101], &layout);
+        let commands: Vec<&str> = out.lines().filter(|l| !l.starts_with('#')).collect();
+        assert_eq!(commands.len(), 8);
+        assert_eq!(commands[0], "setblock 0 0 0 minecraft:redstone_block");
+        assert_eq!(commands[1], "setblock 0 2 0 minecraft:air");
+        assert_eq!(commands[2], "setblock 0 4 0 minecraft:redstone_block");
+
+        let sparse = BlockLayout { zero: None, ..layout };
+        let out = to_mcfunction(&[0b0000_0101, 0], &sparse);
+        assert_eq!(out.lines().filter(|l| l.starts_with("setblock")).count(), 2);
+    }
+
+    #[test]
+    fn test_schematic_structure() {
+        let layout = BlockLayout {
+            origin: [100, 5, 7],
+            bit_step: [0, 1, 0],
+            byte_step: [1, 0, 0],
+            page_step: [0, 0, 1],
+            ..BlockLayout::default()
+        };
+        let gz = to_schematic(&[0x81, 0x00], &layout);
+        let nbt = crate::codec::tests::inflate_stored(&gz[10..gz.len() - 8]);
+        assert_eq!(&nbt[..13], b"\x0a\x00\x09Schematic\x03");
+
+        // 2 字节 × 8 位 → 宽 2、高 8、长 1；BlockData 按 y-z-x 顺序
+        let data_tag = b"BlockData";
+        let at = nbt.windows(data_tag.len()).position(|w| w == data_tag).unwrap();
+        let len_at = at + data_tag.len();
+        let len = i32::from_be_bytes(nbt[len_at..len_at + 4].try_into().unwrap());
+        assert_eq!(len, 16);
+        let blocks = &nbt[len_at + 4..len_at + 4 + 16];
+        assert_eq!(blocks[0], 1); // 字节 0 位 0
+        assert_eq!(blocks[1], 0); // 字节 1 位 0（0 比特为空气）
+        assert_eq!(blocks[7 * 2], 1); // 字节 0 位 7
+        assert_eq!(*nbt.last().unwrap(), 0, "根 compound 应以 TAG_End 结尾");
+
+        let offset_tag = b"Offset";
+        let at = nbt.windows(offset_tag.len()).position(|w| w == offset_tag).unwrap() + offset_tag.len();
+        let x = i32::from_be_bytes(nbt[at + 4..at + 8].try_into().unwrap());
+        assert_eq!(x, 100);
+    }
+
+    #[test]
+    fn test_schematic_palette_has_no_duplicates() {
+        let find = |nbt: &[u8], tag: &[u8]| nbt.windows(tag.len()).position(|w| w == tag).unwrap() + tag.len();
+        let count = |nbt: &[u8], tag: &[u8]| nbt.windows(tag.len()).filter(|w| w == &tag).count();
+        let schematic = |one: &str, zero: Option<&str>| {
+            let layout = BlockLayout {
+                one: one.to_string(),
+                zero: zero.map(str::to_string),
+                ..BlockLayout::default()
+            };
+            let gz = to_schematic(&[0x0F], &layout);
+            crate::codec::tests::inflate_stored(&gz[10..gz.len() - 8])
+        };
+        // (PaletteMax, 比特 1 的 id, 比特 0 的 id)
+        let palette = |nbt: &[u8]| {
+            let at = find(nbt, b"PaletteMax");
+            let max = i32::from_be_bytes(nbt[at..at + 4].try_into().unwrap());
+            let at = find(nbt, b"BlockData") + 4;
+            // 默认布局下字节 0 位 0 在 y=0，位 7 在 y=14；宽、长均为 1
+            (max, nbt[at], nbt[at + 14])
+        };
+
+        let nbt = schematic("minecraft:stone", Some("minecraft:stone"));
+        assert_eq!(count(&nbt, b"minecraft:stone"), 1);
+        assert_eq!(palette(&nbt), (2, 1, 1));
+
+        let nbt = schematic("minecraft:air", Some("minecraft:stone"));
+        assert_eq!(count(&nbt, b"minecraft:air"), 1);
+        assert_eq!(palette(&nbt), (2, 0, 1));
+
+        let nbt = schematic("minecraft:redstone_block", Some("minecraft:glass"));
+        assert_eq!(palette(&nbt), (3, 1, 2));
+    }
+}
@@ -0,0 +1,517 @@
+//! RS8：面向红石计算机的极简 8 位指令集
+//!
+//! # 机器模型
+//!
+//! - 8 个 8 位通用寄存器 `R0`–`R7`，两个标志位：`Z`（结果为零）和 `C`（进位/借位）
+//! - 哈佛结构：程序 ROM 最多 [`ROM_SIZE`] 条指令，跳转地址为 8 位
+//! - 数据 RAM 分为 [`PAGE_COUNT`] 页，每页 [`PAGE_SIZE`] 字节；
+//!   访存地址 = 页号（指令立即数）+ 页内偏移（寄存器）
+//! - 端口 I/O：`IN` / `OUT` 读写 8 位端口；`RND` 由宿主提供随机数
+//!
+//! # 指令
+//!
+//! | 指令              | 语义                           | 标志        |
+//! |-------------------|--------------------------------|-------------|
+//! | `LDI rd, imm`     | rd ← imm                       | —           |
+//! | `MOV rd, rs`      | rd ← rs                        | —           |
+//! | `ADD rd, rs`      | rd ← rd + rs                   | Z, C=进位   |
+//! | `ADDI rd, imm`    | rd ← rd + imm                  | Z, C=进位   |
+//! | `SUB rd, rs`      | rd ← rd − rs                   | Z, C=借位   |
+//! | `SUBI rd, imm`    | rd ← rd − imm                  | Z, C=借位   |
+//! | `ANDI rd, imm`    | rd ← rd & imm                  | Z, C=0      |
+//! | `XORI rd, imm`    | rd ← rd ^ imm                  | Z, C=0      |
+//! | `CMP rd, rs`      | 仅按 rd − rs 设置标志          | Z, C=借位   |
+//! | `CMPI rd, imm`    | 仅按 rd − imm 设置标志         | Z, C=借位   |
+//! | `LD rd, [P + ra]` | rd ← RAM\[P\]\[ra\]            | —           |
+//! | `ST [P + ra], rs` | RAM\[P\]\[ra\] ← rs            | —           |
+//! | `IN rd, port`     | rd ← 端口输入                  | —           |
+//! | `OUT port, rs`    | 端口输出 rs                    | —           |
+//! | `RND rd, rs`      | rd ← \[0, rs) 内的随机数       | —           |
+//! | `JMP a` / `JZ a` / `JNZ a` / `JC a` / `JNC a` | （条件）跳转 | — |
+//! | `HLT`             | 停机，本 tick 结束             | —           |
+//!
+//! # 汇编语法
+//!
+//! 每行一条指令；`;` 之后为注释；`name:` 定义标签；
+//! `.equ NAME value` 定义符号常量。立即数可写十进制、`0x` 十六进制或符号名。
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// 通用寄存器数量
+pub const REG_COUNT: usize = 8;
+/// 每页 RAM 字节数
+pub const PAGE_SIZE: usize = 256;
+/// RAM 页数
+pub const PAGE_COUNT: usize = 8;
+/// 程序 ROM 容量（指令条数）
+pub const ROM_SIZE: usize = 256;
+
+/// 数据 RAM：`PAGE_COUNT` 页 × `PAGE_SIZE` 字节
+pub type Memory = [[u8; PAGE_SIZE]; PAGE_COUNT];
+
+/// 一条 RS8 指令（寄存器以编号 0–7 表示）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Instr {
+    Ldi(u8, u8),
+    Mov(u8, u8),
+    Add(u8, u8),
+    Addi(u8, u8),
+    Sub(u8, u8),
+    Subi(u8, u8),
+    Andi(u8, u8),
+    Xori(u8, u8),
+    Cmp(u8, u8),
+    Cmpi(u8, u8),
+    /// `LD rd, [page + ra]`
+    Ld { rd: u8, page: u8, ra: u8 },
+    /// `ST [page + ra], rs`
+    St { page: u8, ra: u8, rs: u8 },
+    In(u8, u8),
+    Out(u8, u8),
+    Rnd(u8, u8),
+    Jmp(u8),
+    Jz(u8),
+    Jnz(u8),
+    Jc(u8),
+    Jnc(u8),
+    Hlt,
+}
+
+// ============================================================================
+// 汇编器
+// ============================================================================
+
+/// 汇编错误：行号（从 1 开始）与描述
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AsmError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for AsmError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "第 {} 行: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for AsmError {}
+
+/// 去掉注释与首尾空白
+fn strip_line(line: &str) -> &str {
+    line.split(';').next().unwrap_or("").trim()
+}
+
+/// 把汇编文本翻译为指令序列（两遍：先收集标签与常量，再逐行解析）
+pub fn assemble(source: &str) -> Result<Vec<Instr>, AsmError> {
+    let mut symbols: HashMap<&str, u32> = HashMap::new();
+    let mut addr = 0u32;
+
+    // 第一遍：标签与 .equ
+    for (i, raw) in source.lines().enumerate() {
+        let err = |message: String| AsmError { line: i + 1, message };
+        let line = strip_line(raw);
+        if line.is_empty() {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix(".equ") {
+            let mut parts = rest.split_whitespace();
+            let (Some(name), Some(value), None) = (parts.next(), parts.next(), parts.next()) else {
+                return Err(err(format!("非法的 .equ: {line}")));
+            };
+            let value = parse_number(value).ok_or_else(|| err(format!("非法的数值: {value}")))?;
+            if symbols.insert(name, value).is_some() {
+                return Err(err(format!("符号重复定义: {name}")));
+            }
+        } else if let Some(label) = line.strip_suffix(':') {
+            if symbols.insert(label, addr).is_some() {
+                return Err(err(format!("符号重复定义: {label}")));
+            }
+        } else {
+            addr += 1;
+        }
+    }
+    if addr as usize > ROM_SIZE {
+        return Err(AsmError {
+            line: source.lines().count(),
+            message: format!("程序 {addr} 条指令，超出 ROM 容量 {ROM_SIZE}"),
+        });
+    }
+
+    // 第二遍：解析指令
+    let mut program = Vec::with_capacity(addr as usize);
+    for (i, raw) in source.lines().enumerate() {
+        let line = strip_line(raw);
+        if line.is_empty() || line.starts_with(".equ") || line.ends_with(':') {
+            continue;
+        }
+        let instr = parse_instr(line, &symbols).map_err(|message| AsmError { line: i + 1, message })?;
+        program.push(instr);
+    }
+    Ok(program)
+}
+
+fn parse_number(s: &str) -> Option<u32> {
+    match s.strip_prefix("0x") {
+        Some(hex) => u32::from_str_radix(hex, 16).ok(),
+        None => s.parse().ok(),
+    }
+}
+
+fn parse_instr(line: &str, symbols: &HashMap<&str, u32>) -> Result<Instr, String> {
+    let (mnemonic, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+    let ops: Vec<&str> = if rest.trim().is_empty() {
+        Vec::new()
+    } else {
+        rest.split(',').map(str::trim).collect()
+    };
+
+    let reg = |s: &str| -> Result<u8, String> {
+        s.strip_prefix('R')
+            .and_then(|n| n.parse::<u8>().ok())
+            .filter(|&r| (r as usize) < REG_COUNT)
+            .ok_or_else(|| format!("非法的寄存器: {s}"))
+    };
+    let value = |s: &str, max: u32| -> Result<u8, String> {
+        let v = parse_number(s)
+            .or_else(|| symbols.get(s).copied())
+            .ok_or_else(|| format!("未定义的符号或非法数值: {s}"))?;
+        if v > max {
+            return Err(format!("数值 {s} = {v} 超出范围 0..={max}"));
+        }
+        Ok(v as u8)
+    };
+    let imm = |s: &str| value(s, u8::MAX as u32);
+    let target = |s: &str| value(s, ROM_SIZE as u32 - 1);
+    // `[PAGE + Rn]`
+    let mem = |s: &str| -> Result<(u8, u8), String> {
+        let inner = s
+            .strip_prefix('[')
+            .and_then(|s| s.strip_suffix(']'))
+            .ok_or_else(|| format!("非法的访存操作数: {s}"))?;
+        let (page, ra) = inner
+            .split_once('+')
+            .ok_or_else(|| format!("访存操作数应为 [页 + 寄存器]: {s}"))?;
+        Ok((value(page.trim(), PAGE_COUNT as u32 - 1)?, reg(ra.trim())?))
+    };
+
+    let instr = match (mnemonic, ops.as_slice()) {
+        ("LDI", [rd, v]) => Instr::Ldi(reg(rd)?, imm(v)?),
+        ("MOV", [rd, rs]) => Instr::Mov(reg(rd)?, reg(rs)?),
+        ("ADD", [rd, rs]) => Instr::Add(reg(rd)?, reg(rs)?),
+        ("ADDI", [rd, v]) => Instr::Addi(reg(rd)?, imm(v)?),
+        ("SUB", [rd, rs]) => Instr::Sub(reg(rd)?, reg(rs)?),
+        ("SUBI", [rd, v]) => Instr::Subi(reg(rd)?, imm(v)?),
+        ("ANDI", [rd, v]) => Instr::Andi(reg(rd)?, imm(v)?),
+        ("XORI", [rd, v]) => Instr::Xori(reg(rd)?, imm(v)?),
+        ("CMP", [rd, rs]) => Instr::Cmp(reg(rd)?, reg(rs)?),
+        ("CMPI", [rd, v]) => Instr::Cmpi(reg(rd)?, imm(v)?),
+        ("LD", [rd, m]) => {
+            let (page, ra) = mem(m)?;
+            Instr::Ld { rd: reg(rd)?, page, ra }
+        }
+        ("ST", [m, rs]) => {
+            let (page, ra) = mem(m)?;
+            Instr::St { page, ra, rs: reg(rs)? }
+        }
+        ("IN", [rd, port]) => Instr::In(reg(rd)?, imm(port)?),
+        ("OUT", [port, rs]) => Instr::Out(imm(port)?, reg(rs)?),
+        ("RND", [rd, rs]) => Instr::Rnd(reg(rd)?, reg(rs)?),
+        ("JMP", [a]) => Instr::Jmp(target(a)?),
+        ("JZ", [a]) => Instr::Jz(target(a)?),
+        ("JNZ", [a]) => Instr::Jnz(target(a)?),
+        ("JC", [a]) => Instr::Jc(target(a)?),
+        ("JNC", [a]) => Instr::Jnc(target(a)?),
+        ("HLT", []) => Instr::Hlt,
+        (
+            "LDI" | "MOV" | "ADD" | "ADDI" | "SUB" | "SUBI" | "ANDI" | "XORI" | "CMP" | "CMPI"
+            | "LD" | "ST" | "IN" | "OUT" | "RND" | "JMP" | "JZ" | "JNZ" | "JC" | "JNC" | "HLT",
+            _,
+        ) => return Err(format!("{mnemonic} 的操作数个数错误: {}", ops.len())),
+        _ => return Err(format!("未知指令: {mnemonic}")),
+    };
+    Ok(instr)
+}
+
+// ============================================================================
+// 解释器
+// ============================================================================
+
+/// 宿主提供的 I/O：端口读写与随机数
+pub trait Io {
+    /// `IN` 指令读取端口
+    fn input(&mut self, port: u8) -> u8;
+    /// `OUT` 指令写入端口
+    fn output(&mut self, port: u8, value: u8);
+    /// `RND` 指令：返回 `[0, bound)` 内的随机数（`bound > 0`）
+    fn random(&mut self, bound: u8) -> u8;
+}
+
+/// 执行错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExecError {
+    /// 程序计数器越过程序末尾（缺少 HLT）
+    PcOutOfRange(usize),
+    /// `RND` 的上界为 0
+    ZeroRandomBound(usize),
+    /// 超过允许的最大周期数（疑似死循环）
+    CycleLimit,
+}
+
+impl fmt::Display for ExecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExecError::PcOutOfRange(pc) => write!(f, "PC={pc} 越过程序末尾"),
+            ExecError::ZeroRandomBound(pc) => write!(f, "PC={pc}: RND 上界为 0"),
+            ExecError::CycleLimit => write!(f, "超过最大周期数"),
+        }
+    }
+}
+
+impl std::error::Error for ExecError {}
+
+/// RS8 机器状态
+#[derive(Debug, Clone)]
+pub struct Machine {
+    pub regs: [u8; REG_COUNT],
+    /// 零标志
+    pub zero: bool,
+    /// 进位/借位标志
+    pub carry: bool,
+    pub mem: Memory,
+}
+
+impl Machine {
+    /// 以给定 RAM 内容创建机器，寄存器与标志清零
+    pub fn new(mem: Memory) -> Self {
+        Self {
+            regs: [0; REG_COUNT],
+            zero: false,
+            carry: false,
+            mem,
+        }
+    }
+
+    /// 设置算术结果的标志位
+    fn set_flags(&mut self, result: u8, carry: bool) {
+        self.zero = result == 0;
+        self.carry = carry;
+    }
+
+    /// 从地址 0 开始运行，直到 HLT；返回消耗的周期数（每条指令 1 周期）
+    pub fn run(
+        &mut self,
+        program: &[Instr],
+        io: &mut impl Io,
+        max_cycles: u64,
+    ) -> Result<u64, ExecError> {
+        let mut pc = 0usize;
+        let mut cycles = 0u64;
+        loop {
+            if cycles >= max_cycles {
+                return Err(ExecError::CycleLimit);
+            }
+            let instr = *program.get(pc).ok_or(ExecError::PcOutOfRange(pc))?;
+            cycles += 1;
+            pc += 1;
+
+            let r = &mut self.regs;
+            match instr {
+                Instr::Ldi(rd, v) => r[rd as usize] = v,
+                Instr::Mov(rd, rs) => r[rd as usize] = r[rs as usize],
+                Instr::Add(rd, rs) => self.add(rd, self.regs[rs as usize]),
+                Instr::Addi(rd, v) => self.add(rd, v),
+                Instr::Sub(rd, rs) => {
+                    let v = self.sub_flags(rd, self.regs[rs as usize]);
+                    self.regs[rd as usize] = v;
+                }
+                Instr::Subi(rd, v) => {
+                    let v = self.sub_flags(rd, v);
+                    self.regs[rd as usize] = v;
+                }
+                Instr::Andi(rd, v) => {
+                    let v = r[rd as usize] & v;
+                    r[rd as usize] = v;
+                    self.set_flags(v, false);
+                }
+                Instr::Xori(rd, v) => {
+                    let v = r[rd as usize] ^ v;
+                    r[rd as usize] = v;
+                    self.set_flags(v, false);
+                }
+                Instr::Cmp(rd, rs) => {
+                    self.sub_flags(rd, self.regs[rs as usize]);
+                }
+                Instr::Cmpi(rd, v) => {
+                    self.sub_flags(rd, v);
+                }
+                Instr::Ld { rd, page, ra } => {
+                    r[rd as usize] = self.mem[page as usize][r[ra as usize] as usize];
+                }
+                Instr::St { page, ra, rs } => {
+                    self.mem[page as usize][r[ra as usize] as usize] = r[rs as usize];
+                }
+                Instr::In(rd, port) => r[rd as usize] = io.input(port),
+                Instr::Out(port, rs) => io.output(port, r[rs as usize]),
+                Instr::Rnd(rd, rs) => {
+                    let bound = r[rs as usize];
+                    if bound == 0 {
+                        return Err(ExecError::ZeroRandomBound(pc - 1));
+                    }
+                    r[rd as usize] = io.random(bound);
+                }
+                Instr::Jmp(a) => pc = a as usize,
+                Instr::Jz(a) => {
+                    if self.zero {
+                        pc = a as usize;
+                    }
+                }
+                Instr::Jnz(a) => {
+                    if !self.zero {
+                        pc = a as usize;
+                    }
+                }
+                Instr::Jc(a) => {
+                    if self.carry {
+                        pc = a as usize;
+                    }
+                }
+                Instr::Jnc(a) => {
+                    if !self.carry {
+                        pc = a as usize;
+                    }
+                }
+                Instr::Hlt => return Ok(cycles),
+            }
+        }
+    }
+
+    fn add(&mut self, rd: u8, v: u8) {
+        let (result, carry) = self.regs[rd as usize].overflowing_add(v);
+        self.regs[rd as usize] = result;
+        self.set_flags(result, carry);
+    }
+
+    /// 计算 rd − v 并设置标志（不写回）
+    fn sub_flags(&mut self, rd: u8, v: u8) -> u8 {
+        let (result, borrow) = self.regs[rd as usize].overflowing_sub(v);
+        self.set_flags(result, borrow);
+        result
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 记录输出、随机数恒为 bound-1 的测试 I/O
+    struct TestIo {
+        input: u8,
+        outputs: Vec<(u8, u8)>,
+    }
+
+    impl Io for TestIo {
+        fn input(&mut self, _port: u8) -> u8 {
+            self.input
+        }
+        fn output(&mut self, port: u8, value: u8) {
+            self.outputs.push((port, value));
+        }
+        fn random(&mut self, bound: u8) -> u8 {
+            bound - 1
+        }
+    }
+
+    fn run(source: &str, input: u8) -> (Machine, Vec<(u8, u8)>) {
+        let program = assemble(source).unwrap();
+        let mut machine = Machine::new([[0; PAGE_SIZE]; PAGE_COUNT]);
+        let mut io = TestIo { input, outputs: Vec::new() };
+        machine.run(&program, &mut io, 1_000).unwrap();
+        (machine, io.outputs)
+    }
+
+    #[test]
+    fn test_assemble_labels_and_equ() {
+        let program = assemble(
+            ".equ PAGE 3\n\
+             start:\n\
+                 LDI R0, 0x10 ; 注释\n\
+                 ST [PAGE + R0], R0\n\
+                 JMP start\n",
+        )
+        .unwrap();
+        assert_eq!(
+            program,
+            vec![
+                Instr::Ldi(0, 16),
+                Instr::St { page: 3, ra: 0, rs: 0 },
+                Instr::Jmp(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_assemble_errors() {
+        assert_eq!(assemble("NOP").unwrap_err().line, 1);
+        assert!(assemble("LDI R8, 1").is_err());
+        assert!(assemble("LDI R0, 256").is_err());
+        assert!(assemble("LD R0, [8 + R1]").is_err());
+        assert!(assemble("JMP nowhere").is_err());
+        assert!(assemble("a:\na:\nHLT").is_err());
+    }
+
+    #[test]
+    fn test_flags_and_branches() {
+        // 250 + 10 溢出 → C=1；随后 3 − 5 借位 → C=1
+        let (m, out) = run(
+            "LDI R0, 250\n\
+             ADDI R0, 10\n\
+             JNC fail\n\
+             LDI R1, 3\n\
+             CMPI R1, 5\n\
+             JNC fail\n\
+             CMPI R1, 3\n\
+             JNZ fail\n\
+             OUT 1, R0\n\
+             HLT\n\
+             fail:\n\
+             HLT\n",
+            0,
+        );
+        assert_eq!(m.regs[0], 4);
+        assert_eq!(out, vec![(1, 4)]);
+    }
+
+    #[test]
+    fn test_memory_io_and_random() {
+        let (m, out) = run(
+            "IN R0, 0\n\
+             LDI R1, 7\n\
+             ST [2 + R1], R0\n\
+             LD R2, [2 + R1]\n\
+             RND R3, R1\n\
+             OUT 0, R3\n\
+             HLT\n",
+            42,
+        );
+        assert_eq!(m.mem[2][7], 42);
+        assert_eq!(m.regs[2], 42);
+        assert_eq!(out, vec![(0, 6)]);
+    }
+
+    #[test]
+    fn test_exec_errors() {
+        let mut io = TestIo { input: 0, outputs: Vec::new() };
+        let mut m = Machine::new([[0; PAGE_SIZE]; PAGE_COUNT]);
+        let missing_hlt = assemble("LDI R0, 1").unwrap();
+        assert_eq!(m.run(&missing_hlt, &mut io, 10), Err(ExecError::PcOutOfRange(1)));
+        let spin = assemble("l:\nJMP l").unwrap();
+        assert_eq!(m.run(&spin, &mut io, 10), Err(ExecError::CycleLimit));
+        let zero = assemble("RND R0, R1\nHLT").unwrap();
+        assert_eq!(m.run(&zero, &mut io, 10), Err(ExecError::ZeroRandomBound(0)));
+    }
+}
@@ -0,0 +1,141 @@
+//! 引擎数据结构在 RS8 数据 RAM 中的布局
+//!
+//! 每张表独占一页，页内偏移即 hash 或下标（地图最多 256 格，hash 恰好 8 位）：
+//!
+//! | 页 | 名称        | 内容                                            |
+//! |----|-------------|-------------------------------------------------|
+//! | 0  | `MAP`       | 格子状态（0 空 / 1 食物 / 2 蛇身，同 `CellState`） |
+//! | 1  | `BODY`      | 蛇身环形缓冲区，`VAR_TAIL`..=`VAR_HEAD`          |
+//! | 2  | `EMPTY`     | 空格列表 `empty_cells`                          |
+//! | 3  | `EMPTY_IDX` | 反向表 `empty_indices`，`NONE` 表示不在列表中   |
+//! | 4  | `FOOD`      | 食物列表 `food_hashes`                          |
+//! | 5  | `FOOD_IDX`  | 反向表 `food_indices`，`NONE` 表示不是食物       |
+//! | 6  | `VARS`      | 标量变量（见 `VAR_*`）                          |
+//!
+//! 列表有效长度之外的字节无意义；蛇长为 256 时 `VAR_LEN` 回绕为 0（此时游戏已胜利）。
+
+use crate::config::MapConfig;
+use crate::redstone::isa::{Memory, PAGE_COUNT, PAGE_SIZE};
+use crate::snake::SnakeGame;
+use crate::types::{Direction, GameState};
+
+pub const MAP: u8 = 0;
+pub const BODY: u8 = 1;
+pub const EMPTY: u8 = 2;
+pub const EMPTY_IDX: u8 = 3;
+pub const FOOD: u8 = 4;
+pub const FOOD_IDX: u8 = 5;
+pub const VARS: u8 = 6;
+
+/// 当前方向（`NONE` 表示尚未收到方向输入）
+pub const VAR_DIR: u8 = 0;
+/// 蛇尾在 BODY 环中的位置
+pub const VAR_TAIL: u8 = 1;
+/// 蛇头在 BODY 环中的位置
+pub const VAR_HEAD: u8 = 2;
+/// 蛇长
+pub const VAR_LEN: u8 = 3;
+/// 空格列表长度
+pub const VAR_EMPTY_LEN: u8 = 4;
+/// 食物列表长度
+pub const VAR_FOOD_LEN: u8 = 5;
+
+/// 空值哨兵（对应引擎中的 `usize::MAX` / `None`）
+pub const NONE: u8 = 0xFF;
+
+/// 方向输入端口（`IN`）
+pub const PORT_DIR: u8 = 0;
+/// tick 结果端口（`OUT`），值见 [`encode_state`]
+pub const PORT_STATE: u8 = 0;
+
+/// 布局是否支持该地图：总格数 ≤ 256 且宽度为 2 的幂（边界检查只需按位与）
+pub fn supports(config: &MapConfig) -> bool {
+    config.total_size() <= PAGE_SIZE && config.width.is_power_of_two()
+}
+
+/// 方向编码：Up=0, Down=1, Left=2, Right=3（相反方向 = 编码 ^ 1）
+pub const fn encode_direction(dir: Option<Direction>) -> u8 {
+    match dir {
+        Some(Direction::Up) => 0,
+        Some(Direction::Down) => 1,
+        Some(Direction::Left) => 2,
+        Some(Direction::Right) => 3,
+        None => NONE,
+    }
+}
+
+/// [`encode_direction`] 的逆映射
+pub const fn decode_direction(code: u8) -> Option<Direction> {
+    match code {
+        0 => Some(Direction::Up),
+        1 => Some(Direction::Down),
+        2 => Some(Direction::Left),
+        3 => Some(Direction::Right),
+        _ => None,
+    }
+}
+
+/// 状态编码：Ready=0, Running=1, Over=2, Won=3
+pub const fn encode_state(state: GameState) -> u8 {
+    match state {
+        GameState::Ready => 0,
+        GameState::Running => 1,
+        GameState::Over => 2,
+        GameState::Won => 3,
+    }
+}
+
+/// [`encode_state`] 的逆映射
+pub const fn decode_state(code: u8) -> Option<GameState> {
+    match code {
+        0 => Some(GameState::Ready),
+        1 => Some(GameState::Running),
+        2 => Some(GameState::Over),
+        3 => Some(GameState::Won),
+        _ => None,
+    }
+}
+
+/// 把引擎状态写成 RS8 内存映像
+///
+/// 蛇尾放在 BODY 环的位置 0；列表之外的字节填 0，反向表中无效项填 `NONE`。
+///
+/// # Panics
+/// 地图不满足 [`supports`]
+pub fn load_game(game: &SnakeGame) -> Memory {
+    let config = game.config();
+    assert!(
+        supports(config),
+        "RS8 布局要求总格数 ≤ {PAGE_SIZE} 且宽度为 2 的幂（当前 {}×{}）",
+        config.width,
+        config.height,
+    );
+
+    let mut mem = [[0u8; PAGE_SIZE]; PAGE_COUNT];
+    mem[EMPTY_IDX as usize] = [NONE; PAGE_SIZE];
+    mem[FOOD_IDX as usize] = [NONE; PAGE_SIZE];
+
+    for (hash, cell) in mem[MAP as usize][..config.total_size()].iter_mut().enumerate() {
+        *cell = game.cell_state(hash) as u8;
+    }
+    for (i, &h) in game.snake_hashes().enumerate() {
+        mem[BODY as usize][i] = h as u8;
+    }
+    for (i, &h) in game.empty_cells().iter().enumerate() {
+        mem[EMPTY as usize][i] = h as u8;
+        mem[EMPTY_IDX as usize][h] = i as u8;
+    }
+    for (i, &h) in game.food_hashes().iter().enumerate() {
+        mem[FOOD as usize][i] = h as u8;
+        mem[FOOD_IDX as usize][h] = i as u8;
+    }
+
+    let vars = &mut mem[VARS as usize];
+    vars[VAR_DIR as usize] = encode_direction(game.direction());
+    vars[VAR_TAIL as usize] = 0;
+    vars[VAR_HEAD as usize] = (game.length() - 1) as u8;
+    vars[VAR_LEN as usize] = game.length() as u8;
+    vars[VAR_EMPTY_LEN as usize] = game.empty_cells().len() as u8;
+    vars[VAR_FOOD_LEN as usize] = game.food_count() as u8;
+    mem
+}
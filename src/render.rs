@@ -1,5 +1,16 @@
+use crate::config::MapConfig;
+use crate::multi::MultiSnakeGame;
+#[cfg(feature = "ai")]
+use crate::pathfinding::Plan;
 use crate::snake::SnakeGame;
 use crate::types::{CellState, Position};
+use std::fmt::Write;
+
+/// 多蛇对战中各玩家的 ANSI 前景色（按蛇编号循环使用）
+pub const PLAYER_COLORS: [&str; 4] = ["\x1B[32m", "\x1B[34m", "\x1B[33m", "\x1B[35m"];
+
+/// 恢复默认颜色
+const RESET: &str = "\x1B[0m";
 
 /// 将当前游戏状态渲染到给定的 String 缓冲区
 ///
@@ -42,3 +53,189 @@ pub fn render(game: &SnakeGame, output: &mut String) {
     output.push_str(&border_line);
     output.push('\n');
 }
+
+/// AI 调试视图：左侧为棋盘（计划路径标为 `*`），右侧为交规距离热力图，末行为搜索统计
+///
+/// 热力图中距离 0–9 显示为数字，10–35 显示为 `a`–`z`，更远显示为 `+`，不可达显示为 `?`。
+#[cfg(feature = "ai")]
+pub fn render_debug(game: &SnakeGame, plan: &Plan, output: &mut String) {
+    let config = game.config();
+    let w = config.width as usize;
+    let h = config.height as usize;
+    let border_line = "-".repeat(w + 2);
+
+    output.clear();
+    output.push_str("\x1B[2J\x1B[1;1H");
+    let _ = writeln!(output, "{border_line}   {border_line}");
+    for row in 0..h {
+        output.push('|');
+        for col in 0..w {
+            let hash = config.to_hash(Position {
+                x: col as u32,
+                y: row as u32,
+            });
+            output.push(match game.cell_state(hash) {
+                CellState::Snake => '#',
+                CellState::Food => 'F',
+                CellState::Empty if plan.path.contains(&hash) => '*',
+                CellState::Empty => ' ',
+            });
+        }
+        output.push_str("|   |");
+        for col in 0..w {
+            let hash = config.to_hash(Position {
+                x: col as u32,
+                y: row as u32,
+            });
+            output.push(match plan.dist[hash] {
+                d @ 0..=9 => char::from(b'0' + d as u8),
+                d @ 10..=35 => char::from(b'a' + (d - 10) as u8),
+                u32::MAX => '?',
+                _ => '+',
+            });
+        }
+        output.push_str("|\n");
+    }
+    let _ = writeln!(output, "{border_line}   {border_line}");
+    let result = if plan.found() { "found" } else { "fallback" };
+    let _ = writeln!(
+        output,
+        "{result}: path {} steps, {} expanded in {:.1} ms",
+        plan.path.len(),
+        plan.expanded,
+        plan.elapsed.as_secs_f64() * 1000.0
+    );
+}
+
+/// 多蛇画面中单个格子的内容
+#[derive(Clone, Copy)]
+pub(crate) enum Glyph {
+    Empty,
+    Food,
+    /// 第 n 条蛇的蛇身
+    Body(usize),
+    /// 第 n 条蛇的蛇头
+    Head(usize),
+}
+
+/// 渲染多蛇对战画面：各蛇按 [`PLAYER_COLORS`] 着色，蛇头为 `@`，末尾附每位玩家的分数
+pub fn render_multi(game: &MultiSnakeGame, output: &mut String) {
+    let config = game.config();
+    let scores: Vec<u32> = (0..game.snake_count()).map(|i| game.score(i)).collect();
+    render_players(
+        config,
+        |hash| match (game.cell_state(hash), game.owner(hash)) {
+            (CellState::Snake, Some(i)) if game.head_position(i) == config.from_hash(hash) => {
+                Glyph::Head(i)
+            }
+            (CellState::Snake, Some(i)) => Glyph::Body(i),
+            (CellState::Food, _) => Glyph::Food,
+            _ => Glyph::Empty,
+        },
+        &scores,
+        output,
+    );
+}
+
+/// 多蛇画面的通用绘制：`cell` 给出每个格子的内容，`scores[i]` 为第 i 位玩家的分数
+pub(crate) fn render_players(
+    config: &MapConfig,
+    cell: impl Fn(usize) -> Glyph,
+    scores: &[u32],
+    output: &mut String,
+) {
+    let w = config.width as usize;
+    let h = config.height as usize;
+    let border_line = "-".repeat(w + 2);
+
+    output.clear();
+    output.push_str("\x1B[2J\x1B[1;1H");
+    output.push_str(&border_line);
+    output.push('\n');
+
+    for row in 0..h {
+        output.push('|');
+        for col in 0..w {
+            let hash = config.to_hash(Position {
+                x: col as u32,
+                y: row as u32,
+            });
+            match cell(hash) {
+                Glyph::Empty => output.push(' '),
+                Glyph::Food => output.push('F'),
+                Glyph::Body(i) => push_colored(output, i, '#'),
+                Glyph::Head(i) => push_colored(output, i, '@'),
+            }
+        }
+        output.push_str("|\n");
+    }
+
+    output.push_str(&border_line);
+    output.push('\n');
+    for (i, score) in scores.iter().enumerate() {
+        let color = PLAYER_COLORS[i % PLAYER_COLORS.len()];
+        let _ = write!(output, "{color}P{}{RESET}: {score}  ", i + 1);
+    }
+    output.push('\n');
+}
+
+/// 以第 `player` 位玩家的颜色输出一个字符
+fn push_colored(output: &mut String, player: usize, ch: char) {
+    output.push_str(PLAYER_COLORS[player % PLAYER_COLORS.len()]);
+    output.push(ch);
+    output.push_str(RESET);
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(all(test, feature = "ai"))]
+mod tests {
+    use super::*;
+    use crate::game::Game;
+    use crate::pathfinding::{self, AiConfig};
+    use std::time::Duration;
+
+    #[test]
+    fn test_render_debug() {
+        let game = Game::with_seed(MapConfig::new(8, 6), 3, 1, 4);
+        let snake = game.snake();
+        let mut plan = pathfinding::plan(snake, &AiConfig::default()).expect("开局应有合法方向");
+        plan.elapsed = Duration::from_micros(1500);
+        assert!(plan.found() && !plan.path.is_empty());
+        // 覆盖热力图的每种字符
+        for (hash, d) in [(0, 0), (1, 9), (2, 10), (3, 35), (4, 36), (5, u32::MAX)] {
+            plan.dist[hash] = d;
+        }
+
+        let mut out = String::new();
+        render_debug(snake, &plan, &mut out);
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 1 + 6 + 1 + 1);
+        assert_eq!(lines[0], "\x1B[2J\x1B[1;1H----------   ----------");
+        assert_eq!(&lines[1][14..20], "09az+?");
+
+        let config = snake.config();
+        for hash in 0..config.total_size() {
+            let pos = config.from_hash(hash);
+            let row: Vec<char> = lines[1 + pos.y as usize].chars().collect();
+            let expected = match snake.cell_state(hash) {
+                CellState::Snake => '#',
+                CellState::Food => 'F',
+                CellState::Empty if plan.path.contains(&hash) => '*',
+                CellState::Empty => ' ',
+            };
+            assert_eq!(row[1 + pos.x as usize], expected, "格子 {hash}");
+        }
+        assert!(lines[1..7].iter().any(|l| l[..10].contains('*')), "{out}");
+        assert_eq!(
+            lines[8],
+            format!("found: path {} steps, {} expanded in 1.5 ms", plan.path.len(), plan.expanded)
+        );
+
+        plan.target = None;
+        render_debug(snake, &plan, &mut out);
+        assert!(out.lines().last().unwrap().starts_with("fallback: path "), "{out}");
+    }
+}
@@ -0,0 +1,84 @@
+//! 状态可导出的随机数生成器
+//!
+//! 游戏需要把 RNG 状态写入快照、在历史中回退，因此使用状态仅 64 位的 SplitMix64，
+//! 而不是状态不透明的 `ThreadRng`。
+
+use rand::rand_core::impls;
+use rand::{RngCore, SeedableRng};
+
+/// SplitMix64：64 位状态，每次输出 O(1)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GameRng {
+    state: u64,
+}
+
+impl GameRng {
+    /// 从系统熵源创建
+    pub fn from_entropy() -> Self {
+        Self::from_state(rand::random())
+    }
+
+    /// 从导出的状态恢复
+    pub const fn from_state(state: u64) -> Self {
+        Self { state }
+    }
+
+    /// 导出当前状态（恢复后产生完全相同的后续序列）
+    pub const fn state(&self) -> u64 {
+        self.state
+    }
+}
+
+impl RngCore for GameRng {
+    fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        impls::fill_bytes_via_next(self, dst)
+    }
+}
+
+impl SeedableRng for GameRng {
+    type Seed = [u8; 8];
+
+    fn from_seed(seed: Self::Seed) -> Self {
+        Self::from_state(u64::from_le_bytes(seed))
+    }
+
+    fn seed_from_u64(state: u64) -> Self {
+        Self::from_state(state)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::Rng;
+
+    #[test]
+    fn test_splitmix64_reference_values() {
+        // 参考实现（种子 1234567）的前两个输出
+        let mut rng = GameRng::seed_from_u64(1234567);
+        assert_eq!(rng.next_u64(), 6457827717110365317);
+        assert_eq!(rng.next_u64(), 3203168211198807973);
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let mut rng = GameRng::seed_from_u64(9);
+        rng.random_range(0..100usize);
+        let mut copy = GameRng::from_state(rng.state());
+        for _ in 0..10 {
+            assert_eq!(rng.random_range(0..1000usize), copy.random_range(0..1000usize));
+        }
+    }
+}
@@ -0,0 +1,373 @@
+//! 批量模拟：用固定种子无界面地跑多局 AI，统计结果与决策开销
+//!
+//! 每个 tick 都重新调用 [`pathfinding::plan`]（不缓存计划），
+//! 因此统计到的展开数与耗时直接反映 [`AiConfig`] 的影响。
+//! [`play_mcts`] 以同样的方式跑 [`Mcts`] 控制器，便于两者对比。
+
+use crate::config::MapConfig;
+use crate::game::Game;
+use crate::mcts::{Mcts, MctsConfig};
+use crate::pathfinding::{self, AiConfig};
+use crate::snake::SnakeGame;
+use crate::types::{Direction, GameState};
+use std::fmt;
+use std::time::Duration;
+
+/// 每局的开局参数与 tick 上限
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SimOptions {
+    pub map: MapConfig,
+    pub initial_length: usize,
+    pub food_count: usize,
+    /// 达到该步数仍未结束的对局按超时计
+    pub max_ticks: u64,
+}
+
+impl Default for SimOptions {
+    fn default() -> Self {
+        SimOptions {
+            map: MapConfig::new(16, 16),
+            initial_length: 3,
+            food_count: 1,
+            max_ticks: 10_000,
+        }
+    }
+}
+
+/// 单局结果
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GameResult {
+    pub seed: u64,
+    /// 最终状态（超时为 Running）
+    pub state: GameState,
+    pub moves: u64,
+    pub length: usize,
+    /// 搜索次数
+    pub decisions: u64,
+    /// 全部决策的展开状态数之和（MCTS 为树节点数之和）
+    pub expanded: u64,
+    /// 因展开数或时间上限提前结束的决策数
+    pub budget_hits: u64,
+    /// 全部决策耗时之和
+    pub decision_time: Duration,
+    /// 单次决策的最长耗时
+    pub max_decision: Duration,
+    /// 决策耗时分布
+    pub latency: LatencyHistogram,
+}
+
+/// 决策耗时的对数直方图：每个 2 的幂区间再分 8 格，分位数的相对误差不超过 12.5%
+///
+/// 定长计数、可合并，不必保存每次决策的耗时。
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct LatencyHistogram {
+    /// 下标见 [`bucket`](Self::bucket)，按需增长
+    counts: Vec<u64>,
+    total: u64,
+}
+
+impl LatencyHistogram {
+    /// 每个 2 的幂区间细分的格数（2^SUB_BITS）
+    const SUB_BITS: u32 = 3;
+
+    /// 纳秒数所在的格：小于 8 ns 时一格一纳秒，之后每个 2 的幂区间 8 格
+    fn bucket(ns: u64) -> usize {
+        let sub = 1 << Self::SUB_BITS;
+        if ns < sub {
+            return ns as usize;
+        }
+        let e = 63 - ns.leading_zeros();
+        let shift = e - Self::SUB_BITS;
+        ((shift as u64 + 1) * sub + ((ns >> shift) & (sub - 1))) as usize
+    }
+
+    /// 格 `i` 的上界（纳秒，含）
+    fn upper_bound(i: usize) -> u64 {
+        let sub = 1u64 << Self::SUB_BITS;
+        let i = i as u64;
+        if i < sub {
+            return i;
+        }
+        let shift = i / sub - 1;
+        // 最后一格的上界为 2^64，回绕后恰为 u64::MAX
+        ((sub + i % sub + 1) << shift).wrapping_sub(1)
+    }
+
+    pub fn record(&mut self, latency: Duration) {
+        let i = Self::bucket(latency.as_nanos().min(u64::MAX as u128) as u64);
+        if self.counts.len() <= i {
+            self.counts.resize(i + 1, 0);
+        }
+        self.counts[i] += 1;
+        self.total += 1;
+    }
+
+    pub fn merge(&mut self, other: &LatencyHistogram) {
+        if self.counts.len() < other.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+        for (a, b) in self.counts.iter_mut().zip(&other.counts) {
+            *a += b;
+        }
+        self.total += other.total;
+    }
+
+    /// 记录的决策数
+    pub fn count(&self) -> u64 {
+        self.total
+    }
+
+    /// 第 `p` 分位（0.0–1.0）所在格的上界；没有记录时为 0
+    pub fn percentile(&self, p: f64) -> Duration {
+        let rank = ((p.clamp(0.0, 1.0) * self.total as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (i, &c) in self.counts.iter().enumerate() {
+            seen += c;
+            if seen >= rank {
+                return Duration::from_nanos(Self::upper_bound(i));
+            }
+        }
+        Duration::ZERO
+    }
+}
+
+/// 单次决策的结果与开销
+struct Decision {
+    direction: Option<Direction>,
+    expanded: usize,
+    budget_exhausted: bool,
+    elapsed: Duration,
+}
+
+/// 以种子 `seed` 跑一局 A*
+pub fn play(ai: &AiConfig, options: &SimOptions, seed: u64) -> GameResult {
+    play_with(options, seed, |snake| {
+        pathfinding::plan(snake, ai).map(|plan| Decision {
+            direction: plan.direction(),
+            expanded: plan.expanded,
+            budget_exhausted: plan.budget_exhausted,
+            elapsed: plan.elapsed,
+        })
+    })
+}
+
+/// 以种子 `seed` 跑一局 MCTS；rollout 的种子为 `config.seed + seed`
+pub fn play_mcts(config: &MctsConfig, options: &SimOptions, seed: u64) -> GameResult {
+    let mut mcts = Mcts::new(MctsConfig { seed: config.seed.wrapping_add(seed), ..*config });
+    play_with(options, seed, |snake| {
+        let result = mcts.search(snake);
+        Some(Decision {
+            direction: result.direction,
+            expanded: result.nodes,
+            budget_exhausted: false,
+            elapsed: result.elapsed,
+        })
+    })
+}
+
+/// 逐 tick 调用 `decide` 跑一局（返回 None 的 tick 沿原方向前进，不计入决策）
+fn play_with(
+    options: &SimOptions,
+    seed: u64,
+    mut decide: impl FnMut(&SnakeGame) -> Option<Decision>,
+) -> GameResult {
+    let mut game = Game::with_seed(
+        options.map.clone(),
+        options.initial_length,
+        options.food_count,
+        seed,
+    );
+    let mut result = GameResult {
+        seed,
+        state: GameState::Running,
+        moves: 0,
+        length: 0,
+        decisions: 0,
+        expanded: 0,
+        budget_hits: 0,
+        decision_time: Duration::ZERO,
+        max_decision: Duration::ZERO,
+        latency: LatencyHistogram::default(),
+    };
+    while game.moves_count() < options.max_ticks {
+        let decision = decide(game.snake());
+        if let Some(d) = &decision {
+            result.decisions += 1;
+            result.expanded += d.expanded as u64;
+            result.budget_hits += d.budget_exhausted as u64;
+            result.decision_time += d.elapsed;
+            result.max_decision = result.max_decision.max(d.elapsed);
+            result.latency.record(d.elapsed);
+        }
+        let state = game.tick(decision.and_then(|d| d.direction));
+        if matches!(state, GameState::Over | GameState::Won) {
+            result.state = state;
+            break;
+        }
+    }
+    result.moves = game.moves_count();
+    result.length = game.snake().length();
+    result
+}
+
+/// 以种子 `0..games` 依次跑多局 A*
+pub fn run(ai: &AiConfig, options: &SimOptions, games: u64) -> Vec<GameResult> {
+    (0..games).map(|seed| play(ai, options, seed)).collect()
+}
+
+/// 以种子 `0..games` 依次跑多局 MCTS
+pub fn run_mcts(config: &MctsConfig, options: &SimOptions, games: u64) -> Vec<GameResult> {
+    (0..games).map(|seed| play_mcts(config, options, seed)).collect()
+}
+
+/// 多局结果的汇总
+#[derive(Debug, Clone, PartialEq)]
+pub struct Summary {
+    pub games: usize,
+    pub wins: usize,
+    pub deaths: usize,
+    /// 达到 tick 上限仍未结束的局数
+    pub timeouts: usize,
+    pub mean_length: f64,
+    pub mean_moves: f64,
+    pub mean_expanded: f64,
+    pub budget_hits: u64,
+    pub mean_decision: Duration,
+    pub max_decision: Duration,
+}
+
+impl Summary {
+    pub fn new(results: &[GameResult]) -> Self {
+        let games = results.len();
+        let count = |state| results.iter().filter(|r| r.state == state).count();
+        let mean = |value: fn(&GameResult) -> u64| {
+            results.iter().map(value).sum::<u64>() as f64 / games.max(1) as f64
+        };
+        let decisions: u64 = results.iter().map(|r| r.decisions).sum();
+        let decision_time: Duration = results.iter().map(|r| r.decision_time).sum();
+        Summary {
+            games,
+            wins: count(GameState::Won),
+            deaths: count(GameState::Over),
+            timeouts: count(GameState::Running),
+            mean_length: mean(|r| r.length as u64),
+            mean_moves: mean(|r| r.moves),
+            mean_expanded: results.iter().map(|r| r.expanded).sum::<u64>() as f64
+                / decisions.max(1) as f64,
+            budget_hits: results.iter().map(|r| r.budget_hits).sum(),
+            mean_decision: Duration::from_nanos((decision_time.as_nanos() / decisions.max(1) as u128) as u64),
+            max_decision: results.iter().map(|r| r.max_decision).max().unwrap_or_default(),
+        }
+    }
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "games {}  won {}  died {}  timed out {}",
+            self.games, self.wins, self.deaths, self.timeouts
+        )?;
+        writeln!(
+            f,
+            "mean length {:.1}  mean moves {:.1}",
+            self.mean_length, self.mean_moves
+        )?;
+        write!(
+            f,
+            "per decision: {:.1} expanded, {:.3} ms mean, {:.3} ms max, {} budget hits",
+            self.mean_expanded,
+            self.mean_decision.as_secs_f64() * 1000.0,
+            self.max_decision.as_secs_f64() * 1000.0,
+            self.budget_hits
+        )
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_play_is_reproducible() {
+        let options = SimOptions {
+            map: MapConfig::new(16, 16),
+            max_ticks: 200,
+            ..SimOptions::default()
+        };
+        let ai = AiConfig::default();
+        let a = play(&ai, &options, 3);
+        let b = play(&ai, &options, 3);
+        assert_eq!((a.state, a.moves, a.length, a.expanded), (b.state, b.moves, b.length, b.expanded));
+        assert_eq!(a.moves, 200);
+        assert_eq!(a.decisions, 200);
+        assert!(a.length > 3);
+
+        let summary = Summary::new(&[a, b]);
+        assert_eq!((summary.games, summary.timeouts), (2, 2));
+    }
+
+    #[test]
+    fn test_play_mcts_is_reproducible() {
+        let options = SimOptions {
+            map: MapConfig::new(8, 8),
+            max_ticks: 40,
+            ..SimOptions::default()
+        };
+        let config = MctsConfig { iterations: 60, rollout_depth: 8, ..MctsConfig::default() };
+        let a = play_mcts(&config, &options, 1);
+        let b = play_mcts(&config, &options, 1);
+        assert_eq!((a.state, a.moves, a.length, a.expanded), (b.state, b.moves, b.length, b.expanded));
+        assert_eq!(a.decisions, a.moves);
+        assert_eq!(a.budget_hits, 0);
+        assert_eq!(a.latency.count(), a.decisions);
+    }
+
+    #[test]
+    fn test_latency_histogram() {
+        for ns in [0, 1, 7, 8, 9, 15, 16, 17, 100, 1_000, 123_456_789, u64::MAX] {
+            let i = LatencyHistogram::bucket(ns);
+            assert!(ns <= LatencyHistogram::upper_bound(i), "ns={ns}");
+            assert!(i == 0 || ns > LatencyHistogram::upper_bound(i - 1), "ns={ns}");
+        }
+
+        let mut a = LatencyHistogram::default();
+        for us in 1..=100 {
+            a.record(Duration::from_micros(us));
+        }
+        let mut b = LatencyHistogram::default();
+        b.record(Duration::from_millis(50));
+        a.merge(&b);
+        assert_eq!(a.count(), 101);
+        let p50 = a.percentile(0.5).as_secs_f64() * 1e6;
+        assert!((50.0..=50.0 * 1.125).contains(&p50), "p50={p50}");
+        assert!(a.percentile(1.0) >= Duration::from_millis(50));
+        assert!(a.percentile(1.0) <= Duration::from_micros(50 * 1125));
+        assert_eq!(LatencyHistogram::default().percentile(0.5), Duration::ZERO);
+    }
+    #[test]
+    fn test_summary_mean_decision_beyond_u32() {
+        let result = |decisions, decision_time| GameResult {
+            seed: 0,
+            state: GameState::Won,
+            moves: 0,
+            length: 0,
+            decisions,
+            expanded: 0,
+            budget_hits: 0,
+            decision_time,
+            max_decision: Duration::ZERO,
+            latency: LatencyHistogram::default(),
+        };
+        // 决策数恰为 2^32 时按 u32 截断会变成除以 0
+        let summary = Summary::new(&[result(1 << 32, Duration::from_nanos(3 << 32))]);
+        assert_eq!(summary.mean_decision, Duration::from_nanos(3));
+        let summary = Summary::new(&[result(3 << 31, Duration::from_secs(3 << 31))]);
+        assert_eq!(summary.mean_decision, Duration::from_secs(1));
+        assert_eq!(Summary::new(&[]).mean_decision, Duration::ZERO);
+    }
+}
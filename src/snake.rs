@@ -3,6 +3,34 @@ use crate::types::{CellState, Direction, GameState, Position};
 use rand::Rng;
 use std::collections::VecDeque;
 
+/// 一次 [`SnakeGame::update`] 对棋盘的改动
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BoardChange {
+    /// 未移动（等待输入或游戏结束）
+    None,
+    /// 普通移动：蛇尾 `tail` 释放，蛇头进入 `head`（原在 empty_cells 的 `head_empty_idx` 处）
+    Move {
+        head: usize,
+        head_empty_idx: usize,
+        tail: usize,
+    },
+    /// 吃食物：蛇头进入 `head`（原在 food_hashes 的 `food_idx` 处），
+    /// 随后在 empty_cells 的某个下标处生成新食物（若有空格且未胜利）
+    Eat {
+        head: usize,
+        food_idx: usize,
+        spawned: Option<(usize, usize)>,
+    },
+}
+
+/// 单个 tick 的完整增量，足以 O(1) 撤销该 tick（见 [`SnakeGame::revert`]）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickDelta {
+    /// tick 之前的方向
+    pub prev_direction: Option<Direction>,
+    pub change: BoardChange,
+}
+
 /// 贪吃蛇游戏的核心数据结构
 ///
 /// 所有逐帧操作均为 O(1) 时间复杂度：
@@ -11,11 +39,12 @@ use std::collections::VecDeque;
 /// - **碰撞检测**: map[hash] 直接查表
 /// - **食物生成**: 从 `empty_cells` 随机选一个再 swap-remove
 ///   （通过 `empty_indices` 反向查找实现 O(1) 移除）
-/// - **食物被吃**: 从 `food_hashes` swap-remove，蛇头进入蛇身
+/// - **食物被吃**: 从 `food_hashes` swap-remove
+///   （通过 `food_indices` 反向查找实现 O(1) 移除），蛇头进入蛇身
 ///
-/// 内存占用约 3×total_size×sizeof(usize) + total_size×1 字节。
-/// 1000×1000 地图约 25 MB。
-#[derive(Debug)]
+/// 内存占用约 4×total_size×sizeof(usize) + total_size×1 字节。
+/// 1000×1000 地图约 33 MB。
+#[derive(Debug, Clone)]
 pub struct SnakeGame {
     /// 地图配置（宽度、高度、哈希工具）
     config: MapConfig,
@@ -32,6 +61,9 @@ pub struct SnakeGame {
     empty_indices: Vec<usize>,
     /// 当前食物位置的 hash 列表
     food_hashes: Vec<usize>,
+    /// 反向查找表：hash → 在 food_hashes 中的索引
+    /// 值为 usize::MAX 表示该格子不是食物
+    food_indices: Vec<usize>,
 }
 
 impl SnakeGame {
@@ -72,6 +104,7 @@ impl SnakeGame {
             empty_cells: (0..total).collect(),
             empty_indices: (0..total).collect(),
             food_hashes: Vec::with_capacity(food_count),
+            food_indices: vec![usize::MAX; total],
         };
 
         // 放置蛇身
@@ -94,6 +127,63 @@ impl SnakeGame {
         game
     }
 
+    /// 由蛇身与食物列表重建完整状态（用于快照恢复）
+    ///
+    /// `body` 从尾到头，相邻段必须四邻相接；`foods` 不得与蛇身重叠。
+    /// `empty_cells` / `empty_indices` 按"先放蛇身、再放食物"的顺序重新生成，
+    /// 因此同一组参数总得到同一个空格顺序。
+    pub fn from_parts(
+        config: MapConfig,
+        direction: Option<Direction>,
+        body: &[usize],
+        foods: &[usize],
+    ) -> Result<Self, &'static str> {
+        if body.is_empty() {
+            return Err("蛇身为空");
+        }
+        let total = config.total_size();
+        let mut game = Self {
+            config,
+            direction,
+            map: vec![CellState::Empty; total],
+            snake_body: VecDeque::with_capacity(body.len() + 100),
+            empty_cells: (0..total).collect(),
+            empty_indices: (0..total).collect(),
+            food_hashes: Vec::with_capacity(foods.len()),
+            food_indices: vec![usize::MAX; total],
+        };
+
+        for (i, &h) in body.iter().enumerate() {
+            if h >= total {
+                return Err("蛇身越界");
+            }
+            if game.map[h] != CellState::Empty {
+                return Err("蛇身重叠");
+            }
+            if i > 0 {
+                let a = game.config.from_hash(body[i - 1]);
+                let b = game.config.from_hash(h);
+                if a.x.abs_diff(b.x) + a.y.abs_diff(b.y) != 1 {
+                    return Err("蛇身不连续");
+                }
+            }
+            game.advance_head(h);
+        }
+
+        for &f in foods {
+            if f >= total {
+                return Err("食物越界");
+            }
+            if game.map[f] != CellState::Empty {
+                return Err("食物与蛇身或其他食物重叠");
+            }
+            game.place_food(f);
+        }
+
+        debug_check_invariants(&game);
+        Ok(game)
+    }
+
     // ========================================================================
     // 访问器
     // ========================================================================
@@ -138,15 +228,22 @@ impl SnakeGame {
         &self.food_hashes
     }
 
+    /// 返回空格列表（顺序即随机生成食物时的抽样顺序）
+    pub fn empty_cells(&self) -> &[usize] {
+        &self.empty_cells
+    }
+
     // ========================================================================
     // 核心操作（均为 O(1)）
     // ========================================================================
 
-    /// 从空格列表中移除指定 hash（O(1) swap-remove）
-    fn remove_from_empty(&mut self, hash: usize) {
+    /// 从空格列表中移除指定 hash（O(1) swap-remove），返回其原下标
+    ///
+    /// 不在列表中时返回 usize::MAX。
+    fn remove_from_empty(&mut self, hash: usize) -> usize {
         let idx = self.empty_indices[hash];
         if idx == usize::MAX {
-            return; // 已经被移除
+            return idx; // 已经被移除
         }
         let last_idx = self.empty_cells.len() - 1;
         let last_hash = self.empty_cells[last_idx];
@@ -157,6 +254,21 @@ impl SnakeGame {
             self.empty_indices[last_hash] = idx;
         }
         self.empty_indices[hash] = usize::MAX;
+        idx
+    }
+
+    /// `remove_from_empty` 的逆操作：把 hash 放回下标 idx，原占位者移回末尾
+    fn restore_empty_at(&mut self, hash: usize, idx: usize) {
+        let len = self.empty_cells.len();
+        if idx == len {
+            self.empty_cells.push(hash);
+        } else {
+            let moved = self.empty_cells[idx];
+            self.empty_indices[moved] = len;
+            self.empty_cells.push(moved);
+            self.empty_cells[idx] = hash;
+        }
+        self.empty_indices[hash] = idx;
     }
 
     /// 将一个 hash 添加到空格列表中
@@ -165,41 +277,70 @@ impl SnakeGame {
         self.empty_cells.push(hash);
     }
 
-    /// 从空格列表中随机选一个位置生成食物（O(1)）
-    fn spawn_food(&mut self, rng: &mut impl Rng) {
+    /// 从空格列表中随机选一个位置生成食物（O(1)），返回 (hash, 在 empty_cells 中的原下标)
+    fn spawn_food(&mut self, rng: &mut impl Rng) -> Option<(usize, usize)> {
         if self.empty_cells.is_empty() {
-            return;
+            return None;
         }
         let idx = rand::Rng::random_range(rng, 0..self.empty_cells.len());
         let food_hash = self.empty_cells[idx];
+        self.place_food(food_hash);
+        Some((food_hash, idx))
+    }
+
+    /// 在指定空格放置食物
+    fn place_food(&mut self, food_hash: usize) {
         self.remove_from_empty(food_hash);
         self.map[food_hash] = CellState::Food;
+        self.food_indices[food_hash] = self.food_hashes.len();
         self.food_hashes.push(food_hash);
     }
 
-    /// 吃掉指定位置的食物：线性查找后 swap-remove
-    ///
-    /// 查找是 O(food_count)，对于典型食物数量（< 100）足够快。
-    /// 如需严格 O(1)，可额外维护 food_indices 反向查找表。
-    fn consume_food(&mut self, food_hash: usize) {
-        if let Some(idx) = self.food_hashes.iter().position(|&h| h == food_hash) {
-            self.food_hashes.swap_remove(idx);
+    /// 吃掉指定位置的食物（O(1) swap-remove，通过 food_indices 反向查找），返回其原下标
+    fn consume_food(&mut self, food_hash: usize) -> usize {
+        let idx = self.food_indices[food_hash];
+        if idx == usize::MAX {
+            return idx; // 不是食物
         }
+        let last_idx = self.food_hashes.len() - 1;
+        let last_hash = self.food_hashes[last_idx];
+        self.food_hashes.swap_remove(idx);
+        if idx != last_idx {
+            self.food_indices[last_hash] = idx;
+        }
+        self.food_indices[food_hash] = usize::MAX;
+        idx
+    }
+
+    /// `consume_food` 的逆操作：把食物放回下标 idx，原占位者移回末尾
+    fn restore_food_at(&mut self, food_hash: usize, idx: usize) {
+        let len = self.food_hashes.len();
+        if idx == len {
+            self.food_hashes.push(food_hash);
+        } else {
+            let moved = self.food_hashes[idx];
+            self.food_indices[moved] = len;
+            self.food_hashes.push(moved);
+            self.food_hashes[idx] = food_hash;
+        }
+        self.food_indices[food_hash] = idx;
+        self.map[food_hash] = CellState::Food;
     }
 
-    /// 蛇尾前进一步（正常移动时调用）
-    fn advance_tail(&mut self) {
-        if let Some(tail_hash) = self.snake_body.pop_front() {
-            self.map[tail_hash] = CellState::Empty;
-            self.add_to_empty(tail_hash);
-        }
+    /// 蛇尾前进一步（正常移动时调用），返回释放的格子
+    fn advance_tail(&mut self) -> usize {
+        let tail_hash = self.snake_body.pop_front().unwrap();
+        self.map[tail_hash] = CellState::Empty;
+        self.add_to_empty(tail_hash);
+        tail_hash
     }
 
-    /// 蛇头前进一步（移动和吃食时均调用）
-    fn advance_head(&mut self, head_hash: usize) {
-        self.remove_from_empty(head_hash);
+    /// 蛇头前进一步（移动和吃食时均调用），返回蛇头在 empty_cells 中的原下标
+    fn advance_head(&mut self, head_hash: usize) -> usize {
+        let idx = self.remove_from_empty(head_hash);
         self.map[head_hash] = CellState::Snake;
         self.snake_body.push_back(head_hash);
+        idx
     }
 
     /// 游戏更新的主逻辑
@@ -211,6 +352,20 @@ impl SnakeGame {
         direction: Option<Direction>,
         rng: &mut impl Rng,
     ) -> GameState {
+        self.update_recorded(direction, rng).0
+    }
+
+    /// 与 [`update`](Self::update) 相同，并返回可供 [`revert`](Self::revert) 撤销的增量
+    pub fn update_recorded(
+        &mut self,
+        direction: Option<Direction>,
+        rng: &mut impl Rng,
+    ) -> (GameState, TickDelta) {
+        let mut delta = TickDelta {
+            prev_direction: self.direction,
+            change: BoardChange::None,
+        };
+
         // 处理方向输入：更新方向，防止 180 度掉头
         if let Some(dir) = direction
             && self.direction.is_none_or(|d| dir != d.opposite())
@@ -219,7 +374,7 @@ impl SnakeGame {
         }
 
         let dir = match self.direction {
-            None => return GameState::Ready,
+            None => return (GameState::Ready, delta),
             Some(d) => d,
         };
 
@@ -237,7 +392,7 @@ impl SnakeGame {
             || new_y < 0
             || new_y >= self.config.height as i64
         {
-            return GameState::Over;
+            return (GameState::Over, delta);
         }
 
         let new_pos = Position {
@@ -247,116 +402,211 @@ impl SnakeGame {
         let new_hash = self.config.to_hash(new_pos);
 
         // 碰撞检测与处理
-        match self.map[new_hash] {
+        let state = match self.map[new_hash] {
             CellState::Empty => {
-                self.advance_tail();
-                self.advance_head(new_hash);
-                debug_check_invariants(self);
+                let tail = self.advance_tail();
+                let head_empty_idx = self.advance_head(new_hash);
+                delta.change = BoardChange::Move {
+                    head: new_hash,
+                    head_empty_idx,
+                    tail,
+                };
                 GameState::Running
             }
             CellState::Food => {
-                // 检查是否即将填满地图
-                if self.snake_body.len() >= self.config.total_size() - 1 {
-                    // 蛇吃掉最后一份食物后填满全图
-                    self.consume_food(new_hash);
-                    self.advance_head(new_hash);
-                    debug_check_invariants(self);
-                    return GameState::Won;
-                }
-
-                self.consume_food(new_hash);
+                let food_idx = self.consume_food(new_hash);
                 self.advance_head(new_hash);
-                // 补充食物以维持目标数量
-                self.spawn_food(rng);
-                debug_check_invariants(self);
-                GameState::Running
+                // 蛇吃掉最后一份食物后填满全图；否则补充食物以维持目标数量
+                let (state, spawned) = if self.snake_body.len() >= self.config.total_size() {
+                    (GameState::Won, None)
+                } else {
+                    (GameState::Running, self.spawn_food(rng))
+                };
+                delta.change = BoardChange::Eat {
+                    head: new_hash,
+                    food_idx,
+                    spawned,
+                };
+                state
             }
-            CellState::Snake => {
-                debug_check_invariants(self);
-                GameState::Over
+            CellState::Snake => GameState::Over,
+        };
+        debug_check_invariants(self);
+        (state, delta)
+    }
+
+    /// 撤销一个 tick（O(1)），恢复到该 tick 之前的精确状态
+    ///
+    /// 增量必须按与产生时相反的顺序撤销；
+    /// `map`、`snake_body`、`empty_cells`、`food_hashes` 及两张反向表都逐项还原。
+    pub fn revert(&mut self, delta: &TickDelta) {
+        match delta.change {
+            BoardChange::None => {}
+            BoardChange::Move {
+                head,
+                head_empty_idx,
+                tail,
+            } => {
+                // 逆序：先撤销蛇头，再撤销蛇尾（蛇尾此时位于 empty_cells 末尾）
+                self.snake_body.pop_back();
+                self.map[head] = CellState::Empty;
+                self.restore_empty_at(head, head_empty_idx);
+
+                self.empty_cells.pop();
+                self.empty_indices[tail] = usize::MAX;
+                self.map[tail] = CellState::Snake;
+                self.snake_body.push_front(tail);
+            }
+            BoardChange::Eat {
+                head,
+                food_idx,
+                spawned,
+            } => {
+                if let Some((food, empty_idx)) = spawned {
+                    self.food_hashes.pop();
+                    self.food_indices[food] = usize::MAX;
+                    self.map[food] = CellState::Empty;
+                    self.restore_empty_at(food, empty_idx);
+                }
+                self.snake_body.pop_back();
+                self.restore_food_at(head, food_idx);
             }
         }
+        self.direction = delta.prev_direction;
+        debug_check_invariants(self);
     }
 }
 
 // ============================================================================
-// Debug 不变式检查
+// 不变式检查
 // ============================================================================
 
-/// Debug 模式下的数据结构不变式检查
-///
-/// 仅在 debug_assertions 启用时编译，release 构建中完全移除。
-#[cfg(debug_assertions)]
-fn debug_check_invariants(game: &SnakeGame) {
-    let total = game.config.total_size();
-
-    // 1. map 中蛇身格数 == snake_body 长度
-    let snake_in_map = game.map.iter().filter(|&&c| c == CellState::Snake).count();
-    debug_assert_eq!(
-        snake_in_map,
-        game.snake_body.len(),
-        "map 蛇身格数 ({snake_in_map}) ≠ snake_body 长度 ({})",
-        game.snake_body.len(),
-    );
-
-    // 2. map 中食物格数 == food_hashes 长度
-    let food_in_map = game.map.iter().filter(|&&c| c == CellState::Food).count();
-    debug_assert_eq!(
-        food_in_map,
-        game.food_hashes.len(),
-        "map 食物格数 ({food_in_map}) ≠ food_hashes 长度 ({})",
-        game.food_hashes.len(),
-    );
-
-    // 3. snake_body 中的每个 hash 在 map 中都标记为 Snake
-    for &h in &game.snake_body {
-        debug_assert_eq!(
-            game.map[h],
-            CellState::Snake,
-            "蛇身 hash {h} 在 map 中为 {:?}，应为 Snake",
-            game.map[h],
+impl SnakeGame {
+    /// 检查全部数据结构不变式（供属性测试与模糊测试在任意构建下调用）
+    ///
+    /// # Panics
+    /// 任一不变式被破坏
+    pub fn check_invariants(&self) {
+        let total = self.config.total_size();
+
+        // 1. map 中蛇身格数 == snake_body 长度
+        let snake_in_map = self.map.iter().filter(|&&c| c == CellState::Snake).count();
+        assert_eq!(
+            snake_in_map,
+            self.snake_body.len(),
+            "map 蛇身格数 ({snake_in_map}) ≠ snake_body 长度 ({})",
+            self.snake_body.len(),
         );
-    }
 
-    // 4. food_hashes 中的每个 hash 在 map 中都标记为 Food
-    for &h in &game.food_hashes {
-        debug_assert_eq!(
-            game.map[h],
-            CellState::Food,
-            "食物 hash {h} 在 map 中为 {:?}，应为 Food",
-            game.map[h],
+        // 2. map 中食物格数 == food_hashes 长度
+        let food_in_map = self.map.iter().filter(|&&c| c == CellState::Food).count();
+        assert_eq!(
+            food_in_map,
+            self.food_hashes.len(),
+            "map 食物格数 ({food_in_map}) ≠ food_hashes 长度 ({})",
+            self.food_hashes.len(),
         );
-    }
 
-    // 5. empty_cells 中的每个 hash 在 map 中都标记为 Empty
-    for &h in &game.empty_cells {
-        debug_assert_eq!(
-            game.map[h],
-            CellState::Empty,
-            "空格 hash {h} 在 map 中为 {:?}，应为 Empty",
-            game.map[h],
+        // 3. snake_body 中的每个 hash 在 map 中都标记为 Snake
+        for &h in &self.snake_body {
+            assert_eq!(
+                self.map[h],
+                CellState::Snake,
+                "蛇身 hash {h} 在 map 中为 {:?}，应为 Snake",
+                self.map[h],
+            );
+        }
+
+        // 4. food_hashes 中的每个 hash 在 map 中都标记为 Food
+        for &h in &self.food_hashes {
+            assert_eq!(
+                self.map[h],
+                CellState::Food,
+                "食物 hash {h} 在 map 中为 {:?}，应为 Food",
+                self.map[h],
+            );
+        }
+
+        // 5. empty_cells 中的每个 hash 在 map 中都标记为 Empty
+        for &h in &self.empty_cells {
+            assert_eq!(
+                self.map[h],
+                CellState::Empty,
+                "空格 hash {h} 在 map 中为 {:?}，应为 Empty",
+                self.map[h],
+            );
+        }
+
+        // 6. empty_indices 与 empty_cells 互为反向映射
+        for (i, &h) in self.empty_cells.iter().enumerate() {
+            assert_eq!(
+                self.empty_indices[h], i,
+                "empty_indices[{h}] = {}，应为 {i}",
+                self.empty_indices[h],
+            );
+        }
+        let indexed_empty = self.empty_indices.iter().filter(|&&i| i != usize::MAX).count();
+        assert_eq!(
+            indexed_empty,
+            self.empty_cells.len(),
+            "empty_indices 有效项数 ({indexed_empty}) ≠ empty_cells 长度 ({})",
+            self.empty_cells.len(),
         );
-    }
 
-    // 6. 三种分类不重叠，且覆盖所有格子
-    let mut seen = vec![0u8; total];
-    for &h in &game.snake_body {
-        seen[h] += 1;
-    }
-    for &h in &game.food_hashes {
-        seen[h] += 1;
-    }
-    for &h in &game.empty_cells {
-        seen[h] += 1;
-    }
-    for (i, &count) in seen.iter().enumerate() {
-        debug_assert_eq!(
-            count, 1,
-            "hash {i} 被分类了 {count} 次（应为恰好 1 次：蛇身/食物/空格）"
+        // 7. food_indices 与 food_hashes 互为反向映射
+        for (i, &h) in self.food_hashes.iter().enumerate() {
+            assert_eq!(
+                self.food_indices[h], i,
+                "food_indices[{h}] = {}，应为 {i}",
+                self.food_indices[h],
+            );
+        }
+        let indexed_food = self.food_indices.iter().filter(|&&i| i != usize::MAX).count();
+        assert_eq!(
+            indexed_food,
+            self.food_hashes.len(),
+            "food_indices 有效项数 ({indexed_food}) ≠ food_hashes 长度 ({})",
+            self.food_hashes.len(),
         );
+
+        // 8. 三种分类不重叠，且覆盖所有格子
+        let mut seen = vec![0u8; total];
+        for &h in &self.snake_body {
+            seen[h] += 1;
+        }
+        for &h in &self.food_hashes {
+            seen[h] += 1;
+        }
+        for &h in &self.empty_cells {
+            seen[h] += 1;
+        }
+        for (i, &count) in seen.iter().enumerate() {
+            assert_eq!(
+                count, 1,
+                "hash {i} 被分类了 {count} 次（应为恰好 1 次：蛇身/食物/空格）"
+            );
+        }
+
+        // 9. 相邻蛇身段四邻相接
+        for (&a, &b) in self.snake_body.iter().zip(self.snake_body.iter().skip(1)) {
+            let (pa, pb) = (self.config.from_hash(a), self.config.from_hash(b));
+            assert_eq!(
+                pa.x.abs_diff(pb.x) + pa.y.abs_diff(pb.y),
+                1,
+                "蛇身段 {pa:?} 与 {pb:?} 不相邻",
+            );
+        }
     }
 }
 
+/// Debug 模式下的数据结构不变式检查
+///
+/// 仅在 debug_assertions 启用时编译，release 构建中完全移除。
+#[cfg(debug_assertions)]
+fn debug_check_invariants(game: &SnakeGame) {
+    game.check_invariants();
+}
+
 #[cfg(not(debug_assertions))]
 #[inline(always)]
 fn debug_check_invariants(_game: &SnakeGame) {}
@@ -591,6 +841,7 @@ mod tests {
             empty_cells: (0..total).collect(),
             empty_indices: (0..total).collect(),
             food_hashes: Vec::new(),
+            food_indices: vec![usize::MAX; total],
         };
         let mut rng = SmallRng::seed_from_u64(42);
         let state = game.update(None, &mut rng);
@@ -617,4 +868,103 @@ mod tests {
         assert_eq!(*game.snake_body.back().unwrap(), head_hash);
         assert_eq!(game.map[head_hash], CellState::Snake);
     }
+
+    #[test]
+    fn test_food_indices_stress_near_full() {
+        // 食物数接近 total_size：几乎每一步都吃食物，反复触发 swap-remove
+        let config = MapConfig::new(40, 40);
+        let total = config.total_size();
+        let mut rng = SmallRng::seed_from_u64(7);
+        let mut game = SnakeGame::new(config, 3, total - 10, &mut rng);
+        assert_eq!(game.food_count(), total - 10);
+
+        let cycle = [
+            Direction::Right,
+            Direction::Down,
+            Direction::Left,
+            Direction::Down,
+        ];
+        for i in 0..200 {
+            let state = game.update(Some(cycle[i % 4]), &mut rng);
+            if state != GameState::Running {
+                break;
+            }
+            // update 内的 debug_check_invariants 会校验 food_indices
+            for (idx, &f) in game.food_hashes.iter().enumerate() {
+                assert_eq!(game.food_indices[f], idx);
+            }
+        }
+        assert!(game.length() > 3, "满地食物时蛇应已增长");
+    }
+
+    #[test]
+    fn test_from_parts_rebuilds_tables() {
+        let (mut game, mut rng) = make_game(12, 12, 4, 5);
+        for dir in [Direction::Down, Direction::Down, Direction::Left, Direction::Left] {
+            game.update(Some(dir), &mut rng);
+        }
+        let body: Vec<usize> = game.snake_hashes().copied().collect();
+        let rebuilt = SnakeGame::from_parts(
+            game.config.clone(),
+            game.direction(),
+            &body,
+            game.food_hashes(),
+        )
+        .unwrap();
+        assert_eq!(rebuilt.map, game.map);
+        assert_eq!(rebuilt.snake_body, game.snake_body);
+        assert_eq!(rebuilt.food_hashes, game.food_hashes);
+        let mut empty = rebuilt.empty_cells.clone();
+        empty.sort();
+        let mut expected = game.empty_cells.clone();
+        expected.sort();
+        assert_eq!(empty, expected);
+    }
+
+    #[test]
+    fn test_from_parts_rejects_invalid() {
+        let cfg = MapConfig::new(5, 5);
+        let dir = Some(Direction::Right);
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[], &[]).is_err());
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[0, 25], &[]).is_err());
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[0, 1, 0], &[]).is_err());
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[0, 2], &[]).is_err());
+        // 4 与 5 hash 相邻但不在同一行
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[4, 5], &[]).is_err());
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[0, 1], &[1]).is_err());
+        assert!(SnakeGame::from_parts(cfg.clone(), dir, &[0, 1], &[7, 7]).is_err());
+        assert!(SnakeGame::from_parts(cfg, dir, &[0, 1, 6], &[7]).is_ok());
+    }
+
+    // ========================================================================
+    // 回退测试
+    // ========================================================================
+
+    #[test]
+    fn test_revert_restores_all_tables() {
+        // 小地图、多食物：覆盖移动、吃食、生成食物、胜利与死亡
+        for seed in 0..30 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut game = SnakeGame::new(MapConfig::new(4, 4), 2, 3, &mut rng);
+            for _ in 0..200 {
+                let dir = [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+                    [rng.random_range(0..4)];
+                let before = game.clone();
+                let mut tick_rng = rng.clone();
+                let (state, delta) = game.update_recorded(Some(dir), &mut tick_rng);
+                let after = game.clone();
+
+                game.revert(&delta);
+                assert_eq!(format!("{game:?}"), format!("{before:?}"), "seed={seed}");
+
+                // 重做得到同样结果
+                let (redo, _) = game.update_recorded(Some(dir), &mut rng);
+                assert_eq!(redo, state);
+                assert_eq!(format!("{game:?}"), format!("{after:?}"));
+                if state != GameState::Running {
+                    break;
+                }
+            }
+        }
+    }
 }
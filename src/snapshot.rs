@@ -0,0 +1,288 @@
+//! 游戏快照：紧凑的二进制存档格式
+//!
+//! 格式（整数均为 LEB128 变长编码，除魔数与版本外）：
+//!
+//! ```text
+//! "SNKS"  版本(u8)
+//! 宽  高  方向(0 上 / 1 下 / 2 左 / 3 右 / 4 无)  步数  RNG 状态
+//! 蛇长  蛇身 hash × 蛇长（从尾到头）
+//! 食物数  食物 hash × 食物数
+//! ```
+//!
+//! 宽、高均不超过 65535，且总格数不超过 [`MAX_CELLS`]，否则解码直接拒绝。
+//!
+//! 只保存无法推导的数据；`map`、`empty_cells` 等派生表在恢复时由
+//! [`SnakeGame::from_parts`] 重建。注意重建后的空格顺序与存档前不同，
+//! 所以恢复后的食物生成序列只由快照本身决定，而不会与未存档的原局面逐帧相同；
+//! 需要完全一致的分支请直接 `clone` 游戏。
+
+use crate::config::MapConfig;
+use crate::rng::GameRng;
+use crate::snake::SnakeGame;
+use crate::types::Direction;
+use std::fmt;
+
+/// 文件魔数
+pub const MAGIC: [u8; 4] = *b"SNKS";
+/// 当前格式版本
+pub const VERSION: u8 = 1;
+/// 可恢复的最大总格数；`SnakeGame` 为每格分配多张表，过大的尺寸会耗尽内存
+pub const MAX_CELLS: usize = 1 << 20;
+
+/// 快照解析错误
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SnapshotError {
+    /// 魔数不匹配
+    BadMagic,
+    /// 不支持的版本号
+    UnsupportedVersion(u8),
+    /// 数据提前结束
+    Truncated,
+    /// 数据结构不合法
+    Invalid(&'static str),
+}
+
+impl fmt::Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SnapshotError::BadMagic => write!(f, "不是快照文件"),
+            SnapshotError::UnsupportedVersion(v) => write!(f, "不支持的快照版本 {v}"),
+            SnapshotError::Truncated => write!(f, "快照数据不完整"),
+            SnapshotError::Invalid(reason) => write!(f, "快照数据不合法: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for SnapshotError {}
+
+/// 解码结果
+#[derive(Debug, Clone)]
+pub struct Snapshot {
+    pub game: SnakeGame,
+    pub moves_count: u64,
+    pub rng: GameRng,
+}
+
+const fn direction_code(dir: Option<Direction>) -> u64 {
+    match dir {
+        Some(Direction::Up) => 0,
+        Some(Direction::Down) => 1,
+        Some(Direction::Left) => 2,
+        Some(Direction::Right) => 3,
+        None => 4,
+    }
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+/// 编码快照
+pub fn encode(game: &SnakeGame, moves_count: u64, rng: &GameRng) -> Vec<u8> {
+    let config = game.config();
+    let mut out = Vec::with_capacity(32 + (game.length() + game.food_count()) * 2);
+    out.extend_from_slice(&MAGIC);
+    out.push(VERSION);
+    write_varint(&mut out, config.width as u64);
+    write_varint(&mut out, config.height as u64);
+    write_varint(&mut out, direction_code(game.direction()));
+    write_varint(&mut out, moves_count);
+    write_varint(&mut out, rng.state());
+    write_varint(&mut out, game.length() as u64);
+    for &h in game.snake_hashes() {
+        write_varint(&mut out, h as u64);
+    }
+    write_varint(&mut out, game.food_count() as u64);
+    for &h in game.food_hashes() {
+        write_varint(&mut out, h as u64);
+    }
+    out
+}
+
+/// 顺序读取器
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl Reader<'_> {
+    fn varint(&mut self) -> Result<u64, SnapshotError> {
+        let mut value = 0u64;
+        for shift in (0..64).step_by(7) {
+            let (&byte, rest) = self.bytes.split_first().ok_or(SnapshotError::Truncated)?;
+            self.bytes = rest;
+            value |= ((byte & 0x7F) as u64) << shift;
+            if byte & 0x80 == 0 {
+                return Ok(value);
+            }
+        }
+        Err(SnapshotError::Invalid("变长整数过长"))
+    }
+
+    /// 读取不超过 `max` 的计数或下标
+    fn bounded(&mut self, max: u64) -> Result<usize, SnapshotError> {
+        let value = self.varint()?;
+        if value > max {
+            return Err(SnapshotError::Invalid("数值超出范围"));
+        }
+        Ok(value as usize)
+    }
+
+    fn list(&mut self, total: usize) -> Result<Vec<usize>, SnapshotError> {
+        let len = self.bounded(total as u64)?;
+        (0..len).map(|_| self.bounded(total as u64 - 1)).collect()
+    }
+}
+
+/// 解码快照并重建游戏状态
+pub fn decode(bytes: &[u8]) -> Result<Snapshot, SnapshotError> {
+    let (magic, rest) = bytes.split_at_checked(4).ok_or(SnapshotError::Truncated)?;
+    if magic != MAGIC {
+        return Err(SnapshotError::BadMagic);
+    }
+    let (&version, rest) = rest.split_first().ok_or(SnapshotError::Truncated)?;
+    if version != VERSION {
+        return Err(SnapshotError::UnsupportedVersion(version));
+    }
+
+    let mut r = Reader { bytes: rest };
+    let width = r.bounded(u16::MAX as u64)? as u32;
+    let height = r.bounded(u16::MAX as u64)? as u32;
+    if width == 0 || height == 0 {
+        return Err(SnapshotError::Invalid("地图尺寸为 0"));
+    }
+    let total = width as usize * height as usize;
+    if total > MAX_CELLS {
+        return Err(SnapshotError::Invalid("地图过大"));
+    }
+    let direction = match r.varint()? {
+        0 => Some(Direction::Up),
+        1 => Some(Direction::Down),
+        2 => Some(Direction::Left),
+        3 => Some(Direction::Right),
+        4 => None,
+        _ => return Err(SnapshotError::Invalid("方向编码")),
+    };
+    let moves_count = r.varint()?;
+    let rng = GameRng::from_state(r.varint()?);
+    let body = r.list(total)?;
+    let foods = r.list(total)?;
+    if !r.bytes.is_empty() {
+        return Err(SnapshotError::Invalid("尾部有多余数据"));
+    }
+
+    let config = MapConfig::new(width, height);
+    let game = SnakeGame::from_parts(config, direction, &body, &foods)
+        .map_err(SnapshotError::Invalid)?;
+    Ok(Snapshot { game, moves_count, rng })
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::GameState;
+    use rand::SeedableRng;
+
+    fn played_game(seed: u64) -> (SnakeGame, GameRng) {
+        let mut rng = GameRng::seed_from_u64(seed);
+        let mut game = SnakeGame::new(MapConfig::new(20, 15), 4, 6, &mut rng);
+        for dir in [Direction::Up, Direction::Left, Direction::Left, Direction::Down] {
+            game.update(Some(dir), &mut rng);
+        }
+        (game, rng)
+    }
+
+    #[test]
+    fn test_roundtrip() {
+        let (game, rng) = played_game(3);
+        let bytes = encode(&game, 4, &rng);
+        let snap = decode(&bytes).unwrap();
+        assert_eq!(snap.moves_count, 4);
+        assert_eq!(snap.rng, rng);
+        assert_eq!(snap.game.direction(), game.direction());
+        assert!(snap.game.snake_hashes().eq(game.snake_hashes()));
+        assert_eq!(snap.game.food_hashes(), game.food_hashes());
+        for h in 0..game.config().total_size() {
+            assert_eq!(snap.game.cell_state(h), game.cell_state(h));
+        }
+        // 重新编码得到相同字节
+        assert_eq!(encode(&snap.game, snap.moves_count, &snap.rng), bytes);
+    }
+
+    #[test]
+    fn test_restored_games_replay_identically() {
+        // 同一快照恢复出的两局，后续完全一致
+        let (game, rng) = played_game(11);
+        let bytes = encode(&game, 0, &rng);
+        let mut a = decode(&bytes).unwrap();
+        let mut b = decode(&bytes).unwrap();
+        for i in 0..60 {
+            let dir = [Direction::Right, Direction::Down, Direction::Left, Direction::Down][i % 4];
+            let sa = a.game.update(Some(dir), &mut a.rng);
+            let sb = b.game.update(Some(dir), &mut b.rng);
+            assert_eq!(sa, sb);
+            assert_eq!(a.game.food_hashes(), b.game.food_hashes());
+            if sa != GameState::Running {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let (game, rng) = played_game(5);
+        let bytes = encode(&game, 0, &rng);
+
+        assert_eq!(decode(b"XXXX\x01").unwrap_err(), SnapshotError::BadMagic);
+        let mut future = bytes.clone();
+        future[4] = 99;
+        assert_eq!(decode(&future).unwrap_err(), SnapshotError::UnsupportedVersion(99));
+        for cut in 0..bytes.len() {
+            assert!(decode(&bytes[..cut]).is_err(), "截断到 {cut} 字节应失败");
+        }
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(matches!(decode(&trailing), Err(SnapshotError::Invalid(_))));
+
+        // 食物放到蛇头上
+        let head = *game.snake_hashes().last().unwrap();
+        let mut overlap = encode(&game, 0, &rng);
+        let n = overlap.len();
+        overlap.truncate(n - game.food_hashes().iter().map(|&h| varint_len(h as u64)).sum::<usize>() - 1);
+        write_varint(&mut overlap, 1);
+        write_varint(&mut overlap, head as u64);
+        assert!(matches!(decode(&overlap), Err(SnapshotError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_decode_rejects_huge_map() {
+        // 伪造的头部：尺寸合法但总格数巨大，必须在分配之前拒绝
+        let header = |width: u64, height: u64| {
+            let mut bytes = MAGIC.to_vec();
+            bytes.push(VERSION);
+            for value in [width, height, 4, 0, 0, 1, 0, 0] {
+                write_varint(&mut bytes, value);
+            }
+            bytes
+        };
+        let huge = decode(&header(u32::MAX as u64, u32::MAX as u64));
+        assert_eq!(huge.unwrap_err(), SnapshotError::Invalid("数值超出范围"));
+        let huge = decode(&header(u16::MAX as u64, u16::MAX as u64));
+        assert_eq!(huge.unwrap_err(), SnapshotError::Invalid("地图过大"));
+        let max_side = (MAX_CELLS as u64).isqrt();
+        assert!(decode(&header(max_side, max_side)).is_ok());
+    }
+
+    fn varint_len(value: u64) -> usize {
+        let mut out = Vec::new();
+        write_varint(&mut out, value);
+        out.len()
+    }
+}
@@ -0,0 +1,150 @@
+//! 局面的 SVG 快照（用于记录 AI 边界情况）
+//!
+//! 画布以格子为单位（`viewBox` 为 `宽 × 高`），`cell` 只决定输出的像素尺寸。
+//! 图层自下而上：背景与网格、可选的交规箭头、食物、蛇身折线（尾 → 头）、蛇头。
+//! 颜色与 [`image`](crate::image) 共用同一套 [`Palette`]。
+
+use crate::image::{Palette, Rgb};
+use crate::snake::SnakeGame;
+use crate::types::{Direction, Position};
+use std::fmt::Write;
+
+/// 网格线颜色
+const GRID: &str = "#3a3a48";
+/// 交规箭头颜色
+const TRAFFIC: &str = "#6080c0";
+
+/// 渲染局面为 SVG 文本
+///
+/// `traffic` 给出每格允许的两个方向（如 `ai` 特性下的 `TrafficRules::allowed`），
+/// 提供时在每格中心画出指向这两个方向的箭头。
+///
+/// # Panics
+/// `cell` 为 0
+pub fn render(
+    game: &SnakeGame,
+    cell: u32,
+    palette: &Palette,
+    traffic: Option<&dyn Fn(usize) -> [Direction; 2]>,
+) -> String {
+    assert!(cell > 0, "格子像素尺寸必须大于 0");
+    let config = game.config();
+    let (w, h) = (config.width, config.height);
+    let [background, body, head, food] = palette.0.map(hex);
+    let mut out = String::new();
+
+    let _ = writeln!(
+        out,
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {w} {h}\">",
+        w * cell,
+        h * cell
+    );
+    let _ = writeln!(out, "<rect width=\"{w}\" height=\"{h}\" fill=\"{background}\"/>");
+
+    // 网格
+    let mut grid = String::new();
+    for x in 0..=w {
+        let _ = write!(grid, "M{x} 0V{h}");
+    }
+    for y in 0..=h {
+        let _ = write!(grid, "M0 {y}H{w}");
+    }
+    let _ = writeln!(out, "<path d=\"{grid}\" stroke=\"{GRID}\" stroke-width=\"0.04\"/>");
+
+    // 交规箭头
+    if let Some(traffic) = traffic {
+        let _ = writeln!(
+            out,
+            "<defs><marker id=\"arrow\" viewBox=\"0 0 10 10\" refX=\"8\" refY=\"5\" markerWidth=\"4\" markerHeight=\"4\" orient=\"auto\"><path d=\"M0 0L10 5L0 10z\" fill=\"{TRAFFIC}\"/></marker></defs>"
+        );
+        let _ = writeln!(
+            out,
+            "<g class=\"traffic\" stroke=\"{TRAFFIC}\" stroke-width=\"0.06\" marker-end=\"url(#arrow)\">"
+        );
+        for hash in 0..config.total_size() {
+            let (cx, cy) = center(config.from_hash(hash));
+            for dir in traffic(hash) {
+                let (dx, dy) = dir.delta();
+                let _ = writeln!(
+                    out,
+                    "<line x1=\"{cx}\" y1=\"{cy}\" x2=\"{}\" y2=\"{}\"/>",
+                    cx + 0.35 * dx as f32,
+                    cy + 0.35 * dy as f32
+                );
+            }
+        }
+        out.push_str("</g>\n");
+    }
+
+    // 食物
+    for &hash in game.food_hashes() {
+        let (cx, cy) = center(config.from_hash(hash));
+        let _ = writeln!(out, "<circle class=\"food\" cx=\"{cx}\" cy=\"{cy}\" r=\"0.3\" fill=\"{food}\"/>");
+    }
+
+    // 蛇身：沿 snake_hashes() 从尾到头连成折线
+    let mut points = String::new();
+    for &hash in game.snake_hashes() {
+        let (cx, cy) = center(config.from_hash(hash));
+        let _ = write!(points, "{}{cx},{cy}", if points.is_empty() { "" } else { " " });
+    }
+    let _ = writeln!(
+        out,
+        "<polyline class=\"body\" points=\"{points}\" fill=\"none\" stroke=\"{body}\" stroke-width=\"0.6\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>"
+    );
+    if let Some(pos) = game.head_position() {
+        let (cx, cy) = center(pos);
+        let _ = writeln!(out, "<circle class=\"head\" cx=\"{cx}\" cy=\"{cy}\" r=\"0.4\" fill=\"{head}\"/>");
+    }
+
+    out.push_str("</svg>\n");
+    out
+}
+
+/// 格子中心（以格子为单位）
+fn center(pos: Position) -> (f32, f32) {
+    (pos.x as f32 + 0.5, pos.y as f32 + 0.5)
+}
+
+/// `#rrggbb`
+fn hex([r, g, b]: Rgb) -> String {
+    format!("#{r:02x}{g:02x}{b:02x}")
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MapConfig;
+    use crate::game::Game;
+
+    #[test]
+    fn test_body_polyline_follows_tail_to_head() {
+        let mut game = Game::with_seed(MapConfig::new(8, 4), 3, 2, 5);
+        game.tick(Some(Direction::Down));
+        let svg = render(game.snake(), 10, &Palette::default(), None);
+        assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"80\" height=\"40\" viewBox=\"0 0 8 4\">"));
+        assert!(svg.ends_with("</svg>\n"));
+        // 初始蛇 (2,2)(3,2)(4,2) 向右，再向下一步：尾弹出、头进入 (4,3)
+        assert!(svg.contains("points=\"3.5,2.5 4.5,2.5 4.5,3.5\""), "{svg}");
+        assert!(svg.contains("class=\"head\" cx=\"4.5\" cy=\"3.5\""));
+        assert_eq!(svg.matches("class=\"food\"").count(), 2);
+        assert!(!svg.contains("traffic"));
+    }
+
+    #[test]
+    fn test_traffic_overlay() {
+        let game = Game::with_seed(MapConfig::new(4, 2), 2, 1, 1);
+        let rules = |hash: usize| {
+            if hash == 0 { [Direction::Right, Direction::Up] } else { [Direction::Left, Direction::Down] }
+        };
+        let svg = render(game.snake(), 8, &Palette::default(), Some(&rules));
+        assert_eq!(svg.matches("<line ").count(), 2 * 8);
+        // 格子 0 的中心 (0.5, 0.5)，向右、向上各画一支箭头
+        assert!(svg.contains("<line x1=\"0.5\" y1=\"0.5\" x2=\"0.85\" y2=\"0.5\"/>"));
+        assert!(svg.contains("<line x1=\"0.5\" y1=\"0.5\" x2=\"0.5\" y2=\"0.15\"/>"));
+    }
+}
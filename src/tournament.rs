@@ -0,0 +1,393 @@
+//! 并行自对弈锦标赛：多个 AI 策略在同一批种子棋盘上各跑一局，比较胜率、取胜步数与决策延迟
+//!
+//! 任务（策略 × 种子）由 `std::thread` 工作线程从共享计数器领取，结果按（策略, 种子）归位，
+//! 因此除延迟外的全部统计与线程数、调度顺序无关。[`report`] 把这部分写在前面，
+//! 同一组参数重跑得到逐字节相同的内容；随机器而变的延迟分位数单独放在最后一节。
+//! 例外是设置了 [`AiConfig::deadline`] 的参赛者：搜索在哪里停下取决于墙钟，其对局本身就随机器而变。
+
+use crate::mcts::MctsConfig;
+use crate::pathfinding::{AiConfig, Heuristic, Strategy, TieBreak};
+use crate::sim::{self, GameResult, LatencyHistogram, SimOptions};
+use crate::types::GameState;
+use std::fmt::Write;
+use std::ops::Range;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+/// 参赛的 AI
+#[derive(Debug, Clone, PartialEq)]
+pub enum Contestant {
+    AStar(AiConfig),
+    Mcts(MctsConfig),
+}
+
+impl Contestant {
+    /// 报告中使用的名字
+    ///
+    /// A* 参赛者以策略为名，与 [`AiConfig::default`] 不同的搜索参数依次追加为后缀，
+    /// 如 `greedy-n500-20ms-manhattan-shallow`；MCTS 以迭代次数为名，
+    /// 与 [`MctsConfig::default`] 不同的参数同样追加为后缀，如 `mcts-200-d8-c1.4-g0.9-s3`。
+    pub fn name(&self) -> String {
+        match self {
+            Contestant::AStar(ai) => {
+                let mut name = match ai.strategy {
+                    Strategy::Greedy => String::from("greedy"),
+                    Strategy::SafeTail => String::from("safe-tail"),
+                };
+                let default = AiConfig::default();
+                if ai.max_expanded != default.max_expanded {
+                    let _ = write!(name, "-n{}", ai.max_expanded);
+                }
+                if let Some(deadline) = ai.deadline {
+                    let _ = write!(name, "-{}ms", deadline.as_millis());
+                }
+                if ai.heuristic != default.heuristic {
+                    name.push_str(match ai.heuristic {
+                        Heuristic::Traffic => "-traffic",
+                        Heuristic::Manhattan => "-manhattan",
+                    });
+                }
+                if ai.tie_break != default.tie_break {
+                    name.push_str(match ai.tie_break {
+                        TieBreak::Deepest => "-deep",
+                        TieBreak::Shallowest => "-shallow",
+                    });
+                }
+                name
+            }
+            Contestant::Mcts(config) => {
+                let mut name = format!("mcts-{}", config.iterations);
+                let default = MctsConfig::default();
+                if config.rollout_depth != default.rollout_depth {
+                    let _ = write!(name, "-d{}", config.rollout_depth);
+                }
+                if config.exploration != default.exploration {
+                    let _ = write!(name, "-c{}", config.exploration);
+                }
+                if config.discount != default.discount {
+                    let _ = write!(name, "-g{}", config.discount);
+                }
+                if config.seed != default.seed {
+                    let _ = write!(name, "-s{}", config.seed);
+                }
+                name
+            }
+        }
+    }
+
+    /// 以种子 `seed` 跑一局
+    pub fn play(&self, options: &SimOptions, seed: u64) -> GameResult {
+        match self {
+            Contestant::AStar(ai) => sim::play(ai, options, seed),
+            Contestant::Mcts(config) => sim::play_mcts(config, options, seed),
+        }
+    }
+}
+
+/// 锦标赛参数
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TournamentOptions {
+    pub sim: SimOptions,
+    pub seeds: Range<u64>,
+    /// 工作线程数（至少为 1）
+    pub threads: usize,
+}
+
+impl Default for TournamentOptions {
+    fn default() -> Self {
+        TournamentOptions {
+            sim: SimOptions::default(),
+            seeds: 0..100,
+            threads: std::thread::available_parallelism().map_or(1, |n| n.get()),
+        }
+    }
+}
+
+/// 一个策略的全部对局
+#[derive(Debug, Clone, PartialEq)]
+pub struct Standing {
+    pub name: String,
+    /// 按种子升序
+    pub results: Vec<GameResult>,
+}
+
+impl Standing {
+    pub fn wins(&self) -> usize {
+        self.results.iter().filter(|r| r.state == GameState::Won).count()
+    }
+
+    pub fn win_rate(&self) -> f64 {
+        self.wins() as f64 / self.results.len().max(1) as f64
+    }
+
+    /// 获胜对局所用步数的 (平均, 中位数)；没有获胜时为 None
+    pub fn moves_to_win(&self) -> Option<(f64, u64)> {
+        let mut moves: Vec<u64> = self
+            .results
+            .iter()
+            .filter(|r| r.state == GameState::Won)
+            .map(|r| r.moves)
+            .collect();
+        if moves.is_empty() {
+            return None;
+        }
+        moves.sort_unstable();
+        let mean = moves.iter().sum::<u64>() as f64 / moves.len() as f64;
+        Some((mean, moves[(moves.len() - 1) / 2]))
+    }
+
+    pub fn mean_length(&self) -> f64 {
+        self.results.iter().map(|r| r.length).sum::<usize>() as f64 / self.results.len().max(1) as f64
+    }
+
+    /// 全部对局的决策耗时分布
+    pub fn latency(&self) -> LatencyHistogram {
+        let mut all = LatencyHistogram::default();
+        for r in &self.results {
+            all.merge(&r.latency);
+        }
+        all
+    }
+}
+
+/// 同一种子上 `a` 是否胜过 `b`：赢的胜过没赢的；都赢比步数少；都没赢比长度，再比存活步数
+fn beats(a: &GameResult, b: &GameResult) -> bool {
+    let won = |r: &GameResult| r.state == GameState::Won;
+    match (won(a), won(b)) {
+        (true, false) => true,
+        (false, true) => false,
+        (true, true) => a.moves < b.moves,
+        (false, false) => (a.length, a.moves) > (b.length, b.moves),
+    }
+}
+
+/// 让每个策略在 `options.seeds` 的每个种子上各跑一局
+///
+/// # Panics
+/// - `options.threads` 为 0
+/// - 两个参赛者的 [`name`](Contestant::name) 相同（报告无法区分）
+pub fn run(contestants: &[Contestant], options: &TournamentOptions) -> Vec<Standing> {
+    assert!(options.threads > 0, "至少需要一个工作线程");
+    for (i, c) in contestants.iter().enumerate() {
+        let name = c.name();
+        assert!(
+            contestants[..i].iter().all(|other| other.name() != name),
+            "参赛者重名: {name}"
+        );
+    }
+    let seeds = options.seeds.clone();
+    let per = (seeds.end - seeds.start) as usize;
+    let jobs = contestants.len() * per;
+    let next = AtomicUsize::new(0);
+    let slots: Mutex<Vec<Option<GameResult>>> = Mutex::new(vec![None; jobs]);
+
+    std::thread::scope(|scope| {
+        for _ in 0..options.threads.min(jobs.max(1)) {
+            scope.spawn(|| {
+                loop {
+                    // 按种子交错领取，各策略的进度大致同步
+                    let job = next.fetch_add(1, Ordering::Relaxed);
+                    if job >= jobs {
+                        break;
+                    }
+                    let (c, s) = (job % contestants.len(), job / contestants.len());
+                    let result = contestants[c].play(&options.sim, seeds.start + s as u64);
+                    slots.lock().unwrap()[c * per + s] = Some(result);
+                }
+            });
+        }
+    });
+
+    let mut slots = slots.into_inner().unwrap().into_iter().map(|r| r.expect("每个任务都已完成"));
+    contestants
+        .iter()
+        .map(|c| Standing { name: c.name(), results: slots.by_ref().take(per).collect() })
+        .collect()
+}
+
+/// 生成文本报告
+///
+/// 延迟分位数是 `## latency` 之后的最后一节；其之前的内容只取决于参数与策略，可逐字节复现，
+/// 但设置了 [`AiConfig::deadline`] 的参赛者除外（名字带 `-<毫秒>ms` 后缀），它们的对局取决于机器速度。
+pub fn report(standings: &[Standing], options: &TournamentOptions) -> String {
+    let sim = &options.sim;
+    let names: Vec<&str> = standings.iter().map(|s| s.name.as_str()).collect();
+    let mut out = String::new();
+    let _ = writeln!(out, "# snake tournament");
+    let _ = writeln!(
+        out,
+        "map {}x{}  initial length {}  food {}  max ticks {}",
+        sim.map.width, sim.map.height, sim.initial_length, sim.food_count, sim.max_ticks
+    );
+    let _ = writeln!(out, "seeds {}..{}  contestants {}", options.seeds.start, options.seeds.end, names.join(", "));
+
+    let _ = writeln!(out, "\n## standings");
+    let _ = writeln!(
+        out,
+        "{:<12} {:>6} {:>6} {:>7} {:>11} {:>11} {:>11}",
+        "strategy", "games", "won", "win%", "win moves", "median", "mean len"
+    );
+    for s in standings {
+        let (mean, median) = match s.moves_to_win() {
+            Some((mean, median)) => (format!("{mean:.1}"), median.to_string()),
+            None => ("-".into(), "-".into()),
+        };
+        let _ = writeln!(
+            out,
+            "{:<12} {:>6} {:>6} {:>6.1}% {:>11} {:>11} {:>11.1}",
+            s.name,
+            s.results.len(),
+            s.wins(),
+            s.win_rate() * 100.0,
+            mean,
+            median,
+            s.mean_length()
+        );
+    }
+
+    let _ = writeln!(out, "\n## head-to-head (seeds where row beats column)");
+    let _ = write!(out, "{:<12}", "");
+    for name in &names {
+        let _ = write!(out, " {name:>12}");
+    }
+    out.push('\n');
+    for a in standings {
+        let _ = write!(out, "{:<12}", a.name);
+        for b in standings {
+            let wins = a.results.iter().zip(&b.results).filter(|(x, y)| beats(x, y)).count();
+            let _ = write!(out, " {wins:>12}");
+        }
+        out.push('\n');
+    }
+
+    let _ = writeln!(out, "\n## games (strategy seed state moves length)");
+    for s in standings {
+        for r in &s.results {
+            let _ = writeln!(out, "{} {} {:?} {} {}", s.name, r.seed, r.state, r.moves, r.length);
+        }
+    }
+
+    let _ = writeln!(out, "\n## latency (machine-dependent)");
+    let _ = writeln!(
+        out,
+        "{:<12} {:>10} {:>10} {:>10} {:>10} {:>10}",
+        "strategy", "decisions", "p50 ms", "p90 ms", "p99 ms", "max ms"
+    );
+    let ms = |d: Duration| d.as_secs_f64() * 1000.0;
+    for s in standings {
+        let latency = s.latency();
+        let max = s.results.iter().map(|r| r.max_decision).max().unwrap_or_default();
+        let _ = writeln!(
+            out,
+            "{:<12} {:>10} {:>10.3} {:>10.3} {:>10.3} {:>10.3}",
+            s.name,
+            latency.count(),
+            ms(latency.percentile(0.5)),
+            ms(latency.percentile(0.9)),
+            ms(latency.percentile(0.99)),
+            ms(max)
+        );
+    }
+    out
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::MapConfig;
+
+    #[test]
+    fn test_report_is_independent_of_threads() {
+        let contestants = [
+            Contestant::AStar(AiConfig::default()),
+            Contestant::AStar(AiConfig { strategy: Strategy::SafeTail, ..AiConfig::default() }),
+            Contestant::Mcts(MctsConfig { iterations: 20, rollout_depth: 8, ..MctsConfig::default() }),
+        ];
+        let mut options = TournamentOptions {
+            sim: SimOptions { map: MapConfig::new(16, 16), max_ticks: 60, ..SimOptions::default() },
+            seeds: 3..7,
+            threads: 1,
+        };
+        let serial = run(&contestants, &options);
+        options.threads = 3;
+        let parallel = run(&contestants, &options);
+
+        let deterministic = |standings: &[Standing]| {
+            let report = report(standings, &options);
+            report.split("## latency").next().unwrap().to_string()
+        };
+        assert_eq!(deterministic(&serial), deterministic(&parallel));
+        assert_eq!(serial[2].name, "mcts-20-d8");
+        for s in &parallel {
+            assert_eq!(s.results.iter().map(|r| r.seed).collect::<Vec<_>>(), [3, 4, 5, 6]);
+            assert_eq!(s.latency().count(), s.results.iter().map(|r| r.decisions).sum::<u64>());
+        }
+        let report = report(&parallel, &options);
+        assert!(report.contains("seeds 3..7  contestants greedy, safe-tail, mcts-20-d8"), "{report}");
+        let games = report.lines().filter(|l| l.starts_with("greedy ") && l.split(' ').count() == 5);
+        assert_eq!(games.count(), 4);
+    }
+
+    #[test]
+    fn test_names_distinguish_search_parameters() {
+        let greedy = AiConfig::default();
+        let tuned = AiConfig {
+            max_expanded: 500,
+            deadline: Some(Duration::from_millis(20)),
+            heuristic: Heuristic::Manhattan,
+            tie_break: TieBreak::Shallowest,
+            strategy: Strategy::SafeTail,
+        };
+        assert_eq!(Contestant::AStar(greedy).name(), "greedy");
+        assert_eq!(Contestant::AStar(tuned).name(), "safe-tail-n500-20ms-manhattan-shallow");
+        let manhattan = AiConfig { heuristic: Heuristic::Manhattan, ..greedy };
+        assert_ne!(Contestant::AStar(manhattan).name(), Contestant::AStar(greedy).name());
+    }
+
+    #[test]
+    fn test_mcts_names_distinguish_parameters() {
+        let base = MctsConfig { iterations: 200, ..MctsConfig::default() };
+        assert_eq!(Contestant::Mcts(base).name(), "mcts-200");
+        let tuned = MctsConfig { rollout_depth: 8, exploration: 1.4, discount: 0.9, seed: 3, ..base };
+        assert_eq!(Contestant::Mcts(tuned).name(), "mcts-200-d8-c1.4-g0.9-s3");
+        let reseeded = Contestant::Mcts(MctsConfig { seed: 1, ..base });
+        assert_ne!(reseeded.name(), Contestant::Mcts(base).name());
+    }
+
+    #[test]
+    #[should_panic(expected = "参赛者重名")]
+    fn test_run_rejects_duplicate_names() {
+        let greedy = Contestant::AStar(AiConfig::default());
+        let options = TournamentOptions { seeds: 0..1, threads: 1, ..TournamentOptions::default() };
+        run(&[greedy.clone(), greedy], &options);
+    }
+
+    #[test]
+    fn test_beats() {
+        let result = |state, moves, length| GameResult {
+            seed: 0,
+            state,
+            moves,
+            length,
+            decisions: 0,
+            expanded: 0,
+            budget_hits: 0,
+            decision_time: Duration::ZERO,
+            max_decision: Duration::ZERO,
+            latency: LatencyHistogram::default(),
+        };
+        let won_fast = result(GameState::Won, 100, 256);
+        let won_slow = result(GameState::Won, 200, 256);
+        let died_long = result(GameState::Over, 300, 50);
+        let died_short = result(GameState::Over, 400, 20);
+        assert!(beats(&won_slow, &died_long));
+        assert!(beats(&won_fast, &won_slow) && !beats(&won_slow, &won_fast));
+        assert!(beats(&died_long, &died_short));
+        assert!(!beats(&won_fast, &won_fast));
+    }
+}
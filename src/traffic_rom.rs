@@ -0,0 +1,192 @@
+//! 交规 ROM 表生成器
+//!
+//! 把 [`pathfinding`](crate::pathfinding) 的交规（按坐标奇偶性现算）与四邻接预计算成只读表，
+//! 供红石计算机直接查表。ROM 分为 5 页，每页 256 字节，页内偏移即格子 hash：
+//!
+//! | 页 | 内容 |
+//! |----|------|
+//! | 0  | `TRAFFIC`：bit0–1 第一个允许方向、bit2–3 第二个允许方向、bit4–7 各方向是否在界内 |
+//! | 1–4 | `NEIGHBOR[d]`：向方向 d 走一步后的 hash（出界时为 0，以 bit4–7 为准） |
+//!
+//! 方向按 [`encode_direction`] 编码（Up=0, Down=1, Left=2, Right=3）。
+//! 由于 256 格地图的 hash 占满 8 位，越界只能用单独的标志位表示，不能用哨兵值。
+
+use crate::config::MapConfig;
+use crate::pathfinding::TrafficRules;
+use crate::redstone::isa::PAGE_SIZE;
+use crate::redstone::layout::{decode_direction, encode_direction};
+use crate::types::Direction;
+use std::fmt::Write;
+
+/// 交规页
+pub const TRAFFIC: usize = 0;
+/// 邻接表首页（方向编码 d 对应第 `NEIGHBOR + d` 页）
+pub const NEIGHBOR: usize = 1;
+/// ROM 总页数
+pub const ROM_PAGES: usize = 5;
+
+/// 交规与邻接的 ROM 映像
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrafficRom {
+    total_size: usize,
+    bytes: Vec<u8>,
+}
+
+/// 方向的 2 位编码
+fn code(dir: Direction) -> u8 {
+    encode_direction(Some(dir))
+}
+
+impl TrafficRom {
+    /// 为给定地图生成 ROM
+    ///
+    /// # Panics
+    /// 地图总格数超过 256（hash 无法用 8 位表示）
+    pub fn generate(config: &MapConfig) -> Self {
+        let total = config.total_size();
+        assert!(total <= PAGE_SIZE, "交规 ROM 要求总格数 ≤ {PAGE_SIZE}（当前 {total}）");
+
+        let mut bytes = vec![0u8; ROM_PAGES * PAGE_SIZE];
+        for hash in 0..total {
+            let [a, b] = config.allowed(hash);
+            let mut entry = code(a) | code(b) << 2;
+            for dir in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+                if let Some(next) = config.neighbor(hash, dir) {
+                    entry |= 1 << (4 + code(dir));
+                    bytes[(NEIGHBOR + code(dir) as usize) * PAGE_SIZE + hash] = next as u8;
+                }
+            }
+            bytes[TRAFFIC * PAGE_SIZE + hash] = entry;
+        }
+        Self { total_size: total, bytes }
+    }
+
+    /// 从二进制映像恢复（例如读回烧录内容做校验）
+    ///
+    /// 长度不等于 `ROM_PAGES * PAGE_SIZE` 或 `total_size` 超过 256 时返回 None。
+    pub fn from_bytes(total_size: usize, bytes: Vec<u8>) -> Option<Self> {
+        (bytes.len() == ROM_PAGES * PAGE_SIZE && total_size <= PAGE_SIZE)
+            .then_some(Self { total_size, bytes })
+    }
+
+    /// 二进制映像（各页依次拼接，可直接交给 `redstone::export`）
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Intel HEX 格式的映像（每条记录 16 字节）
+    pub fn to_intel_hex(&self) -> String {
+        let mut out = String::with_capacity(self.bytes.len() * 3);
+        for (i, chunk) in self.bytes.chunks(16).enumerate() {
+            let addr = (i * 16) as u16;
+            let mut record = vec![chunk.len() as u8, (addr >> 8) as u8, addr as u8, 0x00];
+            record.extend_from_slice(chunk);
+            let checksum = record.iter().fold(0u8, |acc, &b| acc.wrapping_add(b)).wrapping_neg();
+            record.push(checksum);
+            out.push(':');
+            for b in record {
+                let _ = write!(out, "{b:02X}");
+            }
+            out.push('\n');
+        }
+        out.push_str(":00000001FF\n");
+        out
+    }
+
+    #[inline]
+    fn entry(&self, hash: usize) -> u8 {
+        self.bytes[TRAFFIC * PAGE_SIZE + hash]
+    }
+}
+
+impl TrafficRules for TrafficRom {
+    #[inline]
+    fn total_size(&self) -> usize {
+        self.total_size
+    }
+
+    #[inline]
+    fn allowed(&self, hash: usize) -> [Direction; 2] {
+        let entry = self.entry(hash);
+        // 2 位编码总能解码出方向
+        let decode = |bits: u8| decode_direction(bits & 0b11).unwrap();
+        [decode(entry), decode(entry >> 2)]
+    }
+
+    #[inline]
+    fn neighbor(&self, hash: usize, dir: Direction) -> Option<usize> {
+        let d = code(dir);
+        (self.entry(hash) & 1 << (4 + d) != 0)
+            .then(|| self.bytes[(NEIGHBOR + d as usize) * PAGE_SIZE + hash] as usize)
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::{next_dir, next_dir_rom};
+    use crate::snake::SnakeGame;
+    use crate::types::GameState;
+    use rand::SeedableRng;
+    use rand::rngs::SmallRng;
+
+    #[test]
+    fn test_rom_matches_arithmetic_rules() {
+        for (w, h) in [(16, 16), (10, 10), (7, 3), (1, 1), (256, 1)] {
+            let cfg = MapConfig::new(w, h);
+            let rom = TrafficRom::generate(&cfg);
+            for hash in 0..cfg.total_size() {
+                assert_eq!(rom.allowed(hash), cfg.allowed(hash), "{w}×{h} hash {hash}");
+                for dir in [Direction::Up, Direction::Down, Direction::Left, Direction::Right] {
+                    assert_eq!(rom.neighbor(hash, dir), cfg.neighbor(hash, dir));
+                }
+                let (mut from_rom, mut from_cfg) = (Vec::new(), Vec::new());
+                rom.for_each_neighbor(hash, |n| from_rom.push(n));
+                cfg.for_each_neighbor(hash, |n| from_cfg.push(n));
+                assert_eq!(from_rom, from_cfg, "{w}×{h} hash {hash}: 四邻顺序");
+            }
+        }
+    }
+
+    #[test]
+    fn test_rom_bytes_roundtrip_and_hex() {
+        let rom = TrafficRom::generate(&MapConfig::new(16, 16));
+        let copy = TrafficRom::from_bytes(256, rom.as_bytes().to_vec()).unwrap();
+        assert_eq!(copy, rom);
+        assert!(TrafficRom::from_bytes(256, vec![0; 3]).is_none());
+
+        let hex = rom.to_intel_hex();
+        let lines: Vec<&str> = hex.lines().collect();
+        assert_eq!(lines.len(), ROM_PAGES * PAGE_SIZE / 16 + 1);
+        assert_eq!(*lines.last().unwrap(), ":00000001FF");
+        // 每条记录的字节和（含校验和）为 0
+        for line in &lines {
+            let sum = (1..line.len())
+                .step_by(2)
+                .map(|i| u8::from_str_radix(&line[i..i + 2], 16).unwrap())
+                .fold(0u8, |acc, b| acc.wrapping_add(b));
+            assert_eq!(sum, 0, "{line}");
+        }
+    }
+
+    #[test]
+    fn test_rom_ai_equals_arithmetic_ai() {
+        let cfg = MapConfig::new(16, 16);
+        let rom = TrafficRom::generate(&cfg);
+        for seed in 0..4 {
+            let mut rng = SmallRng::seed_from_u64(seed);
+            let mut game = SnakeGame::new(cfg.clone(), 3, 3, &mut rng);
+            for step in 0..150 {
+                let dir = next_dir(&game);
+                assert_eq!(next_dir_rom(&game, &rom), dir, "seed={seed} step={step}");
+                if game.update(dir, &mut rng) != GameState::Running {
+                    break;
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,83 @@
+//! 外部 bot 协议的端到端测试：以 `greedy_bot` 二进制作为子进程
+
+use snake::bot::{self, BotProcess, BotReply};
+use snake::config::MapConfig;
+use snake::game::Game;
+use snake::types::GameState;
+use std::process::Command;
+use std::time::Duration;
+
+fn stand_in(args: &[&str]) -> BotProcess {
+    BotProcess::spawn(Command::new(env!("CARGO_BIN_EXE_greedy_bot")).args(args)).unwrap()
+}
+
+#[test]
+fn greedy_bot_plays_a_game() {
+    let mut game = Game::with_seed(MapConfig::new(12, 12), 3, 2, 7);
+    let mut process = stand_in(&[]);
+    let report = bot::run(&mut game, &mut process, Duration::from_secs(2), 300);
+    assert!(!report.exited);
+    assert_eq!(report.timeouts, 0);
+    assert_eq!(report.invalid_replies, 0);
+    assert!(report.length > 3, "贪心 bot 应至少吃到一份食物: {report:?}");
+    assert_eq!(report.moves, game.moves_count());
+}
+
+#[test]
+fn run_with_observes_every_tick() {
+    let mut game = Game::with_seed(MapConfig::new(12, 12), 3, 2, 7);
+    let mut process = stand_in(&[]);
+    let mut seen = Vec::new();
+    let report = bot::run_with(&mut game, &mut process, Duration::from_secs(2), 20, |game| {
+        seen.push(game.moves_count());
+    });
+    assert_eq!(seen, (1..=report.moves).collect::<Vec<_>>());
+}
+
+#[test]
+fn slow_bot_times_out() {
+    let mut game = Game::with_seed(MapConfig::new(12, 12), 3, 2, 7);
+    let mut process = stand_in(&["--delay", "200"]);
+    let report = bot::run(&mut game, &mut process, Duration::from_millis(20), 3);
+    assert_eq!(report.timeouts, 3);
+    // 超时按保持方向处理，蛇照常前进
+    assert_eq!(report.state, GameState::Running);
+    assert_eq!(report.moves, 3);
+}
+
+#[test]
+fn garbage_replies_are_counted() {
+    let mut game = Game::with_seed(MapConfig::new(12, 12), 3, 2, 7);
+    let mut process = stand_in(&["--garbage"]);
+    assert_eq!(process.ask(&game, Duration::from_secs(2)), BotReply::Invalid("sideways".into()));
+    let report = bot::run(&mut game, &mut process, Duration::from_secs(2), 2);
+    assert_eq!(report.invalid_replies, 2);
+}
+
+#[test]
+fn exited_bot_ends_the_run() {
+    let mut game = Game::with_seed(MapConfig::new(12, 12), 3, 2, 7);
+    let mut process = stand_in(&["--exit"]);
+    let report = bot::run(&mut game, &mut process, Duration::from_secs(2), 10);
+    assert!(report.exited);
+    assert_eq!(report.moves, 0);
+}
+
+#[test]
+fn late_reply_is_not_used_for_the_next_state() {
+    // 两个局面上贪心 bot 的选择不同
+    let first = Game::with_seed(MapConfig::new(12, 12), 3, 1, 1);
+    let second = Game::with_seed(MapConfig::new(12, 12), 3, 1, 3);
+    let mut reference = stand_in(&[]);
+    let (BotReply::Dir(a), BotReply::Dir(b)) =
+        (reference.ask(&first, Duration::from_secs(2)), reference.ask(&second, Duration::from_secs(2)))
+    else {
+        panic!("参考 bot 应正常回复");
+    };
+    assert_ne!(a, b);
+
+    // 第一问超时，它的回复在第二问发出之后才到达，必须被丢弃
+    let mut process = stand_in(&["--delay", "200"]);
+    assert_eq!(process.ask(&first, Duration::from_millis(20)), BotReply::Timeout);
+    assert_eq!(process.ask(&second, Duration::from_secs(2)), BotReply::Dir(b));
+}
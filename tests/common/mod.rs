@@ -0,0 +1,27 @@
+//! 属性测试共用的生成策略
+
+use proptest::prelude::*;
+use snake::types::Direction;
+
+/// (宽, 高, 初始长度, 食物数)，宽高各在 `1..=max_side` 中；
+/// 初始蛇水平放在中间一行，长度不能超过宽度
+pub fn setup(max_side: u32) -> impl Strategy<Value = (u32, u32, usize, usize)> {
+    (1..=max_side, 1..=max_side, any::<prop::sample::Index>(), any::<prop::sample::Index>()).prop_map(
+        |(w, h, len, food)| {
+            let len = len.index(w as usize) + 1;
+            let food = food.index((w * h) as usize - len + 1);
+            (w, h, len, food)
+        },
+    )
+}
+
+/// 方向输入；None 表示本 tick 没有按键
+pub fn input() -> impl Strategy<Value = Option<Direction>> {
+    prop_oneof![
+        Just(None),
+        Just(Some(Direction::Up)),
+        Just(Some(Direction::Down)),
+        Just(Some(Direction::Left)),
+        Just(Some(Direction::Right)),
+    ]
+}
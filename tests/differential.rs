@@ -0,0 +1,385 @@
+//! 差分测试：朴素参考模型与 `SnakeGame` 逐 tick 对拍
+//!
+//! 参考模型只用二维数组和线性扫描，规则逐条照搬玩法说明，不追求任何复杂度。
+//! 两边用同一种子的 RNG，食物生成时消耗完全相同的随机数；
+//! 第一次出现地图、蛇身、食物或状态不一致时，先缩减输入序列，再以最小化的逐 tick 轨迹报错。
+
+mod common;
+
+use common::{input, setup};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use rand::{Rng, SeedableRng};
+use snake::config::MapConfig;
+use snake::rng::GameRng;
+use snake::snake::SnakeGame;
+use snake::types::{CellState, Direction, GameState, Position};
+use std::collections::VecDeque;
+use std::fmt::Write;
+
+// ============================================================================
+// 参考模型
+// ============================================================================
+
+/// 朴素参考实现：`grid[y][x]`，蛇身从尾到头
+#[derive(Debug, Clone)]
+struct Reference {
+    width: u32,
+    height: u32,
+    grid: Vec<Vec<CellState>>,
+    body: VecDeque<(u32, u32)>,
+    direction: Direction,
+    /// 生成食物时的抽样顺序：开局按行优先排列，被占用的格子与末尾交换后移除，
+    /// 空出的蛇尾追加到末尾（与 `SnakeGame::empty_cells` 的公开约定一致）
+    empty: Vec<(u32, u32)>,
+    /// 故意引入的错误：允许蛇头进入当前蛇尾所在格（只用于验证对拍本身）
+    enter_tail: bool,
+}
+
+impl Reference {
+    fn new(width: u32, height: u32, length: usize, food: usize, rng: &mut GameRng) -> Self {
+        let mut r = Reference {
+            width,
+            height,
+            grid: vec![vec![CellState::Empty; width as usize]; height as usize],
+            body: VecDeque::new(),
+            direction: Direction::Right,
+            empty: (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).collect(),
+            enter_tail: false,
+        };
+        // 蛇水平放在中间一行，蛇头在中点（蛇太长时整体靠左）
+        let tail_x = (width / 2).saturating_sub(length as u32 - 1);
+        for i in 0..length as u32 {
+            r.occupy((tail_x + i, height / 2), CellState::Snake);
+            r.body.push_back((tail_x + i, height / 2));
+        }
+        for _ in 0..food {
+            r.spawn_food(rng);
+        }
+        r
+    }
+
+    /// 把一个空格标记为 `cell` 并从抽样顺序中移除
+    fn occupy(&mut self, (x, y): (u32, u32), cell: CellState) {
+        let i = self.empty.iter().position(|&p| p == (x, y)).expect("只能占用空格");
+        self.empty.swap_remove(i);
+        self.grid[y as usize][x as usize] = cell;
+    }
+
+    fn spawn_food(&mut self, rng: &mut GameRng) {
+        if !self.empty.is_empty() {
+            let p = self.empty[rng.random_range(0..self.empty.len())];
+            self.occupy(p, CellState::Food);
+        }
+    }
+
+    fn update(&mut self, input: Option<Direction>, rng: &mut GameRng) -> GameState {
+        // 掉头 180 度的输入被忽略
+        if let Some(dir) = input
+            && dir != self.direction.opposite()
+        {
+            self.direction = dir;
+        }
+        let (hx, hy) = *self.body.back().unwrap();
+        let (dx, dy) = self.direction.delta();
+        let (x, y) = (hx as i64 + dx as i64, hy as i64 + dy as i64);
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return GameState::Over;
+        }
+        let next = (x as u32, y as u32);
+        match self.grid[next.1 as usize][next.0 as usize] {
+            // 蛇尾在这一 tick 才离开，撞上它同样结束
+            CellState::Snake if !(self.enter_tail && next == self.body[0]) => GameState::Over,
+            CellState::Food => {
+                self.grid[next.1 as usize][next.0 as usize] = CellState::Snake;
+                self.body.push_back(next);
+                if self.body.len() == (self.width * self.height) as usize {
+                    return GameState::Won;
+                }
+                self.spawn_food(rng);
+                GameState::Running
+            }
+            _ => {
+                let (tx, ty) = self.body.pop_front().unwrap();
+                self.grid[ty as usize][tx as usize] = CellState::Empty;
+                self.empty.push((tx, ty));
+                self.occupy(next, CellState::Snake);
+                self.body.push_back(next);
+                GameState::Running
+            }
+        }
+    }
+
+    fn cell(&self, x: u32, y: u32) -> CellState {
+        self.grid[y as usize][x as usize]
+    }
+}
+
+// ============================================================================
+// 对拍
+// ============================================================================
+
+/// 一次对拍的全部输入
+#[derive(Debug, Clone, PartialEq)]
+struct Case {
+    width: u32,
+    height: u32,
+    length: usize,
+    food: usize,
+    seed: u64,
+    inputs: Vec<Option<Direction>>,
+}
+
+/// 第一次不一致
+#[derive(Debug, Clone)]
+struct Divergence {
+    /// 出现不一致的 tick（0 表示开局）
+    tick: usize,
+    /// 不一致的方面：map / body / food / state / rng
+    what: &'static str,
+    detail: String,
+    engine: String,
+    reference: String,
+}
+
+/// 比较两边的局面，返回第一处不一致的方面与说明
+fn compare(engine: &SnakeGame, reference: &Reference) -> Result<(), (&'static str, String)> {
+    let cfg = engine.config();
+    for hash in 0..cfg.total_size() {
+        let Position { x, y } = cfg.from_hash(hash);
+        let (a, b) = (engine.cell_state(hash), reference.cell(x, y));
+        if a != b {
+            return Err(("map", format!("({x},{y}): engine {a:?}, reference {b:?}")));
+        }
+    }
+    let body: Vec<(u32, u32)> = engine.snake_hashes().map(|&h| cfg.from_hash(h)).map(|p| (p.x, p.y)).collect();
+    if !body.iter().eq(&reference.body) {
+        return Err(("body", format!("engine {body:?}, reference {:?}", reference.body)));
+    }
+    // 食物列表的顺序是实现细节，只比较集合
+    let mut food: Vec<(u32, u32)> = engine.food_hashes().iter().map(|&h| cfg.from_hash(h)).map(|p| (p.x, p.y)).collect();
+    let mut expected: Vec<(u32, u32)> = (0..reference.height)
+        .flat_map(|y| (0..reference.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| reference.cell(x, y) == CellState::Food)
+        .collect();
+    food.sort_unstable();
+    expected.sort_unstable();
+    if food != expected {
+        return Err(("food", format!("engine {food:?}, reference {expected:?}")));
+    }
+    Ok(())
+}
+
+/// 两边同步运行 `case`；`enter_tail` 为参考模型注入错误
+fn lockstep(case: &Case, enter_tail: bool) -> Result<(), Divergence> {
+    let mut engine_rng = GameRng::seed_from_u64(case.seed);
+    let mut reference_rng = GameRng::seed_from_u64(case.seed);
+    let cfg = MapConfig::new(case.width, case.height);
+    let mut engine = SnakeGame::new(cfg, case.length, case.food, &mut engine_rng);
+    let mut reference = Reference::new(case.width, case.height, case.length, case.food, &mut reference_rng);
+    reference.enter_tail = enter_tail;
+
+    let diverge = |tick, what, detail, engine: &SnakeGame, reference: &Reference| Divergence {
+        tick,
+        what,
+        detail,
+        engine: render_engine(engine),
+        reference: render_reference(reference),
+    };
+    if let Err((what, detail)) = compare(&engine, &reference) {
+        return Err(diverge(0, what, detail, &engine, &reference));
+    }
+    for (i, &input) in case.inputs.iter().enumerate() {
+        let a = engine.update(input, &mut engine_rng);
+        let b = reference.update(input, &mut reference_rng);
+        if a != b {
+            return Err(diverge(i + 1, "state", format!("engine {a:?}, reference {b:?}"), &engine, &reference));
+        }
+        if let Err((what, detail)) = compare(&engine, &reference) {
+            return Err(diverge(i + 1, what, detail, &engine, &reference));
+        }
+        if matches!(a, GameState::Over | GameState::Won) {
+            break;
+        }
+        // 两边消耗的随机数个数也必须相同
+        if engine_rng != reference_rng {
+            return Err(diverge(i + 1, "rng", "消耗的随机数个数不同".into(), &engine, &reference));
+        }
+    }
+    Ok(())
+}
+
+/// 缩减输入：截断到第一次不一致，再逐个尝试删除输入，仍不一致就保留删除
+fn minimize(mut case: Case, enter_tail: bool) -> (Case, Divergence) {
+    let mut divergence = lockstep(&case, enter_tail).expect_err("只缩减不一致的用例");
+    case.inputs.truncate(divergence.tick);
+    let mut i = 0;
+    while i < case.inputs.len() {
+        let mut shorter = case.clone();
+        shorter.inputs.remove(i);
+        match lockstep(&shorter, enter_tail) {
+            Err(d) => {
+                shorter.inputs.truncate(d.tick);
+                case = shorter;
+                divergence = d;
+            }
+            Ok(()) => i += 1,
+        }
+    }
+    (case, divergence)
+}
+
+/// 最小化轨迹：开局参数、逐 tick 输入，以及不一致时两边的局面
+fn trace(case: &Case, d: &Divergence) -> String {
+    let mut out = String::new();
+    let _ = writeln!(
+        out,
+        "{}x{} length {} food {} seed {}",
+        case.width, case.height, case.length, case.food, case.seed
+    );
+    for (i, input) in case.inputs.iter().enumerate() {
+        let _ = writeln!(out, "tick {}: {input:?}", i + 1);
+    }
+    let _ = writeln!(out, "diverged at tick {} ({}): {}", d.tick, d.what, d.detail);
+    let _ = writeln!(out, "engine:\n{}reference:\n{}", d.engine, d.reference);
+    out
+}
+
+/// `#` 蛇身、`@` 蛇头、`*` 食物、`.` 空格
+fn render(width: u32, height: u32, head: Option<(u32, u32)>, cell: impl Fn(u32, u32) -> CellState) -> String {
+    let mut out = String::new();
+    for y in 0..height {
+        for x in 0..width {
+            out.push(match cell(x, y) {
+                _ if head == Some((x, y)) => '@',
+                CellState::Snake => '#',
+                CellState::Food => '*',
+                CellState::Empty => '.',
+            });
+        }
+        out.push('\n');
+    }
+    out
+}
+
+fn render_engine(game: &SnakeGame) -> String {
+    let cfg = game.config();
+    let head = game.head_position().map(|p| (p.x, p.y));
+    render(cfg.width, cfg.height, head, |x, y| game.cell_state(cfg.to_hash(Position { x, y })))
+}
+
+fn render_reference(r: &Reference) -> String {
+    render(r.width, r.height, r.body.back().copied(), |x, y| r.cell(x, y))
+}
+
+/// 对拍 `case`，不一致时以最小化轨迹报错
+fn check(case: Case) -> Result<(), String> {
+    match lockstep(&case, false) {
+        Ok(()) => Ok(()),
+        Err(_) => {
+            let (case, d) = minimize(case, false);
+            Err(trace(&case, &d))
+        }
+    }
+}
+
+// ============================================================================
+// 测试
+// ============================================================================
+
+/// 随机游走：在不会立即撞上的方向中随机选（都会撞上时随便选一个）
+fn safe_walk(case: &Case, ticks: usize) -> Vec<Option<Direction>> {
+    let dirs = [Direction::Up, Direction::Down, Direction::Left, Direction::Right];
+    let mut rng = GameRng::seed_from_u64(case.seed ^ 0x5eed);
+    let mut game_rng = GameRng::seed_from_u64(case.seed);
+    let mut game = Reference::new(case.width, case.height, case.length, case.food, &mut game_rng);
+    let mut inputs = Vec::with_capacity(ticks);
+    for _ in 0..ticks {
+        let (hx, hy) = *game.body.back().unwrap();
+        let safe: Vec<Direction> = dirs
+            .into_iter()
+            .filter(|&d| d != game.direction.opposite())
+            .filter(|&d| {
+                let (dx, dy) = d.delta();
+                let (x, y) = (hx as i64 + dx as i64, hy as i64 + dy as i64);
+                x >= 0
+                    && y >= 0
+                    && x < game.width as i64
+                    && y < game.height as i64
+                    && game.cell(x as u32, y as u32) != CellState::Snake
+            })
+            .collect();
+        let dir = if safe.is_empty() { dirs[rng.random_range(0..4)] } else { safe[rng.random_range(0..safe.len())] };
+        inputs.push(Some(dir));
+        if matches!(game.update(Some(dir), &mut game_rng), GameState::Over | GameState::Won) {
+            break;
+        }
+    }
+    inputs
+}
+
+#[test]
+fn long_random_walks_match() {
+    let mut wins = 0;
+    for seed in 0..60 {
+        let (width, height) = [(2, 2), (3, 2), (4, 4), (5, 3), (8, 8), (12, 6)][seed as usize % 6];
+        let mut case = Case {
+            width,
+            height,
+            length: 1 + seed as usize % width as usize,
+            food: 1 + seed as usize % 3,
+            seed,
+            inputs: Vec::new(),
+        };
+        case.food = case.food.min((width * height) as usize - case.length);
+        case.inputs = safe_walk(&case, 2000);
+        let mut rng = GameRng::seed_from_u64(seed);
+        let mut replay = Reference::new(width, height, case.length, case.food, &mut rng);
+        wins += case.inputs.iter().any(|&input| replay.update(input, &mut rng) == GameState::Won) as u32;
+        if let Err(trace) = check(case) {
+            panic!("引擎与参考模型不一致:\n{trace}");
+        }
+    }
+    // 小地图上随机游走也能填满，胜利分支同样被对拍
+    assert!(wins > 0);
+}
+
+#[test]
+fn injected_fault_is_minimized() {
+    // 参考模型允许蛇头进入蛇尾所在格：找到第一个暴露它的随机输入，缩减后只剩绕一圈撞尾的几步
+    let dirs = [Some(Direction::Up), Some(Direction::Down), Some(Direction::Left), Some(Direction::Right), None];
+    let mut rng = GameRng::seed_from_u64(1);
+    let case = (0..)
+        .map(|seed| Case {
+            width: 8,
+            height: 6,
+            length: 4,
+            food: 0,
+            seed,
+            inputs: (0..40).map(|_| dirs[rng.random_range(0..dirs.len())]).collect(),
+        })
+        .find(|case| lockstep(case, true).is_err())
+        .unwrap();
+    let (minimized, d) = minimize(case, true);
+    assert_eq!(d.what, "state");
+    assert_eq!(minimized.inputs.len(), 3, "{}", trace(&minimized, &d));
+    assert_eq!(d.tick, 3);
+    let trace = trace(&minimized, &d);
+    assert!(trace.contains("diverged at tick 3 (state): engine Over, reference Running"), "{trace}");
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn random_inputs_match(
+        (width, height, length, food) in setup(10),
+        seed in any::<u64>(),
+        inputs in vec(input(), 0..200),
+    ) {
+        let case = Case { width, height, length, food, seed, inputs };
+        if let Err(trace) = check(case) {
+            prop_assert!(false, "引擎与参考模型不一致:\n{}", trace);
+        }
+    }
+}
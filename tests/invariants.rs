@@ -0,0 +1,79 @@
+//! 引擎不变式的属性测试：随机地图尺寸、初始长度、食物数与方向序列，
+//! 每次 `update` / `revert` 之后都调用 `SnakeGame::check_invariants`
+
+mod common;
+
+use common::{input, setup};
+use proptest::collection::vec;
+use proptest::prelude::*;
+use rand::SeedableRng;
+use snake::config::MapConfig;
+use snake::rng::GameRng;
+use snake::snake::SnakeGame;
+use snake::types::{Direction, GameState};
+
+/// 可观察的完整状态（`SnakeGame` 未实现 `PartialEq`）
+fn observe(game: &SnakeGame) -> (Vec<usize>, Vec<usize>, Vec<usize>, Option<Direction>) {
+    (
+        game.snake_hashes().copied().collect(),
+        game.food_hashes().to_vec(),
+        game.empty_cells().to_vec(),
+        game.direction(),
+    )
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(256))]
+
+    #[test]
+    fn invariants_hold_after_every_update(
+        (w, h, len, food) in setup(12),
+        seed in any::<u64>(),
+        inputs in vec(input(), 0..300),
+    ) {
+        let total = (w * h) as usize;
+        let mut rng = GameRng::seed_from_u64(seed);
+        let mut game = SnakeGame::new(MapConfig::new(w, h), len, food, &mut rng);
+        game.check_invariants();
+        for dir in inputs {
+            let length = game.length();
+            let state = game.update(dir, &mut rng);
+            game.check_invariants();
+            prop_assert!(game.length() == length || game.length() == length + 1);
+            if matches!(state, GameState::Over | GameState::Won) {
+                prop_assert!(state == GameState::Over || game.length() == total);
+                break;
+            }
+            // 食物数维持在目标值，除非空格不够
+            prop_assert_eq!(game.food_count(), food.min(total - game.length()));
+        }
+    }
+
+    #[test]
+    fn revert_restores_exact_state(
+        (w, h, len, food) in setup(12),
+        seed in any::<u64>(),
+        inputs in vec(input(), 1..100),
+    ) {
+        let mut rng = GameRng::seed_from_u64(seed);
+        let mut game = SnakeGame::new(MapConfig::new(w, h), len, food, &mut rng);
+        let mut history = Vec::new();
+        let mut states = vec![observe(&game)];
+        for dir in inputs {
+            // 结束的 tick 同样要记录：胜利时吃掉了最后一份食物，撞上时也已改变方向
+            let (state, delta) = game.update_recorded(dir, &mut rng);
+            game.check_invariants();
+            history.push(delta);
+            states.push(observe(&game));
+            if matches!(state, GameState::Over | GameState::Won) {
+                break;
+            }
+        }
+        states.pop();
+        while let Some(delta) = history.pop() {
+            game.revert(&delta);
+            game.check_invariants();
+            prop_assert_eq!(&observe(&game), &states.pop().unwrap());
+        }
+    }
+}